@@ -1,12 +1,17 @@
+use std::fmt::{Display, Formatter};
 use std::iter::Sum;
 use std::ops::{Add, Sub};
 
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Cost {
     pub material_cost: u64,
     pub leftover_value: f32,
     pub part_area_excluded: u64,
     pub part_area_included: u64,
+    pub cut_length: u64,
 }
 
 
@@ -17,11 +22,12 @@ impl Cost {
             leftover_value: 0.0,
             part_area_excluded: 0,
             part_area_included: 0,
+            cut_length: 0,
         }
     }
 
-    pub fn new(material_cost: u64, leftover_value: f32, part_area_excluded: u64, part_area_included: u64) -> Self {
-        Self { material_cost, leftover_value, part_area_excluded, part_area_included }
+    pub fn new(material_cost: u64, leftover_value: f32, part_area_excluded: u64, part_area_included: u64, cut_length: u64) -> Self {
+        Self { material_cost, leftover_value, part_area_excluded, part_area_included, cut_length }
     }
 
     pub fn add(mut self, other: &Cost) -> Self {
@@ -29,6 +35,7 @@ impl Cost {
         self.leftover_value += other.leftover_value;
         self.part_area_excluded += other.part_area_excluded;
         self.part_area_included += other.part_area_included;
+        self.cut_length += other.cut_length;
         self
     }
 
@@ -37,6 +44,7 @@ impl Cost {
         self.leftover_value -= other.leftover_value;
         self.part_area_excluded -= other.part_area_excluded;
         self.part_area_included -= other.part_area_included;
+        self.cut_length -= other.cut_length;
         self
     }
 
@@ -53,6 +61,11 @@ impl Cost {
         self.material_cost += material_cost;
         self
     }
+
+    pub fn add_cut_length(mut self, cut_length: u64) -> Self {
+        self.cut_length += cut_length;
+        self
+    }
 }
 
 impl Add for Cost {
@@ -64,6 +77,7 @@ impl Add for Cost {
             leftover_value: self.leftover_value + rhs.leftover_value,
             part_area_excluded: self.part_area_excluded + rhs.part_area_excluded,
             part_area_included: self.part_area_included + rhs.part_area_included,
+            cut_length: self.cut_length + rhs.cut_length,
         }
     }
 }
@@ -77,12 +91,54 @@ impl Sub for Cost {
             leftover_value: self.leftover_value - rhs.leftover_value,
             part_area_excluded: self.part_area_excluded - rhs.part_area_excluded,
             part_area_included: self.part_area_included - rhs.part_area_included,
+            cut_length: self.cut_length - rhs.cut_length,
         }
     }
 }
 
 impl Sum for Cost {
     fn sum<I: Iterator<Item=Self>>(iter: I) -> Self {
-        iter.fold(Self::new(0, 0.0, 0, 0), |acc, cost| acc + cost)
+        iter.fold(Self::new(0, 0.0, 0, 0, 0), |acc, cost| acc + cost)
+    }
+}
+
+impl Serialize for Cost {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Cost", 6)?;
+        state.serialize_field("material_cost", &self.material_cost)?;
+        state.serialize_field("leftover_value", &self.leftover_value)?;
+        state.serialize_field("part_area_excluded", &self.part_area_excluded)?;
+        state.serialize_field("part_area_included", &self.part_area_included)?;
+        state.serialize_field("part_area_fraction_included", &self.part_area_fraction_included())?;
+        state.serialize_field("cut_length", &self.cut_length)?;
+        state.end()
+    }
+}
+
+impl Display for Cost {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mat: {}, p_excl: {}, p_incl: {:.3}%",
+               self.material_cost,
+               self.part_area_excluded,
+               self.part_area_fraction_included() * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::cost::Cost;
+
+    #[test]
+    fn cost_is_serialized_and_displayed_with_named_fields() {
+        let cost = Cost::new(200, 1.5, 25, 75, 40);
+        let json = serde_json::to_value(&cost).unwrap();
+        let keys = json.as_object().unwrap().keys().cloned().collect::<Vec<String>>();
+        assert_eq!(keys.len(), 6);
+        for key in ["material_cost", "leftover_value", "part_area_excluded", "part_area_included", "part_area_fraction_included", "cut_length"] {
+            assert!(keys.iter().any(|k| k == key), "missing key {}", key);
+        }
+        assert_eq!(json["material_cost"], 200);
+        assert_eq!(json["part_area_fraction_included"], 0.75);
+        assert_eq!(cost.to_string(), "mat: 200, p_excl: 25, p_incl: 75.000%");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,21 @@
+/// Rectangular region of a sheet which cannot be part of any item, e.g. a knot or a scratch.
+/// The position is that of its top left corner, relative to the top left corner of the sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Defect {
+    pub x: u64,
+    pub y: u64,
+    pub width: u64,
+    pub height: u64,
+}
+
+impl Defect {
+    /// Whether the defect overlaps the interior of the rectangle at (x, y) with dimensions (width, height)
+    pub fn overlaps(&self, x: u64, y: u64, width: u64, height: u64) -> bool {
+        self.x < x + width && x < self.x + self.width && self.y < y + height && y < self.y + self.height
+    }
+
+    /// Whether the defect covers the entire rectangle at (x, y) with dimensions (width, height)
+    pub fn covers(&self, x: u64, y: u64, width: u64, height: u64) -> bool {
+        self.x <= x && x + width <= self.x + self.width && self.y <= y && y + height <= self.y + self.height
+    }
+}
@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 use generational_arena::{Arena, Index};
 use itertools::Itertools;
 
 use crate::{Instance, Orientation};
 use crate::core::{cost::Cost, insertion::insertion_blueprint::InsertionBlueprint};
+use crate::core::defect::Defect;
 use crate::core::entities::node::Node;
+use crate::core::geometry;
 use crate::core::insertion::node_blueprint::NodeBlueprint;
 use crate::optimization::rr::cache_updates::IOCUpdates;
 use crate::util::assertions;
@@ -19,12 +23,16 @@ pub struct Layout<'a> {
     cached_cost: Option<Cost>,
     cached_usage: Option<f64>,
     sorted_empty_nodes: Vec<Index>, //sorted by descending area
+    part_counts: HashMap<usize, usize>, //number of copies of every parttype in the layout
 }
 
 impl<'a> Layout<'a> {
     pub fn new(id: usize, sheettype: &'a SheetType, first_cut_orientation: Orientation) -> Self {
         let mut nodes = Arena::new();
-        let top_node = Node::new(sheettype.width(), sheettype.height(), first_cut_orientation, None);
+        let top_node = match sheettype.defects().is_empty() {
+            true => Node::new(sheettype.width(), sheettype.height(), first_cut_orientation, None),
+            false => Node::new_fixed(sheettype.width(), sheettype.height(), first_cut_orientation)
+        };
         let top_node_i = nodes.insert(top_node);
 
         let mut layout = Self {
@@ -35,11 +43,126 @@ impl<'a> Layout<'a> {
             cached_cost: None,
             cached_usage: None,
             sorted_empty_nodes: vec![],
+            part_counts: HashMap::new(),
         };
 
-        //The top node cannot be modified, so we register a placeholder node to be able to insert parts
-        let placeholder_node = Node::new(sheettype.width(), sheettype.height(), first_cut_orientation.rotate(), None);
-        layout.register_node(placeholder_node, top_node_i, true);
+        match sheettype.defects().is_empty() {
+            true => {
+                //The top node cannot be modified, so we register a placeholder node to be able to insert parts
+                let placeholder_node = Node::new(sheettype.width(), sheettype.height(), first_cut_orientation.rotate(), None);
+                layout.register_node(placeholder_node, top_node_i, true);
+            }
+            false => {
+                layout.register_defect_structure(top_node_i, (0, 0, sheettype.width(), sheettype.height()), sheettype.defects());
+            }
+        }
+
+        layout
+    }
+
+    /*
+        The defects are cut out of the sheet by a fixed structure of nodes, which cannot be removed during the optimization.
+        Regions without defects get a fixed node with a placeholder, into which parts can be inserted like into an empty sheet.
+        Regions containing defects are split along the edges of the defects, by cuts which do not cross any defect.
+        Where the defects block every such cut, the region is cut along the edges of the defects through the other defects instead.
+        Regions covered entirely by a defect become a defect node.
+
+             ******************          ******************
+             *                *          *   *  *         *
+             *   XX           *    ->    *   *XX*         *
+             *                *          *   ****         *
+             ******************          ******************
+     */
+
+    /// Registers the fixed node covering the region (x, y, width, height) as a child of `parent`
+    fn register_defect_structure(&mut self, parent: Index, region: (u64, u64, u64, u64), defects: &[Defect]) {
+        let (x, y, width, height) = region;
+        let next_cut_orient = self.nodes[parent].next_cut_orient().rotate();
+        let defects = defects.iter().filter(|d| d.overlaps(x, y, width, height)).copied().collect_vec();
+
+        if defects.is_empty() {
+            let region_node_i = self.register_node(Node::new_fixed(width, height, next_cut_orient), parent, false);
+            let placeholder_node = Node::new(width, height, next_cut_orient.rotate(), None);
+            self.register_node(placeholder_node, region_node_i, true);
+            return;
+        }
+        if defects.iter().any(|d| d.covers(x, y, width, height)) {
+            self.register_node(Node::new_fixed(width, height, next_cut_orient), parent, false);
+            return;
+        }
+
+        //Positions of the edges of the defects along the direction in which the children of the node are placed,
+        //optionally only those at which no defect is crossed
+        let splits = |orientation: Orientation, crossing_allowed: bool| {
+            let (start, size) = match orientation {
+                Orientation::Vertical => (x, width),
+                Orientation::Horizontal => (y, height),
+            };
+            defects.iter()
+                .flat_map(|d| match orientation {
+                    Orientation::Vertical => [d.x, d.x + d.width],
+                    Orientation::Horizontal => [d.y, d.y + d.height],
+                })
+                .filter(|pos| *pos > start && *pos < start + size)
+                .filter(|pos| crossing_allowed || defects.iter().all(|d| match orientation {
+                    Orientation::Vertical => !(d.x < *pos && *pos < d.x + d.width),
+                    Orientation::Horizontal => !(d.y < *pos && *pos < d.y + d.height),
+                }))
+                .sorted()
+                .dedup()
+                .collect_vec()
+        };
+
+        let node_i = self.register_node(Node::new_fixed(width, height, next_cut_orient), parent, false);
+        let own_splits = match (splits(next_cut_orient, false), splits(next_cut_orient.rotate(), false).is_empty()) {
+            (own_splits, _) if !own_splits.is_empty() => own_splits,
+            //The region can only be split in the other direction, which is possible one level deeper
+            (_, false) => vec![],
+            //No cut separates the defects, so the region is cut through the defects instead of leaving it unused entirely.
+            //The pieces of the defects end up in smaller regions, until every region is either free or covered by a defect.
+            (_, true) => splits(next_cut_orient, true),
+        };
+        if own_splits.is_empty() {
+            self.register_defect_structure(node_i, region, &defects);
+            return;
+        }
+        let (start, end) = match next_cut_orient {
+            Orientation::Vertical => (x, x + width),
+            Orientation::Horizontal => (y, y + height),
+        };
+        let bounds = std::iter::once(start).chain(own_splits).chain(std::iter::once(end)).collect_vec();
+        for (from, to) in bounds.into_iter().tuple_windows() {
+            let child_region = match next_cut_orient {
+                Orientation::Vertical => (from, y, to - from, height),
+                Orientation::Horizontal => (x, from, width, to - from),
+            };
+            self.register_defect_structure(node_i, child_region, &defects);
+        }
+    }
+
+    /// Recreates a layout from the blueprint of its top node (e.g. from a SendableLayout)
+    pub fn from_top_node_blueprint(id: usize, sheettype: &'a SheetType, top_node: &NodeBlueprint, instance: &'a Instance) -> Self {
+        let mut nodes = Arena::new();
+        let top_node_i = match top_node.is_fixed() {
+            true => nodes.insert(Node::new_fixed(sheettype.width(), sheettype.height(), top_node.next_cut_orient())),
+            false => nodes.insert(Node::new(sheettype.width(), sheettype.height(), top_node.next_cut_orient(), None))
+        };
+
+        let mut layout = Self {
+            id,
+            sheettype,
+            nodes,
+            top_node_i,
+            cached_cost: None,
+            cached_usage: None,
+            sorted_empty_nodes: vec![],
+            part_counts: HashMap::new(),
+        };
+
+        for child in top_node.children() {
+            layout.implement_node_blueprint(top_node_i, child, instance, &mut vec![]);
+        }
+        debug_assert!(assertions::children_nodes_fit(&top_node_i, &layout.nodes));
 
         layout
     }
@@ -72,9 +195,13 @@ impl<'a> Layout<'a> {
     }
 
     fn implement_node_blueprint(&mut self, parent: Index, blueprint: &NodeBlueprint, instance: &'a Instance, new_nodes: &mut Vec<Index>) {
-        let parttype = blueprint.parttype_id().map(|id| instance.get_parttype(id));
+        let parttype = blueprint.parttype_id().zip(blueprint.rotation())
+            .map(|(id, rotation)| (instance.get_parttype(id), rotation));
 
-        let node = Node::new(blueprint.width(), blueprint.height(), blueprint.next_cut_orient(), parttype);
+        let node = match blueprint.is_fixed() {
+            true => Node::new_fixed(blueprint.width(), blueprint.height(), blueprint.next_cut_orient()),
+            false => Node::new(blueprint.width(), blueprint.height(), blueprint.next_cut_orient(), parttype)
+        };
         let node_index = self.register_node(node, parent, blueprint.is_empty());
 
         new_nodes.push(node_index);
@@ -84,7 +211,10 @@ impl<'a> Layout<'a> {
         }
     }
 
+    /// Removes the node (and everything in it) from the layout, returning the ids of the removed parts.
+    /// The top node can only be removed together with the entire layout, see `Problem::remove_node`.
     pub fn remove_node(&mut self, node_index: Index) -> Vec<usize>{
+        debug_assert!(node_index != self.top_node_i && !self.nodes[node_index].is_fixed());
         /*®
            Scenario 1: Empty node present + other child(ren)
             -> expand existing waste piece
@@ -128,8 +258,8 @@ impl<'a> Layout<'a> {
         match empty_node {
             Some(&empty_node_index) => {
                 //Scenario 1 and 3
-                if parent_node.children().len() > 1 || parent_node.parent().is_none() {
-                    //Scenario 1 (also do this when the parent node is the root)
+                if parent_node.children().len() > 1 || parent_node.parent().is_none() || parent_node.is_fixed() {
+                    //Scenario 1 (also do this when the parent node is the root or part of the fixed structure around defects)
                     //Two children are merged into one
 
                     let node = &self.nodes[node_index];
@@ -188,18 +318,22 @@ impl<'a> Layout<'a> {
     fn calculate_cost(&self) -> Cost {
         let material_cost = Cost::empty().add_material_cost(self.sheettype.value());
         self.nodes.iter()
-            .map(|(_, node)| node.calculate_cost())
+            .map(|(_, node)| {
+                let cut_length = geometry::cut_length(node.width(), node.height(), node.children().iter().map(|c| (self.nodes[*c].width(), self.nodes[*c].height())));
+                node.calculate_cost(self.sheettype).add_cut_length(cut_length)
+            })
             .fold(material_cost, |acc, cost| acc.add(&cost))
     }
 
     fn calculate_usage(&self) -> f64 {
         let used_area = self.nodes.iter().map(|(_, node)| {
             match node.parttype(){
-                Some(_) => node.area(),
+                Some(parttype) => parttype.area(),
                 None => 0
             }
         }).sum::<u64>();
 
+        //Both areas exclude the spacing by which parts and sheet are enlarged
         used_area as f64 / self.sheettype.area() as f64
     }
 
@@ -294,12 +428,26 @@ impl<'a> Layout<'a> {
         debug_assert!(assertions::node_arena_valid(&self.nodes, &self.top_node_i));
     }
 
-    fn register_part(&mut self, _parttype: &PartType) {
+    fn register_part(&mut self, parttype: &PartType) {
         self.invalidate_caches();
+        *self.part_counts.entry(parttype.id()).or_insert(0) += 1;
     }
 
-    fn unregister_part(&mut self, _parttype: &PartType) {
+    fn unregister_part(&mut self, parttype: &PartType) {
         self.invalidate_caches();
+        if let Some(count) = self.part_counts.get_mut(&parttype.id()) {
+            *count -= 1;
+        }
+    }
+
+    /// Number of copies of the parttype in the layout
+    pub fn part_count(&self, parttype_id: usize) -> usize {
+        self.part_counts.get(&parttype_id).copied().unwrap_or(0)
+    }
+
+    /// Whether another copy of the parttype can be inserted without exceeding its maximum per pattern
+    pub fn accepts_part(&self, parttype: &PartType) -> bool {
+        parttype.max_per_pattern().is_none_or(|max_per_pattern| self.part_count(parttype.id()) < max_per_pattern)
     }
 
     pub fn get_included_parts(&self) -> Vec<usize> {
@@ -351,6 +499,12 @@ impl<'a> Layout<'a> {
         usage
     }
 
+    /// Fraction of the area of the sheet which is covered by parts.
+    /// This is the authoritative definition of the usage of a layout, the usage of a solution is the area-weighted mean over its layouts.
+    pub fn utilization(&self) -> f64 {
+        self.usage_immut(false)
+    }
+
     pub fn usage_immut(&self, force_recalc: bool) -> f64 {
         let usage = match (self.cached_usage.as_ref(), force_recalc) {
             (Some(usage), false) => *usage,
@@ -371,13 +525,47 @@ impl<'a> Layout<'a> {
     }
 
     pub fn get_removable_nodes(&self) -> Vec<Index> {
-        //All nodes with children or that contain a part are removable
+        //All nodes with children or that contain a part are removable, except for the fixed structure around defects (which can only be removed entirely)
         self.nodes.iter()
             .filter(|(_, node)| node.parttype().is_some() || !node.children().is_empty())
+            .filter(|(index, node)| !node.is_fixed() || *index == self.top_node_i)
             .map(|(index, _)| index)
             .collect_vec()
     }
 
+    /// Position (x, y) of the top left corner of a node, relative to the top left corner of the sheet
+    pub fn node_position(&self, node_index: Index) -> (u64, u64) {
+        let (mut x, mut y) = (0, 0);
+        let mut current = node_index;
+        while let Some(parent_index) = *self.nodes[current].parent() {
+            let parent = &self.nodes[parent_index];
+            for sibling in parent.children().iter().take_while(|c| **c != current) {
+                match parent.next_cut_orient() {
+                    Orientation::Horizontal => y += self.nodes[*sibling].height(),
+                    Orientation::Vertical => x += self.nodes[*sibling].width(),
+                }
+            }
+            current = parent_index;
+        }
+        (x, y)
+    }
+
+    /// Number of guillotine stages required to cut out a node: the cuts separating it from its siblings, and those of its ancestors.
+    /// A node spanning its entire parent requires no cut, so the stages are not the number of flips of the cut orientation.
+    /// The spacing of a part is included in its node, cutting it off the part does not count as a stage.
+    pub fn node_stage(&self, node_index: Index) -> usize {
+        let mut stage = 0;
+        let mut current = &self.nodes[node_index];
+        while let Some(parent_index) = *current.parent() {
+            let parent = &self.nodes[parent_index];
+            if (parent.width(), parent.height()) != (current.width(), current.height()) {
+                stage += 1;
+            }
+            current = parent;
+        }
+        stage
+    }
+
     pub fn sheettype(&self) -> &'a SheetType {
         self.sheettype
     }
@@ -393,4 +581,135 @@ impl<'a> Layout<'a> {
     pub fn id(&self) -> usize {
         self.id
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use generational_arena::Index;
+    use serde_json::json;
+
+    use crate::core::defect::Defect;
+    use crate::core::entities::layout::Layout;
+    use crate::core::entities::sheettype::SheetType;
+    use crate::core::layout_index::LayoutIndex;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::Orientation;
+    use crate::util::test_util::solve;
+
+    /// Checks that no empty node overlaps a defect and returns their total area
+    fn free_area(defects: Vec<Defect>) -> u64 {
+        let sheettype = SheetType::new(0, 100, 100, 100, None, None, defects);
+        let layout = Layout::new(0, &sheettype, Orientation::Horizontal);
+        layout.sorted_empty_nodes().iter()
+            .map(|node_i| {
+                let node = &layout.nodes()[*node_i];
+                let (x, y) = layout.node_position(*node_i);
+                assert!(sheettype.defects().iter().all(|d| !d.overlaps(x, y, node.width(), node.height())));
+                node.area()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn only_defects_are_cut_out_of_the_sheet() {
+        assert_eq!(free_area(vec![Defect { x: 0, y: 0, width: 20, height: 20 }]), 9600);
+        assert_eq!(free_area(vec![Defect { x: 45, y: 45, width: 10, height: 10 }]), 9900);
+        //No cut separates the two defects without crossing the other one
+        let cross = vec![Defect { x: 0, y: 40, width: 100, height: 20 }, Defect { x: 40, y: 0, width: 20, height: 100 }];
+        assert_eq!(free_area(cross), 6400);
+    }
+
+    fn defect_instance(x: u64, y: u64) -> serde_json::Value {
+        json!({
+            "Name": "defects",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100,
+                "Defects": [{"X": x, "Y": y, "Length": 20, "Height": 20}]}],
+            "Items": [{"Length": 30, "Height": 20, "Demand": 12, "Value": 6}, {"Length": 10, "Height": 10, "Demand": 10, "Value": 1}]
+        })
+    }
+
+    fn assert_items_avoid_defects(x: u64, y: u64) {
+        let (_, instance, _, solution) = solve(defect_instance(x, y), json!({}));
+        assert_eq!(solution.parttype_qtys(), &vec![0, 0]);
+        let sheettype = instance.get_sheettype(0);
+        for (i, sendable_layout) in solution.layouts().iter().enumerate() {
+            let layout = sendable_layout.convert_to_layout(i, &instance);
+            for (node_i, node) in layout.nodes().iter().filter(|(_, node)| node.parttype().is_some()) {
+                let (x, y) = layout.node_position(node_i);
+                assert!(sheettype.defects().iter().all(|d| !d.overlaps(x, y, node.width(), node.height())));
+            }
+        }
+    }
+
+    #[test]
+    fn items_never_overlap_a_corner_defect() {
+        assert_items_avoid_defects(0, 0);
+    }
+
+    #[test]
+    fn items_never_overlap_a_centre_defect() {
+        assert_items_avoid_defects(40, 40);
+    }
+
+    #[test]
+    fn removing_the_fixed_top_node_removes_the_layout() {
+        let (_, instance, config, solution) = solve(defect_instance(40, 40), json!({}));
+        let mut problem = Problem::from_sendable(&instance, &config, &solution);
+        let (layout_i, layout) = problem.layouts().iter().next().unwrap();
+        let top_node_i = *layout.top_node_index();
+        assert!(layout.get_removable_nodes().contains(&top_node_i));
+
+        let removed = problem.remove_node(top_node_i, LayoutIndex::Existing(layout_i));
+        assert!(removed.is_some());
+        assert_eq!(problem.layouts().len(), 0);
+        assert_eq!(problem.parttype_qtys(), &vec![12, 10]);
+        assert_eq!(problem.sheettype_qtys(), &vec![1]);
+    }
+
+    /// Highest number of nested cuts, counted from the top node, through which an item is cut out of a layout of the solution.
+    /// Counted independently of `Layout::node_stage`: descending into a child only requires a cut if it is smaller than its parent
+    fn max_item_stage(instance: serde_json::Value, config: serde_json::Value) -> usize {
+        fn max_stage(layout: &Layout, node_i: Index, stage: usize) -> usize {
+            let node = &layout.nodes()[node_i];
+            let own = match node.parttype() {
+                Some(_) => stage,
+                None => 0,
+            };
+            node.children().iter()
+                .map(|child_i| {
+                    let child = &layout.nodes()[*child_i];
+                    let cut = (child.width(), child.height()) != (node.width(), node.height());
+                    max_stage(layout, *child_i, stage + cut as usize)
+                })
+                .fold(own, usize::max)
+        }
+        let (_, instance, _, solution) = solve(instance, config);
+        solution.layouts().iter().enumerate()
+            .map(|(i, sendable_layout)| {
+                let layout = sendable_layout.convert_to_layout(i, &instance);
+                max_stage(&layout, *layout.top_node_index(), 0)
+            })
+            .max()
+            .unwrap()
+    }
+
+    fn stages_instance() -> serde_json::Value {
+        json!({
+            "Name": "stages",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 10, "Cost": 100}],
+            "Items": [
+                {"Length": 45, "Height": 30, "Demand": 4, "Value": 1},
+                {"Length": 20, "Height": 25, "Demand": 5, "Value": 1},
+                {"Length": 60, "Height": 15, "Demand": 3, "Value": 1}
+            ]
+        })
+    }
+
+    #[test]
+    fn no_item_is_cut_out_beyond_the_maximum_stage() {
+        assert!(max_item_stage(stages_instance(), json!({})) > 2);
+        assert!(max_item_stage(stages_instance(), json!({"maxStages": 2})) <= 2);
+        //The spacing is part of the nodes of the items, its strips do not add stages
+        assert!(max_item_stage(stages_instance(), json!({"maxStages": 2, "kerf": 2, "partSpacing": 1})) <= 2);
+    }
+}
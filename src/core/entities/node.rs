@@ -1,7 +1,8 @@
 use generational_arena::{Index};
 
-use crate::{Orientation, PartType};
+use crate::{Orientation, PartType, SheetType};
 use crate::core::cost::Cost;
+use crate::core::geometry;
 use crate::core::insertion::node_blueprint::NodeBlueprint;
 use crate::core::leftover_valuator;
 use crate::core::rotation::Rotation;
@@ -13,19 +14,33 @@ pub struct Node<'a> {
     children: Vec<Index>,
     parent: Option<Index>,
     parttype: Option<&'a PartType>,
+    rotation: Option<Rotation>,
     next_cut_orient: Orientation,
+    fixed: bool,
 }
 
 
 impl<'a> Node<'a> {
-    pub fn new(width: u64, height: u64, next_cut_orient: Orientation, parttype: Option<&'a PartType>) -> Node<'a> {
+    /// A node containing a part also stores the rotation in which it is placed
+    pub fn new(width: u64, height: u64, next_cut_orient: Orientation, parttype: Option<(&'a PartType, Rotation)>) -> Node<'a> {
         Node {
             width,
             height,
             children: vec![],
             parent: None,
-            parttype,
+            parttype: parttype.map(|(parttype, _)| parttype),
+            rotation: parttype.map(|(_, rotation)| rotation),
             next_cut_orient,
+            fixed: false,
+        }
+    }
+
+    /// Node of the fixed structure around the defects of a sheet, which cannot be removed or replaced.
+    /// Without children it is a defect itself, otherwise its children are in their physical order.
+    pub fn new_fixed(width: u64, height: u64, next_cut_orient: Orientation) -> Node<'a> {
+        Node {
+            fixed: true,
+            ..Node::new(width, height, next_cut_orient, None)
         }
     }
 
@@ -76,7 +91,7 @@ impl<'a> Node<'a> {
 
         if self.next_cut_orient == Orientation::Horizontal && self.height == part_size.height() {
             let remainder_width = self.width - part_size.width();
-            let part_node = NodeBlueprint::new(part_size.width(), self.height, Some(parttype), self.next_cut_orient);
+            let part_node = NodeBlueprint::new(part_size.width(), self.height, Some((parttype, rotation)), self.next_cut_orient);
             let remainder_node = NodeBlueprint::new(remainder_width, self.height, None, self.next_cut_orient);
 
             insertion_replacements.push(vec![part_node, remainder_node]);
@@ -84,7 +99,7 @@ impl<'a> Node<'a> {
         }
         if self.next_cut_orient == Orientation::Vertical && self.width == part_size.width() {
             let remainder_height = self.height - part_size.height();
-            let part_node = NodeBlueprint::new(self.width, part_size.height(), Some(parttype), self.next_cut_orient);
+            let part_node = NodeBlueprint::new(self.width, part_size.height(), Some((parttype, rotation)), self.next_cut_orient);
             let remainder_node = NodeBlueprint::new(self.width, remainder_height, None, self.next_cut_orient);
 
             insertion_replacements.push(vec![part_node, remainder_node]);
@@ -105,7 +120,7 @@ impl<'a> Node<'a> {
 
             let remainder_height = self.height - part_size.height();
 
-            let part_node = NodeBlueprint::new(self.width, part_size.height(), Some(parttype), self.next_cut_orient.rotate());
+            let part_node = NodeBlueprint::new(self.width, part_size.height(), Some((parttype, rotation)), self.next_cut_orient.rotate());
             let remainder_node = NodeBlueprint::new(self.width, remainder_height, None, self.next_cut_orient.rotate());
 
             copy.add_child(part_node);
@@ -120,7 +135,7 @@ impl<'a> Node<'a> {
 
             let remainder_width = self.width - part_size.width();
 
-            let part_node = NodeBlueprint::new(part_size.width(), self.height, Some(parttype), self.next_cut_orient.rotate());
+            let part_node = NodeBlueprint::new(part_size.width(), self.height, Some((parttype, rotation)), self.next_cut_orient.rotate());
             let remainder_node = NodeBlueprint::new(remainder_width, self.height, None, self.next_cut_orient.rotate());
 
             copy.add_child(part_node);
@@ -150,7 +165,7 @@ impl<'a> Node<'a> {
             let remainder_node_top = NodeBlueprint::new(remainder_width_top, self.height, None, self.next_cut_orient);
 
             let remainder_height_bottom = self.height - part_size.height();
-            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some(parttype), self.next_cut_orient.rotate());
+            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some((parttype, rotation)), self.next_cut_orient.rotate());
             let remainder_node_bottom = NodeBlueprint::new(part_size.width(), remainder_height_bottom, None, self.next_cut_orient.rotate());
 
             part_node_parent.add_child(part_node);
@@ -165,7 +180,7 @@ impl<'a> Node<'a> {
             let remainder_node_top = NodeBlueprint::new(self.width, remainder_height_top, None, self.next_cut_orient);
 
             let remainder_width_bottom = self.width - part_size.width();
-            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some(parttype), self.next_cut_orient.rotate());
+            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some((parttype, rotation)), self.next_cut_orient.rotate());
             let remainder_node_bottom = NodeBlueprint::new(remainder_width_bottom, part_size.height(), None, self.next_cut_orient.rotate());
 
             part_node_parent.add_child(part_node);
@@ -192,7 +207,7 @@ impl<'a> Node<'a> {
             let remainder_node_top = NodeBlueprint::new(self.width, remainder_height_top, None, self.next_cut_orient.rotate());
 
             let remainder_width_bottom = self.width - part_size.width();
-            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some(parttype), self.next_cut_orient.rotate().rotate());
+            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some((parttype, rotation)), self.next_cut_orient.rotate().rotate());
             let remainder_node_bottom = NodeBlueprint::new(remainder_width_bottom, part_size.height(), None, self.next_cut_orient.rotate().rotate());
 
             part_node_parent.add_child(part_node);
@@ -213,7 +228,7 @@ impl<'a> Node<'a> {
 
             let remainder_height_bottom = self.height - part_size.height();
 
-            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some(parttype), self.next_cut_orient.rotate().rotate());
+            let part_node = NodeBlueprint::new(part_size.width(), part_size.height(), Some((parttype, rotation)), self.next_cut_orient.rotate().rotate());
             let remainder_node_bottom = NodeBlueprint::new(part_size.width(), remainder_height_bottom, None, self.next_cut_orient.rotate().rotate());
 
             part_node_parent.add_child(part_node);
@@ -236,20 +251,29 @@ impl<'a> Node<'a> {
             Rotation::Rotated => parttype.rotated_size()
         };
 
-        self.width >= part_size.width() && self.height >= part_size.height()
+        geometry::fits(part_size.width(), part_size.height(), self.width, self.height)
     }
 
-    pub fn calculate_cost(&self) -> Cost {
+    /// Only leftover nodes on a sheet of the `sheettype` are valued, by their actual size (see `SheetType::leftover_size`)
+    pub fn calculate_cost(&self, sheettype: &SheetType) -> Cost {
         match (self.parttype, self.children.is_empty()) {
             (Some(_), true) => Cost::empty(), // part-node
             (None, false) => Cost::empty(), // structure-node
-            (None, true) => Cost::empty().add_leftover_value(leftover_valuator::valuate(self.area())), //leftover node
+            (None, true) if self.fixed => Cost::empty(), // defect-node
+            (None, true) => match sheettype.leftover_size(self.width, self.height) { //leftover node
+                Some((width, height)) => Cost::empty().add_leftover_value(leftover_valuator::valuate(width, height)),
+                None => Cost::empty()
+            },
             (Some(_), false) => panic!("Parttype set on node with children"),
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.parttype.is_none() && self.children.is_empty()
+        self.parttype.is_none() && self.children.is_empty() && !self.fixed
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.fixed
     }
 
     pub fn width(&self) -> u64 {
@@ -261,11 +285,15 @@ impl<'a> Node<'a> {
     pub fn parttype(&self) -> &Option<&PartType> {
         &self.parttype
     }
+    /// Rotation in which the part of the node is placed, None if the node does not contain a part
+    pub fn rotation(&self) -> Option<Rotation> {
+        self.rotation
+    }
     pub fn next_cut_orient(&self) -> Orientation {
         self.next_cut_orient
     }
     pub fn area(&self) -> u64 {
-        self.width * self.height
+        geometry::area(self.width, self.height)
     }
     pub fn children(&self) -> &Vec<Index> {
         &self.children
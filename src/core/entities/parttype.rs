@@ -1,7 +1,9 @@
 use std::hash::{Hash, Hasher};
 
+use crate::core::geometry;
+use crate::core::grain::Grain;
 use crate::core::size::Size;
-use crate::Rotation;
+use crate::{Rotation, SheetType};
 
 #[derive(Debug)]
 pub struct PartType {
@@ -11,20 +13,53 @@ pub struct PartType {
     fixed_rotation: Option<Rotation>,
     size: Size,
     rotated_size: Size,
+    original: usize,
+    n_pieces: usize,
+    max_per_pattern: Option<usize>,
+    grain: Option<Grain>,
+    spacing: u64,
 }
 
 impl PartType {
-    pub fn new(id: usize, width: u64, height: u64, fixed_rotation: Option<Rotation>) -> PartType {
+    pub fn new(id: usize, width: u64, height: u64, fixed_rotation: Option<Rotation>, max_per_pattern: Option<usize>, grain: Option<Grain>) -> PartType {
         PartType {
             id,
             width,
             height,
             fixed_rotation,
             size: Size::new(width, height),
-            rotated_size: Size::new(height, width),
+            rotated_size: {
+                let (rotated_width, rotated_height) = geometry::rotate(width, height);
+                Size::new(rotated_width, rotated_height)
+            },
+            original: id,
+            n_pieces: 1,
+            max_per_pattern,
+            grain,
+            spacing: 0,
         }
     }
 
+    /// Makes the part one of the `n_pieces` pieces into which the (splittable) parttype `original` is split
+    pub fn as_piece_of(mut self, original: usize, n_pieces: usize) -> PartType {
+        debug_assert!(n_pieces >= 2);
+        self.original = original;
+        self.n_pieces = n_pieces;
+        self
+    }
+
+    /// Enlarges the part by `spacing` in both dimensions, reserving the clearance (and kerf) to its neighbours on its right and bottom side.
+    /// The dimensions used for placement include the spacing, the area remains that of the actual part.
+    pub fn with_spacing(mut self, spacing: u64) -> PartType {
+        self.width += spacing;
+        self.height += spacing;
+        self.size = Size::new(self.width, self.height);
+        let (rotated_width, rotated_height) = geometry::rotate(self.width, self.height);
+        self.rotated_size = Size::new(rotated_width, rotated_height);
+        self.spacing = spacing;
+        self
+    }
+
     pub fn id(&self) -> usize {
         self.id
     }
@@ -49,8 +84,69 @@ impl PartType {
         &self.rotated_size
     }
 
+    /// Area of the actual part, excluding the spacing
     pub fn area(&self) -> u64 {
-        self.size.area()
+        geometry::area(self.width - self.spacing, self.height - self.spacing)
+    }
+
+    /// Spacing by which the dimensions of the part are enlarged, see `with_spacing`
+    pub fn spacing(&self) -> u64 {
+        self.spacing
+    }
+
+    /// Whether the part fits in a rectangle of the given dimensions, in any of its allowed rotations
+    pub fn fits(&self, width: u64, height: u64) -> bool {
+        match self.fixed_rotation {
+            Some(Rotation::Default) => geometry::fits(self.size.width(), self.size.height(), width, height),
+            Some(Rotation::Rotated) => geometry::fits(self.rotated_size.width(), self.rotated_size.height(), width, height),
+            None => geometry::fits_rotatable(self.width, self.height, width, height),
+        }
+    }
+
+    /// Whether the part can be placed in its default and rotated orientation on a sheet of the sheettype.
+    /// Combines the fixed rotation of the part with the alignment of its grain to the grain of the sheet:
+    /// if both are grained, only the aligned orientation is allowed, regardless of whether rotation is allowed.
+    pub fn allowed_rotations(&self, sheettype: &SheetType) -> (bool, bool) {
+        let (default_allowed, rotated_allowed) = match self.fixed_rotation {
+            Some(Rotation::Default) => (true, false),
+            Some(Rotation::Rotated) => (false, true),
+            None => (true, true),
+        };
+        match (self.grain, sheettype.grain()) {
+            (Some(grain), Some(sheet_grain)) => match grain.aligning_rotation(sheet_grain) {
+                Rotation::Default => (default_allowed, false),
+                Rotation::Rotated => (false, rotated_allowed),
+            },
+            _ => (default_allowed, rotated_allowed)
+        }
+    }
+
+    /// Whether the part fits on an empty sheet of the sheettype, in any of its allowed rotations on that sheet.
+    /// Parts and sheets are enlarged by the same spacing, so this is equivalent to comparing their actual dimensions.
+    pub fn fits_sheet(&self, sheettype: &SheetType) -> bool {
+        let (default_allowed, rotated_allowed) = self.allowed_rotations(sheettype);
+        (default_allowed && geometry::fits(self.size.width(), self.size.height(), sheettype.width(), sheettype.height())) ||
+            (rotated_allowed && geometry::fits(self.rotated_size.width(), self.rotated_size.height(), sheettype.width(), sheettype.height()))
+    }
+
+    pub fn grain(&self) -> Option<Grain> {
+        self.grain
+    }
+
+    /// Number of pieces which together make up one unit of the original (splittable) part.
+    /// Is 1 for regular parts and for splittable parts which are produced as a whole.
+    pub fn n_pieces(&self) -> usize {
+        self.n_pieces
+    }
+
+    /// Id of the parttype of which this part is a piece, its own id if it is not a piece
+    pub fn original(&self) -> usize {
+        self.original
+    }
+
+    /// Maximum number of copies of the part which are allowed in a single layout
+    pub fn max_per_pattern(&self) -> Option<usize> {
+        self.max_per_pattern
     }
 }
 
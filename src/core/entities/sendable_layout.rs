@@ -1,6 +1,7 @@
 use crate::core::cost::Cost;
 use crate::core::entities::layout::Layout;
 use crate::core::insertion::node_blueprint::NodeBlueprint;
+use crate::optimization::config::HashConfig;
 use crate::Instance;
 
 ///Representation of a layout that can be sent across threads
@@ -10,7 +11,7 @@ pub struct SendableLayout {
     sheettype_id: usize,
     top_node: NodeBlueprint,
     cost: Cost,
-    usage: f64,
+    utilization: f64,
 }
 
 impl SendableLayout {
@@ -19,12 +20,13 @@ impl SendableLayout {
             sheettype_id: layout.sheettype().id(),
             top_node: NodeBlueprint::from_node(*layout.top_node_index(), layout.nodes()),
             cost: layout.cost_immut(false),
-            usage: layout.usage_immut(false),
+            utilization: layout.utilization(),
         }
     }
 
-    pub fn convert_to_layout(&self, _instance: &Instance) -> Layout {
-        todo!();
+    pub fn convert_to_layout<'a>(&self, id: usize, instance: &'a Instance) -> Layout<'a> {
+        let sheettype = instance.get_sheettype(self.sheettype_id);
+        Layout::from_top_node_blueprint(id, sheettype, &self.top_node, instance)
     }
 
     pub fn sheettype_id(&self) -> usize {
@@ -36,7 +38,134 @@ impl SendableLayout {
     pub fn cost(&self) -> &Cost {
         &self.cost
     }
-    pub fn usage(&self) -> f64 {
-        self.usage
+    /// See `Layout::utilization`
+    pub fn utilization(&self) -> f64 {
+        self.utilization
     }
-}
\ No newline at end of file
+
+    /// Usage of the layout in which up to `scrap_allowance` area of waste is considered to be used
+    pub fn effective_usage(&self, sheet_area: u64, scrap_allowance: u64) -> f64 {
+        let waste = (1.0 - self.utilization) * sheet_area as f64;
+        f64::min(self.utilization + f64::min(waste, scrap_allowance as f64) / sheet_area as f64, 1.0)
+    }
+
+    /// Dimensions of the part of the sheet, from its top left corner, which is covered by parts.
+    /// The rest of the sheet could be trimmed off and returned to stock.
+    pub fn used_bounding_box(&self) -> (u64, u64) {
+        self.top_node.used_bounding_box()
+    }
+
+    /// Representation of the cutting pattern which is identical for layouts which are mirror images of each other
+    pub fn canonical_form(&self, hash_config: &HashConfig) -> String {
+        let canonical_structure = [(false, false), (true, false), (false, true), (true, true)].iter()
+            .map(|(mirror_x, mirror_y)| self.top_node.structure_string(*mirror_x, *mirror_y, hash_config))
+            .min()
+            .unwrap();
+        format!("S{}:{}", self.sheettype_id, canonical_structure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::core::entities::layout::Layout;
+    use crate::core::entities::sendable_layout::SendableLayout;
+    use crate::core::insertion::node_blueprint::NodeBlueprint;
+    use crate::core::leftover_valuator;
+    use crate::core::rotation::Rotation;
+    use crate::optimization::config::HashConfig;
+    use crate::Orientation;
+    use crate::util::test_util::parse;
+
+    #[test]
+    fn used_bounding_box_covers_only_the_parts() {
+        let (_, instance, config) = parse(json!({
+            "Name": "bounding_box",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 1}]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        //The part in the lower left quadrant, which is exported as the upper left one
+        let mut strip = NodeBlueprint::new(100, 50, None, Orientation::Vertical);
+        strip.add_child(NodeBlueprint::new(50, 50, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Horizontal));
+        strip.add_child(NodeBlueprint::new(50, 50, None, Orientation::Horizontal));
+        let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+        top_node.add_child(NodeBlueprint::new(100, 50, None, Orientation::Vertical));
+        top_node.add_child(strip);
+
+        let layout = Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance);
+        assert_eq!(SendableLayout::new(&layout).used_bounding_box(), (50, 50));
+    }
+
+    #[test]
+    fn waste_within_the_scrap_allowance_counts_as_used() {
+        let (_, instance, config) = parse(json!({
+            "Name": "scrap_allowance",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 100, "Height": 98, "Demand": 1, "Value": 1}]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+        top_node.add_child(NodeBlueprint::new(100, 98, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Vertical));
+        top_node.add_child(NodeBlueprint::new(100, 2, None, Orientation::Vertical));
+
+        let sendable_layout = SendableLayout::new(&Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance));
+        assert_eq!(sendable_layout.utilization(), 0.98);
+        assert_eq!(sendable_layout.effective_usage(10000, 0), 0.98);
+        assert_eq!(sendable_layout.effective_usage(10000, 300), 1.0);
+        assert!((sendable_layout.effective_usage(10000, 100) - 0.99).abs() < 1e-9);
+    }
+
+    #[test]
+    fn leftover_subdivision_is_only_hashed_when_leftovers_are_included() {
+        let (_, instance, config) = parse(json!({
+            "Name": "hash_config",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 2, "Cost": 100}],
+            "Items": [{"Length": 100, "Height": 50, "Demand": 2, "Value": 1}]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        //A part on top of a leftover, which is either left whole or cut in two
+        let sendable_layout = |subdivided: bool| {
+            let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+            top_node.add_child(NodeBlueprint::new(100, 50, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Vertical));
+            let mut leftover = NodeBlueprint::new(100, 50, None, Orientation::Vertical);
+            if subdivided {
+                leftover.add_child(NodeBlueprint::new(50, 50, None, Orientation::Horizontal));
+                leftover.add_child(NodeBlueprint::new(50, 50, None, Orientation::Horizontal));
+            }
+            top_node.add_child(leftover);
+            SendableLayout::new(&Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance))
+        };
+        let (whole, subdivided) = (sendable_layout(false), sendable_layout(true));
+
+        let with_leftovers = HashConfig::default();
+        assert_ne!(whole.canonical_form(&with_leftovers), subdivided.canonical_form(&with_leftovers));
+        let without_leftovers = HashConfig { leftovers: false, ..HashConfig::default() };
+        assert_eq!(whole.canonical_form(&without_leftovers), subdivided.canonical_form(&without_leftovers));
+    }
+
+    #[test]
+    fn utilization_is_the_placed_part_area_relative_to_the_sheet_area() {
+        let (_, instance, config) = parse(json!({
+            "Name": "utilization",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [
+                {"Length": 100, "Height": 50, "Demand": 1, "Value": 1},
+                {"Length": 25, "Height": 50, "Demand": 1, "Value": 1}
+            ]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let mut strip = NodeBlueprint::new(100, 50, None, Orientation::Vertical);
+        strip.add_child(NodeBlueprint::new(25, 50, Some((instance.get_parttype(1), Rotation::Default)), Orientation::Horizontal));
+        strip.add_child(NodeBlueprint::new(75, 50, None, Orientation::Horizontal));
+        let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+        top_node.add_child(NodeBlueprint::new(100, 50, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Vertical));
+        top_node.add_child(strip);
+
+        let layout = Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance);
+        let placed_part_area = (100 * 50 + 25 * 50) as f64;
+        assert_eq!(layout.utilization(), placed_part_area / instance.get_sheettype(0).area() as f64);
+        assert_eq!(SendableLayout::new(&layout).utilization(), layout.utilization());
+    }
+}
@@ -1,6 +1,9 @@
 use std::hash::{Hash, Hasher};
 
 use crate::Orientation;
+use crate::core::defect::Defect;
+use crate::core::geometry;
+use crate::core::grain::Grain;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct SheetType {
@@ -9,19 +12,39 @@ pub struct SheetType {
     height: u64,
     value: u64,
     fixed_first_cut_orientation: Option<Orientation>,
+    grain: Option<Grain>,
+    defects: Vec<Defect>,
+    spacing: u64,
+    kerf: u64,
 }
 
 impl SheetType {
-    pub fn new(id: usize, width: u64, height: u64, value: u64, fixed_first_cut_orientation: Option<Orientation>) -> SheetType {
+    pub fn new(id: usize, width: u64, height: u64, value: u64, fixed_first_cut_orientation: Option<Orientation>, grain: Option<Grain>, defects: Vec<Defect>) -> SheetType {
         SheetType {
             id,
             width,
             height,
             value,
             fixed_first_cut_orientation,
+            grain,
+            defects,
+            spacing: 0,
+            kerf: 0,
         }
     }
 
+    /// Enlarges the sheet by `spacing` in both dimensions, matching parts enlarged by the same spacing (see `PartType::with_spacing`).
+    /// No clearance is required between the parts and the edges of the sheet, the area remains that of the actual sheet.
+    /// The `kerf` (included in the spacing) is the minimum size of a leftover, see `leftover_size`.
+    pub fn with_spacing(mut self, spacing: u64, kerf: u64) -> SheetType {
+        debug_assert!(kerf <= spacing);
+        self.width += spacing;
+        self.height += spacing;
+        self.spacing = spacing;
+        self.kerf = kerf;
+        self
+    }
+
     pub fn id(&self) -> usize {
         self.id
     }
@@ -38,13 +61,40 @@ impl SheetType {
         self.value
     }
 
+    /// Area of the actual sheet, excluding the spacing
     pub fn area(&self) -> u64 {
-        self.width * self.height
+        geometry::area(self.width - self.spacing, self.height - self.spacing)
+    }
+
+    /// Spacing by which the dimensions of the sheet are enlarged, see `with_spacing`
+    pub fn spacing(&self) -> u64 {
+        self.spacing
+    }
+
+    /// Actual dimensions of a leftover node of the given dimensions on this sheettype.
+    /// Like the parts, every node includes the spacing to its neighbours on its right and bottom side, which is not part of the leftover.
+    /// A cut which leaves less than the kerf of material (in either dimension) produces no leftover.
+    pub fn leftover_size(&self, width: u64, height: u64) -> Option<(u64, u64)> {
+        let (width, height) = (width.saturating_sub(self.spacing), height.saturating_sub(self.spacing));
+        let min_size = u64::max(self.kerf, 1);
+        match width >= min_size && height >= min_size {
+            true => Some((width, height)),
+            false => None
+        }
     }
 
     pub fn fixed_first_cut_orientation(&self) -> Option<Orientation> {
         self.fixed_first_cut_orientation
     }
+
+    pub fn grain(&self) -> Option<Grain> {
+        self.grain
+    }
+
+    /// Regions of the sheet which cannot be covered by items
+    pub fn defects(&self) -> &[Defect] {
+        &self.defects
+    }
 }
 
 impl Hash for SheetType {
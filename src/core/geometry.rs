@@ -0,0 +1,97 @@
+//! Pure rectangle computations (area, fit checks, rotation).
+//! Only depends on `core` and `alloc`, no collections or threading, so it can be reused independently of the optimizer
+//! (`tests/geometry_no_std.rs` compiles it in a `no_std` crate).
+
+use alloc::vec::Vec;
+
+/// Area of a rectangle
+pub fn area(width: u64, height: u64) -> u64 {
+    width * height
+}
+
+/// Dimensions of a rectangle after a 90° rotation
+pub fn rotate(width: u64, height: u64) -> (u64, u64) {
+    (height, width)
+}
+
+/// Whether a rectangle fits in a container rectangle, without rotating it
+pub fn fits(width: u64, height: u64, container_width: u64, container_height: u64) -> bool {
+    width <= container_width && height <= container_height
+}
+
+/// Whether a rectangle fits in a container rectangle, either as is or rotated by 90°
+pub fn fits_rotatable(width: u64, height: u64, container_width: u64, container_height: u64) -> bool {
+    let (rotated_width, rotated_height) = rotate(width, height);
+    fits(width, height, container_width, container_height) || fits(rotated_width, rotated_height, container_width, container_height)
+}
+
+/// Batched version of `fits`: whether a rectangle fits in each of the containers, given by their widths and heights.
+/// Operates on plain slices without branches, so the comparisons can be vectorized by the compiler.
+/// The result is identical to calling `fits` for every container.
+pub fn fits_batch(width: u64, height: u64, container_widths: &[u64], container_heights: &[u64], fits: &mut Vec<bool>) {
+    debug_assert!(container_widths.len() == container_heights.len());
+    fits.clear();
+    fits.extend(container_widths.iter().zip(container_heights.iter())
+        .map(|(container_width, container_height)| (width <= *container_width) & (height <= *container_height)));
+}
+
+/// Total length of the guillotine cuts which separate the children of a rectangle from each other.
+/// The children lie side by side: either all spanning the full height of the rectangle (separated by vertical cuts) or its full width.
+/// Children without area require no cut.
+pub fn cut_length(width: u64, height: u64, children: impl Iterator<Item=(u64, u64)>) -> u64 {
+    let mut n_children = 0;
+    let mut side_by_side = false;
+    for (child_width, child_height) in children.filter(|(child_width, child_height)| area(*child_width, *child_height) > 0) {
+        let child_side_by_side = child_height == height && child_width < width;
+        if n_children == 0 {
+            side_by_side = child_side_by_side;
+        }
+        debug_assert!(child_side_by_side == side_by_side, "children of a {}x{} rectangle lie side by side in both orientations", width, height);
+        n_children += 1;
+    }
+    match (n_children, side_by_side) {
+        (0, _) => 0,
+        (n, true) => (n - 1) * height,
+        (n, false) => (n - 1) * width,
+    }
+}
+
+/// Fraction of the container's area covered by the given area
+pub fn area_fraction(covered_area: u64, container_width: u64, container_height: u64) -> f64 {
+    covered_area as f64 / area(container_width, container_height) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::SmallRng;
+
+    //Relative, so the module also compiles on its own (see `tests/geometry_no_std.rs`)
+    use super::{cut_length, fits, fits_batch};
+
+    #[test]
+    fn batched_fit_equals_scalar_fit() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let container_widths = (0..500).map(|_| rng.gen_range(0..50)).collect::<Vec<u64>>();
+        let container_heights = (0..500).map(|_| rng.gen_range(0..50)).collect::<Vec<u64>>();
+        let mut batched_fits = Vec::new();
+        for _ in 0..500 {
+            let (width, height) = (rng.gen_range(0..50), rng.gen_range(0..50));
+            fits_batch(width, height, &container_widths, &container_heights, &mut batched_fits);
+            let scalar_fits = container_widths.iter().zip(container_heights.iter())
+                .map(|(container_width, container_height)| fits(width, height, *container_width, *container_height))
+                .collect::<Vec<bool>>();
+            assert_eq!(batched_fits, scalar_fits);
+        }
+    }
+
+    #[test]
+    fn cut_length_counts_the_cuts_between_side_by_side_and_stacked_children() {
+        assert_eq!(cut_length(10, 5, [(3, 5), (7, 5)].into_iter()), 5);
+        assert_eq!(cut_length(10, 5, [(10, 2), (10, 2), (10, 1)].into_iter()), 20);
+        assert_eq!(cut_length(10, 5, [(10, 5), (0, 5)].into_iter()), 0);
+        assert_eq!(cut_length(10, 5, core::iter::empty()), 0);
+    }
+}
@@ -0,0 +1,20 @@
+use crate::Rotation;
+
+/// Direction of the grain of a material, relative to its own dimensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Grain {
+    /// Grain runs along the width (the length in the JSON formats)
+    Width,
+    /// Grain runs along the height
+    Height,
+}
+
+impl Grain {
+    /// The rotation of a part with this grain which aligns it with the grain of the sheet
+    pub fn aligning_rotation(&self, sheet_grain: Grain) -> Rotation {
+        match *self == sheet_grain {
+            true => Rotation::Default,
+            false => Rotation::Rotated
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use std::fmt::Debug;
 
 use generational_arena::{Index};
-use itertools::Itertools;
 
 use crate::{PartType, Rotation};
 use crate::core::cost::Cost;
+use crate::core::geometry;
 use crate::core::insertion::insertion_blueprint::InsertionBlueprint;
+use crate::core::insertion::node_blueprint::NodeBlueprint;
 use crate::core::layout_index::LayoutIndex;
 use crate::optimization::problem::Problem;
 
@@ -33,6 +34,13 @@ impl<'a> InsertionOption<'a> {
     }
 
     pub fn generate_blueprints(&self, problem: &Problem) -> Vec<InsertionBlueprint<'a>> {
+        let mut blueprints = Vec::new();
+        self.append_blueprints(problem, &mut blueprints);
+        blueprints
+    }
+
+    /// Same as `generate_blueprints`, but appends the blueprints to an existing vector
+    pub fn append_blueprints(&self, problem: &Problem, blueprints: &mut Vec<InsertionBlueprint<'a>>) {
         let layout = problem.get_layout(&self.layout_i);
         let original_node = &layout.nodes()[self.original_node_i];
         let node_blueprints = match self.rotation {
@@ -44,14 +52,29 @@ impl<'a> InsertionOption<'a> {
                 original_node.generate_insertion_node_blueprints(self.parttype, Rotation::Rotated, node_blueprints)
             }
         };
-        let original_cost = original_node.calculate_cost();
+        let original_cost = original_node.calculate_cost(layout.sheettype());
+
+        //With a stage limit, the replacements which require too many stages to cut out their part are discarded
+        let within_stage_limit = |nbs: &Vec<NodeBlueprint>| match problem.max_stages() {
+            None => true,
+            Some(max_stages) => {
+                let parent_i = original_node.parent().expect("original node should have a parent");
+                let parent = &layout.nodes()[parent_i];
+                let parent_stage = layout.node_stage(parent_i);
+                nbs.iter()
+                    .filter_map(|nb| nb.max_part_stage((parent.width(), parent.height()), parent_stage))
+                    .all(|stage| stage <= max_stages)
+            }
+        };
 
         //Convert the node blueprints into insertion blueprints
-        node_blueprints.into_iter().map(|nbs| {
-            let new_cost = nbs.iter().map(|replacement| replacement.calculate_cost()).sum::<Cost>();
+        blueprints.extend(node_blueprints.into_iter().filter(within_stage_limit).map(|nbs| {
+            //Replacing the node by multiple nodes on the same level requires cuts between them
+            let sibling_cut_length = geometry::cut_length(original_node.width(), original_node.height(), nbs.iter().map(|r| (r.width(), r.height())));
+            let new_cost = nbs.iter().map(|replacement| replacement.calculate_cost(layout.sheettype())).sum::<Cost>().add_cut_length(sibling_cut_length);
             let insertion_cost = new_cost.subtract(&original_cost);
             InsertionBlueprint::new(self.layout_i, self.original_node_i, nbs, self.parttype, insertion_cost)
-        }).collect_vec()
+        }));
     }
 
     pub fn parttype(&self) -> &'a PartType {
@@ -69,4 +92,52 @@ impl<'a> InsertionOption<'a> {
     pub fn layout_index(&self) -> &LayoutIndex {
         &self.layout_i
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::core::cost::Cost;
+    use crate::core::layout_index::LayoutIndex;
+    use crate::core::leftover_valuator;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::rr::insertion_option_cache::InsertionOptionCache;
+    use crate::util::test_util::parse;
+
+    /// Costs of the blueprints inserting a part of the given height into an empty 100x100 sheet
+    fn blueprint_costs(part_height: u64) -> Vec<Cost> {
+        let (_, instance, config) = parse(json!({
+            "Name": "blueprint_cost",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 100, "Height": part_height, "Demand": 1, "Value": 1}]
+        }), json!({"rotationAllowed": false}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let problem = Problem::new(&instance, &config);
+        let empty_layout = &problem.empty_layouts()[0];
+        let node_i = empty_layout.sorted_empty_nodes()[0];
+        let option = InsertionOptionCache::generate_insertion_option(&empty_layout.nodes()[node_i], instance.get_parttype(0),
+            LayoutIndex::Empty(0), node_i, empty_layout.sheettype()).unwrap();
+        option.generate_blueprints(&problem).iter().map(|blueprint| blueprint.cost().clone()).collect()
+    }
+
+    #[test]
+    fn part_exactly_filling_a_node_consumes_the_leftover() {
+        let costs = blueprint_costs(100);
+        assert!(!costs.is_empty());
+        for cost in costs {
+            //The full leftover is lost, no new leftover or cut is created
+            assert_eq!(cost, Cost::new(0, -leftover_valuator::valuate(100, 100), 0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn part_leaving_a_sliver_creates_a_leftover_and_a_cut() {
+        let costs = blueprint_costs(98);
+        assert!(!costs.is_empty());
+        for cost in costs {
+            let expected_leftover_value = leftover_valuator::valuate(100, 2) - leftover_valuator::valuate(100, 100);
+            assert!((cost.leftover_value - expected_leftover_value).abs() < 1.0);
+            assert_eq!((cost.material_cost, cost.part_area_excluded, cost.part_area_included, cost.cut_length), (0, 0, 0, 100));
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use generational_arena::{Arena, Index};
 use itertools::Itertools;
 
-use crate::{Orientation, PartType};
+use crate::{Orientation, PartType, SheetType};
 use crate::core::cost::Cost;
 use crate::core::entities::node::Node;
+use crate::core::geometry;
 use crate::core::leftover_valuator;
+use crate::core::rotation::Rotation;
+use crate::optimization::config::HashConfig;
 
 /// Represents a node in an InsertionBlueprint
 
@@ -14,17 +17,21 @@ pub struct NodeBlueprint {
     height: u64,
     children: Vec<NodeBlueprint>,
     parttype_id: Option<usize>,
+    rotation: Option<Rotation>,
     next_cut_orient: Orientation,
+    fixed: bool,
 }
 
 impl NodeBlueprint {
-    pub fn new(width: u64, height: u64, parttype: Option<&PartType>, next_cut_orient: Orientation) -> Self {
+    /// A node containing a part also stores the rotation in which it is placed
+    pub fn new(width: u64, height: u64, parttype: Option<(&PartType, Rotation)>, next_cut_orient: Orientation) -> Self {
         let children = Vec::new();
         let parttype_id = match parttype {
-            Some(parttype) => Some(parttype.id()),
+            Some((parttype, _)) => Some(parttype.id()),
             None => None,
         };
-        Self { width, height, children, parttype_id, next_cut_orient }
+        let rotation = parttype.map(|(_, rotation)| rotation);
+        Self { width, height, children, parttype_id, rotation, next_cut_orient, fixed: false }
     }
 
     pub fn from_node(node_index: Index, nodes: &Arena<Node>) -> Self {
@@ -40,24 +47,31 @@ impl NodeBlueprint {
             .map(|child_index| NodeBlueprint::from_node(*child_index, nodes))
             .collect_vec();
 
-        Self { width, height, parttype_id, children, next_cut_orient }
+        Self { width, height, parttype_id, rotation: node.rotation(), children, next_cut_orient, fixed: node.is_fixed() }
     }
 
+    /// Children without area (e.g. a remainder reduced to nothing) are degenerate and pruned
     pub fn add_child(&mut self, child: NodeBlueprint) {
-        self.children.push(child);
+        if child.area() > 0 {
+            self.children.push(child);
+        }
     }
 
-    pub fn calculate_cost(&self) -> Cost {
-        if self.parttype_id.is_some() {
-            return Cost::new(0, 0.0, 0, 0);
+    /// See `Node::calculate_cost`
+    pub fn calculate_cost(&self, sheettype: &SheetType) -> Cost {
+        if self.parttype_id.is_some() || self.is_defect() {
+            Cost::new(0, 0.0, 0, 0, 0)
         } else if self.children.is_empty() {
-            return Cost::new(0, leftover_valuator::valuate(self.area()), 0, 0);
+            let leftover_value = sheettype.leftover_size(self.width, self.height)
+                .map_or(0.0, |(width, height)| leftover_valuator::valuate(width, height));
+            Cost::new(0, leftover_value, 0, 0, 0)
         } else {
-            let mut cost = Cost::new(0, 0.0, 0, 0);
+            let cut_length = geometry::cut_length(self.width, self.height, self.children.iter().map(|c| (c.width, c.height)));
+            let mut cost = Cost::new(0, 0.0, 0, 0, cut_length);
             for child in &self.children {
-                cost = cost + child.calculate_cost();
+                cost = cost + child.calculate_cost(sheettype);
             }
-            return cost;
+            cost
         }
     }
 
@@ -78,7 +92,16 @@ impl NodeBlueprint {
     }
 
     pub fn is_empty(&self) -> bool {
-        self.parttype_id.is_none() && self.children.is_empty()
+        self.parttype_id.is_none() && self.children.is_empty() && !self.fixed
+    }
+
+    /// See `Node::new_fixed`
+    pub fn is_fixed(&self) -> bool {
+        self.fixed
+    }
+
+    pub fn is_defect(&self) -> bool {
+        self.fixed && self.children.is_empty()
     }
 
     pub fn area(&self) -> u64 {
@@ -101,8 +124,149 @@ impl NodeBlueprint {
         self.parttype_id
     }
 
+    /// Rotation in which the part of the node is placed, None if the node does not contain a part
+    pub fn rotation(&self) -> Option<Rotation> {
+        self.rotation
+    }
+
+    /// Ids of the parttypes of all parts in the node and its descendants, once per part
+    pub fn parttype_ids(&self, ids: &mut Vec<usize>) {
+        ids.extend(self.parttype_id);
+        self.children.iter().for_each(|child| child.parttype_ids(ids));
+    }
+
     pub fn next_cut_orient(&self) -> Orientation {
         self.next_cut_orient
     }
 
+    /// Highest guillotine stage (see `Layout::node_stage`) at which a part is cut out of this node, if it contains any parts.
+    /// `parent_size` and `parent_stage` are those of the node this blueprint is inserted into.
+    pub fn max_part_stage(&self, parent_size: (u64, u64), parent_stage: usize) -> Option<usize> {
+        let stage = parent_stage + ((self.width, self.height) != parent_size) as usize;
+        match self.parttype_id {
+            Some(_) => Some(stage),
+            None => self.children.iter()
+                .filter_map(|child| child.max_part_stage((self.width, self.height), stage))
+                .max()
+        }
+    }
+
+    /// The children in the order in which they are exported, highest usage first.
+    /// The fixed structure around defects keeps its physical order.
+    pub fn sorted_children(&self) -> Vec<&NodeBlueprint> {
+        if self.children.iter().any(|child| child.fixed) {
+            return self.children.iter().collect_vec();
+        }
+        self.children.iter()
+            .sorted_by(|a, b| a.calculate_usage().partial_cmp(&b.calculate_usage()).unwrap().reverse())
+            .collect_vec()
+    }
+
+    /// Dimensions of the smallest rectangle, anchored at the top left corner of the node, containing all of its parts.
+    /// Children are positioned in their export order.
+    pub fn used_bounding_box(&self) -> (u64, u64) {
+        match (self.parttype_id, self.children.is_empty()) {
+            (Some(_), _) => (self.width, self.height),
+            (None, true) => (0, 0),
+            (None, false) => {
+                let (mut width, mut height) = (0, 0);
+                let mut offset = 0;
+                for child in self.sorted_children() {
+                    let (child_width, child_height) = child.used_bounding_box();
+                    match self.next_cut_orient {
+                        Orientation::Horizontal => {
+                            if child_height > 0 {
+                                height = offset + child_height;
+                            }
+                            width = u64::max(width, child_width);
+                            offset += child.height;
+                        }
+                        Orientation::Vertical => {
+                            if child_width > 0 {
+                                width = offset + child_width;
+                            }
+                            height = u64::max(height, child_height);
+                            offset += child.width;
+                        }
+                    }
+                }
+                (width, height)
+            }
+        }
+    }
+
+    /// Appends the position (x, y) and actual dimensions (width, height, see `SheetType::leftover_size`) of all leftover nodes
+    /// among the node and its descendants, given the position of the node's top left corner. Children are positioned in their export order.
+    pub fn leftovers(&self, x: u64, y: u64, sheettype: &SheetType, leftovers: &mut Vec<(u64, u64, u64, u64)>) {
+        match (self.parttype_id, self.children.is_empty()) {
+            (Some(_), _) => {}
+            (None, true) if self.fixed => {}
+            (None, true) => if let Some((width, height)) = sheettype.leftover_size(self.width, self.height) {
+                leftovers.push((x, y, width, height))
+            },
+            (None, false) => {
+                let mut offset = 0;
+                for child in self.sorted_children() {
+                    match self.next_cut_orient {
+                        Orientation::Horizontal => {
+                            child.leftovers(x, y + offset, sheettype, leftovers);
+                            offset += child.height;
+                        }
+                        Orientation::Vertical => {
+                            child.leftovers(x + offset, y, sheettype, leftovers);
+                            offset += child.width;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Textual representation of the physical structure of the node and its descendants.
+    /// `mirror_x` mirrors the structure left to right, `mirror_y` top to bottom.
+    /// Nodes without area do not influence the physical pattern and are omitted, the others are in their export order.
+    /// The `hash_config` defines which attributes of the nodes are part of the representation.
+    pub fn structure_string(&self, mirror_x: bool, mirror_y: bool, hash_config: &HashConfig) -> String {
+        let dimensions = match hash_config.dimensions {
+            true => {
+                let precision = hash_config.dimension_precision.max(1);
+                let round = |dimension: u64| (dimension + precision / 2) / precision;
+                format!("[{}x{}]", round(self.width), round(self.height))
+            }
+            false => String::new()
+        };
+        match (self.parttype_id, self.children.is_empty()) {
+            (Some(parttype_id), _) => match hash_config.part_ids {
+                true => format!("P{}{}", parttype_id, dimensions),
+                false => format!("P{}", dimensions),
+            },
+            (None, true) if self.fixed => format!("D{}", dimensions),
+            (None, true) => format!("L{}", dimensions),
+            (None, false) => {
+                //Vertical cuts place the children next to each other along the x axis, horizontal cuts along the y axis
+                let reverse = match self.next_cut_orient {
+                    Orientation::Vertical => mirror_x,
+                    Orientation::Horizontal => mirror_y,
+                };
+                let mut children = self.sorted_children().into_iter()
+                    .filter(|child| child.area() > 0)
+                    .filter(|child| hash_config.leftovers || child.contains_parts())
+                    .map(|child| child.structure_string(mirror_x, mirror_y, hash_config))
+                    .collect_vec();
+                if reverse {
+                    children.reverse();
+                }
+                let orientation = match (hash_config.orientation, self.next_cut_orient) {
+                    (false, _) => "S",
+                    (true, Orientation::Horizontal) => "H",
+                    (true, Orientation::Vertical) => "V",
+                };
+                format!("{}({})", orientation, children.join(","))
+            }
+        }
+    }
+
+    fn contains_parts(&self) -> bool {
+        self.parttype_id.is_some() || self.children.iter().any(|child| child.contains_parts())
+    }
 }
@@ -2,6 +2,7 @@ use std::cell::RefCell;
 
 thread_local! {
     static VALUATION_POWER : RefCell<Option<f32>> = RefCell::new(None);
+    static MAX_ASPECT_RATIO : RefCell<Option<f64>> = const { RefCell::new(None) };
 }
 
 pub fn set_power(power: f32) {
@@ -10,9 +11,39 @@ pub fn set_power(power: f32) {
     })
 }
 
-pub fn valuate(area: u64) -> f32 {
+/// Leftovers more elongated than this ratio (longest side / shortest side) are considered waste
+pub fn set_max_aspect_ratio(max_aspect_ratio: Option<f64>) {
+    MAX_ASPECT_RATIO.with(|r| {
+        *r.borrow_mut() = max_aspect_ratio;
+    })
+}
+
+pub fn valuate(width: u64, height: u64) -> f32 {
+    if let Some(max_aspect_ratio) = MAX_ASPECT_RATIO.with(|r| *r.borrow()) {
+        let aspect_ratio = u64::max(width, height) as f64 / u64::min(width, height) as f64;
+        if aspect_ratio > max_aspect_ratio {
+            return 0.0;
+        }
+    }
     VALUATION_POWER.with(|p| {
         let power = p.borrow().expect("valuation power not set for this thread!");
-        f32::powf(area as f32, power)
+        f32::powf((width * height) as f32, power)
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::core::leftover_valuator::{set_max_aspect_ratio, set_power, valuate};
+
+    #[test]
+    fn elongated_leftovers_are_waste_under_an_aspect_ratio_cap() {
+        set_power(2.0);
+        assert_eq!(valuate(1, 100), valuate(10, 10));
+
+        set_max_aspect_ratio(Some(10.0));
+        assert_eq!(valuate(1, 100), 0.0);
+        assert_eq!(valuate(100, 1), 0.0);
+        assert_eq!(valuate(10, 10), 10000.0);
+        //At the cap itself, a leftover is still valued
+        assert!(valuate(1, 10) > 0.0);
+    }
+}
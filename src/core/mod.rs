@@ -1,8 +1,11 @@
 pub mod cost;
 pub mod size;
+pub mod geometry;
 pub mod insertion;
 pub mod orientation;
 pub mod entities;
 pub mod rotation;
+pub mod grain;
+pub mod defect;
 pub mod leftover_valuator;
 pub mod layout_index;
@@ -11,4 +11,14 @@ impl Rotation {
             Rotation::Rotated => Rotation::Default
         }
     }
-}
\ No newline at end of file
+
+    /// Converts a counterclockwise rotation in degrees to the corresponding Rotation.
+    /// A 180° turn does not change the footprint of a rectangular part, so 0°/180° and 90°/270° are equivalent.
+    pub fn from_degrees(degrees: u16) -> Option<Rotation> {
+        match degrees {
+            0 | 180 => Some(Rotation::Default),
+            90 | 270 => Some(Rotation::Rotated),
+            _ => None
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::core::geometry;
+
 #[derive(Debug, Clone)]
 pub struct Size {
     width: u64,
@@ -10,7 +12,7 @@ impl Size {
         Size {
             width,
             height,
-            area: width * height,
+            area: geometry::area(width, height),
         }
     }
 
@@ -0,0 +1,87 @@
+use crate::io::json_format::{JsonCPNode, JsonCPNodeType, JsonOrientation, JsonSolution};
+use crate::optimization::config::CoordinateOrigin;
+
+const HEADER: &str = "pattern,object,item,x,y,length,height,rotated";
+
+/// One row per item placed in the cutting patterns of a solution, for spreadsheet based workflows.
+/// The position (x, y) is that of the corner of the item closest to the coordinate origin, the dimensions are those of the placed item.
+/// An item is rotated when it is placed with its height along the length of the object (see `JsonCPNode::rotated`).
+pub fn generate_solution_csv(json_solution: &JsonSolution, origin: CoordinateOrigin) -> String {
+    let mut csv = String::from(HEADER);
+    csv.push('\n');
+    for (pattern, json_cp) in json_solution.cutting_patterns.iter().enumerate() {
+        let mut rows = Vec::new();
+        collect_items(&json_cp.root, (0, 0), &mut rows);
+        for (x, y, node) in rows {
+            let item = node.item.expect("item node without item");
+            let y = origin.transform_y(y, node.height, json_cp.root.height);
+            csv.push_str(&format!("{},{},{},{},{},{},{},{}\n",
+                                  pattern, json_cp.object, item, x, y, node.length, node.height,
+                                  node.rotated.unwrap_or(false)));
+        }
+    }
+    csv
+}
+
+/// Item nodes among the node and its descendants, with the position of their top left corner
+fn collect_items<'a>(node: &'a JsonCPNode, reference: (u64, u64), rows: &mut Vec<(u64, u64, &'a JsonCPNode)>) {
+    if let JsonCPNodeType::Item = node.node_type {
+        rows.push((reference.0, reference.1, node));
+    }
+    let mut reference = reference;
+    for child in &node.children {
+        collect_items(child, reference, rows);
+        match node.orientation {
+            Some(JsonOrientation::H) => reference.1 += child.height,
+            Some(JsonOrientation::V) => reference.0 += child.length,
+            None => panic!("Node with children should have orientation")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::io::csv_export::{generate_solution_csv, HEADER};
+    use crate::io::parser::generate_json_solution;
+    use crate::optimization::config::CoordinateOrigin;
+    use crate::util::test_util::{config_path, solve};
+
+    #[test]
+    fn one_row_per_included_part_inside_the_sheet() {
+        //The second item only fits rotated
+        let instance = json!({
+            "Name": "csv",
+            "Objects": [{"Length": 100, "Height": 30, "Stock": 2, "Cost": 100}],
+            "Items": [
+                {"Length": 40, "Height": 30, "Demand": 1, "Value": 1},
+                {"Length": 30, "Height": 60, "Demand": 1, "Value": 1}
+            ]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert!(json_solution.statistics.excluded_parts.is_empty());
+
+        for origin in [CoordinateOrigin::TopLeft, CoordinateOrigin::BottomLeft] {
+            let csv = generate_solution_csv(&json_solution, origin);
+            let mut lines = csv.lines();
+            assert_eq!(lines.next(), Some(HEADER));
+            let rows = lines.map(|line| line.split(',').map(str::to_string).collect::<Vec<_>>()).collect::<Vec<_>>();
+            let n_placed = json_solution.statistics.placed_per_part.iter().map(|p| p.placed).sum::<usize>();
+            assert_eq!(rows.len(), n_placed);
+            for row in &rows {
+                let object = &json_solution.sheettypes[row[1].parse::<usize>().unwrap()];
+                let [x, y, length, height] = [3, 4, 5, 6].map(|i| row[i].parse::<u64>().unwrap());
+                assert!(x + length <= object.length && y + height <= object.height);
+                let (item, rotated) = (row[2].parse::<usize>().unwrap(), row[7].parse::<bool>().unwrap());
+                assert_eq!(rotated, item == 1);
+                let json_part = &json_solution.parttypes[item];
+                match rotated {
+                    true => assert_eq!((length, height), (json_part.height, json_part.length)),
+                    false => assert_eq!((length, height), (json_part.length, json_part.height)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::io::json_format::{JsonCP, JsonCPNode, JsonCutInstruction, JsonOrientation, JsonPartType};
+use crate::optimization::config::CoordinateOrigin;
+
+/// Generates the guillotine cuts required to produce a cutting pattern, grouped by stage.
+/// All first stage cuts (separating the children of the root node) are listed first, then all second stage cuts, etc.
+/// Within a stage, the cuts are listed in the order of the cutting pattern tree.
+pub fn generate_cut_plan(json_cp: &JsonCP, origin: CoordinateOrigin) -> Vec<JsonCutInstruction> {
+    let mut cuts = Vec::new();
+    generate_node_cuts(&json_cp.root, (0, 0), 1, &mut cuts);
+
+    //stable sort, preserves the tree order within a stage
+    cuts.sort_by_key(|cut| cut.stage);
+
+    let sheet_height = json_cp.root.height;
+    for cut in cuts.iter_mut() {
+        cut.y = match cut.orientation {
+            JsonOrientation::H => origin.transform_y(cut.y, 0, sheet_height),
+            JsonOrientation::V => origin.transform_y(cut.y, cut.length, sheet_height),
+        };
+    }
+    cuts
+}
+
+/// Recommended order in which to cut the patterns (indices in `cutting_patterns`), for just-in-time delivery of urgent items.
+/// Patterns are ordered by the earliest due date of the items they produce, patterns without items with a due date come last.
+/// Ties preserve the order of the cutting patterns.
+pub fn generate_cutting_sequence(cutting_patterns: &[JsonCP], parttypes: &[JsonPartType]) -> Vec<usize> {
+    let mut sequence = (0..cutting_patterns.len()).collect::<Vec<usize>>();
+    //stable sort, None (no due date) is mapped to the end
+    sequence.sort_by_key(|i| earliest_due_date(&cutting_patterns[*i].root, parttypes).unwrap_or(u64::MAX));
+    sequence
+}
+
+fn earliest_due_date(node: &JsonCPNode, parttypes: &[JsonPartType]) -> Option<u64> {
+    let own_due_date = node.item.and_then(|item| parttypes[item].due_date);
+    node.children.iter()
+        .map(|child| earliest_due_date(child, parttypes))
+        .fold(own_due_date, |a, b| match (a, b) {
+            (Some(a), Some(b)) => Some(u64::min(a, b)),
+            (a, b) => a.or(b)
+        })
+}
+
+/// Adds the cuts separating the children of the node, and recursively those of its descendants.
+/// Coordinates are relative to the top left corner of the sheet.
+fn generate_node_cuts(node: &JsonCPNode, reference: (u64, u64), stage: usize, cuts: &mut Vec<JsonCutInstruction>) {
+    let (node_end_x, node_end_y) = (reference.0 + node.length, reference.1 + node.height);
+    //A node with a single child spanning the entire node requires no cut, its child's cuts remain in the same stage
+    let child_stage = match node.children.iter().filter(|c| c.length * c.height > 0).count() > 1 {
+        true => stage + 1,
+        false => stage
+    };
+    let mut reference = reference;
+    for child in &node.children {
+        generate_node_cuts(child, reference, child_stage, cuts);
+        match node.orientation {
+            Some(JsonOrientation::H) => {
+                reference.1 += child.height;
+                //No cut is required at the edge of the node
+                if reference.1 < node_end_y {
+                    cuts.push(JsonCutInstruction { stage, orientation: JsonOrientation::H, x: reference.0, y: reference.1, length: node.length });
+                }
+            }
+            Some(JsonOrientation::V) => {
+                reference.0 += child.length;
+                if reference.0 < node_end_x {
+                    cuts.push(JsonCutInstruction { stage, orientation: JsonOrientation::V, x: reference.0, y: reference.1, length: node.height });
+                }
+            }
+            None => panic!("Node with children should have orientation")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::io::cut_plan::{generate_cut_plan, generate_cutting_sequence};
+    use crate::io::json_format::{JsonCP, JsonOrientation, JsonPartType};
+    use crate::optimization::config::CoordinateOrigin;
+
+    /// A strip of 100x30 on top of a 40x70 item next to a 60x70 leftover
+    fn json_cp() -> JsonCP {
+        let node = |length: u64, height: u64, node_type: &str, item: Option<usize>| json!({
+            "Length": length, "Height": height, "Type": node_type, "Item": item, "Children": []
+        });
+        serde_json::from_value(json!({
+            "Object": 0,
+            "Usage": 0.58,
+            "UsedBoundingBox": {"Length": 100, "Height": 100},
+            "Root": {"Length": 100, "Height": 100, "Orientation": "H", "Type": "Structure", "Children": [
+                node(100, 30, "Item", Some(0)),
+                {"Length": 100, "Height": 70, "Orientation": "V", "Type": "Structure", "Children": [
+                    node(40, 70, "Item", Some(1)),
+                    node(60, 70, "Leftover", None)
+                ]}
+            ]}
+        })).unwrap()
+    }
+
+    fn cuts(origin: CoordinateOrigin) -> Vec<(usize, bool, u64, u64, u64)> {
+        generate_cut_plan(&json_cp(), origin).iter()
+            .map(|cut| (cut.stage, cut.orientation == JsonOrientation::H, cut.x, cut.y, cut.length))
+            .collect()
+    }
+
+    #[test]
+    fn bottom_left_origin_flips_the_y_coordinates() {
+        assert_eq!(cuts(CoordinateOrigin::TopLeft), vec![(1, true, 0, 30, 100), (2, false, 40, 30, 70)]);
+        //The vertical cut now starts at the bottom edge and runs upwards
+        assert_eq!(cuts(CoordinateOrigin::BottomLeft), vec![(1, true, 0, 70, 100), (2, false, 40, 0, 70)]);
+
+        //The items, at the top and the bottom of the sheet
+        assert_eq!(CoordinateOrigin::TopLeft.transform_y(0, 30, 100), 0);
+        assert_eq!(CoordinateOrigin::BottomLeft.transform_y(0, 30, 100), 70);
+        assert_eq!(CoordinateOrigin::BottomLeft.transform_y(30, 70, 100), 0);
+    }
+
+    #[test]
+    fn patterns_with_more_urgent_items_are_cut_first() {
+        let parttypes: Vec<JsonPartType> = [Some(30), None, Some(10)].iter()
+            .map(|due_date| serde_json::from_value(json!({"Length": 40, "Height": 70, "Demand": 1, "Value": 1, "DueDate": due_date})).unwrap())
+            .collect();
+        //The first item of the example pattern is replaced, the second one (without due date) is produced by all patterns
+        let pattern = |item: usize| {
+            let mut json_cp = json_cp();
+            json_cp.root.children[0].item = Some(item);
+            json_cp
+        };
+        let cutting_patterns = vec![pattern(0), pattern(1), pattern(2), pattern(0)];
+        assert_eq!(generate_cutting_sequence(&cutting_patterns, &parttypes), vec![2, 0, 3, 1]);
+    }
+}
@@ -6,8 +6,9 @@ use svg::node::element::{Group, Rectangle};
 use svg::node::Text;
 
 use crate::io::json_format::{JsonCP, JsonCPNode, JsonCPNodeType, JsonOrientation, JsonSolution};
+use crate::optimization::config::CoordinateOrigin;
 
-pub fn generate_solution(json_solution: &JsonSolution) -> String {
+pub fn generate_solution(json_solution: &JsonSolution, origin: CoordinateOrigin) -> String {
     let html = format!("{}", html! {
         : doctype::HTML;
         html(style="font-family:Arial") {
@@ -59,7 +60,10 @@ pub fn generate_solution(json_solution: &JsonSolution) -> String {
                             : "Run time";
                         }
                         td {
-                            : format!{"{}s", json_solution.statistics.run_time_ms as f64 / 1000.0};
+                            : match json_solution.statistics.run_time_ms {
+                                Some(run_time_ms) => format!{"{}s", run_time_ms as f64 / 1000.0},
+                                None => "-".to_string()
+                            };
                         }
                     }
                     tr {
@@ -85,7 +89,7 @@ pub fn generate_solution(json_solution: &JsonSolution) -> String {
                         );
                     }
                     div(style="width:1000px;") {
-                        : Raw(generate_cutting_pattern(&json_solution.cutting_patterns[i]))
+                        : Raw(generate_cutting_pattern(&json_solution.cutting_patterns[i], origin))
                     }
 
                 }
@@ -96,17 +100,21 @@ pub fn generate_solution(json_solution: &JsonSolution) -> String {
     html
 }
 
-pub fn generate_cutting_pattern(json_cp: &JsonCP) -> String {
+pub fn generate_cutting_pattern(json_cp: &JsonCP, origin: CoordinateOrigin) -> String {
     let stroke_width = 0.002 * u64::max(json_cp.root.height, json_cp.root.length) as f64;
     let mut document = Document::new()
         .set("width", "100%")
         .set("height", "100%")
         .set("viewBox", (-stroke_width, -stroke_width, json_cp.root.length as f64 + stroke_width * 2.0, json_cp.root.height as f64 + stroke_width * 2.0));
     let mut group = Group::new();
+    if origin == CoordinateOrigin::BottomLeft {
+        //The y axis of the coordinates points upwards, flip it back for rendering
+        group = group.set("transform", format!("translate(0 {}) scale(1 -1)", json_cp.root.height));
+    }
 
     let mut subgroups = Vec::new();
 
-    generate_node(&json_cp.root, (0, 0), &mut subgroups, stroke_width);
+    generate_node(&json_cp.root, (0, 0), &mut subgroups, stroke_width, origin, json_cp.root.height);
     for rect in subgroups {
         group = group.add(rect);
     }
@@ -120,15 +128,17 @@ pub fn generate_cutting_pattern(json_cp: &JsonCP) -> String {
     std::str::from_utf8(&write_buffer).expect("Failed to convert to string").to_string()
 }
 
-fn generate_node(json_cp_node: &JsonCPNode, reference: (u64, u64), groups: &mut Vec<Group>, stroke_width: f64) {
+fn generate_node(json_cp_node: &JsonCPNode, reference: (u64, u64), groups: &mut Vec<Group>, stroke_width: f64, origin: CoordinateOrigin, sheet_height: u64) {
     match json_cp_node.children.is_empty() {
         true => {
             let color = match json_cp_node.node_type {
                 JsonCPNodeType::Structure => panic!("Structure node should have children"),
                 JsonCPNodeType::Item => "#BFBFBF",
                 JsonCPNodeType::Leftover => "#A9D18E",
+                JsonCPNodeType::Defect => "#C00000",
+                JsonCPNodeType::Waste => "#7F7F7F",
             };
-            let (x, y) = (reference.0 as f64, reference.1 as f64);
+            let (x, y) = (reference.0 as f64, origin.transform_y(reference.1, json_cp_node.height, sheet_height) as f64);
             let (width, height) = (json_cp_node.length as f64, json_cp_node.height as f64);
             let mut group = Group::new();
             let rect = Rectangle::new()
@@ -156,8 +166,16 @@ fn generate_node(json_cp_node: &JsonCPNode, reference: (u64, u64), groups: &mut
                                           json_cp_node.height)
                         )
                     );
+                    let mut transforms = Vec::new();
+                    if origin == CoordinateOrigin::BottomLeft {
+                        //Counteract the flip of the y axis, so the text remains readable
+                        transforms.push(format!("translate(0 {}) scale(1 -1)", 2.0 * y + height));
+                    }
                     if json_cp_node.height > json_cp_node.length {
-                        text = text.set("transform", format!("rotate(-90 {} {})", x + (width * 0.5), y + (height * 0.5)));
+                        transforms.push(format!("rotate(-90 {} {})", x + (width * 0.5), y + (height * 0.5)));
+                    }
+                    if !transforms.is_empty() {
+                        text = text.set("transform", transforms.join(" "));
                     }
                     let font_size = f64::min(
                         0.005 * u64::max(json_cp_node.height, json_cp_node.length) as f64,
@@ -173,7 +191,7 @@ fn generate_node(json_cp_node: &JsonCPNode, reference: (u64, u64), groups: &mut
         false => {
             let mut reference = reference;
             for child in &json_cp_node.children {
-                generate_node(child, reference, groups, stroke_width);
+                generate_node(child, reference, groups, stroke_width, origin, sheet_height);
                 match json_cp_node.orientation {
                     Some(JsonOrientation::H) => {
                         reference.1 += child.height;
@@ -8,6 +8,9 @@ pub struct JsonInstance {
     pub sheettypes: Vec<JsonSheetType>,
     #[serde(rename = "Items")]
     pub parttypes: Vec<JsonPartType>,
+    /// Config embedded in the instance file, fields of a separate config file take precedence
+    #[serde(rename = "Config", skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -22,7 +25,12 @@ pub struct JsonSolution {
     pub cutting_patterns: Vec<JsonCP>,
     #[serde(rename = "Statistics")]
     pub statistics: JsonSolutionStats,
-
+    /// Recommended order in which to cut the patterns (indices in `CuttingPatterns`)
+    #[serde(rename = "CuttingSequence", default, skip_serializing_if = "Option::is_none")]
+    pub cutting_sequence: Option<Vec<usize>>,
+    /// Set if cutting patterns were left out to respect the maximum output size, the statistics still describe the full solution
+    #[serde(rename = "Truncated", default, skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -34,6 +42,30 @@ pub struct JsonSheetType {
     pub cost: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reference: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replenishments: Option<Vec<JsonReplenishment>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grain: Option<JsonGrain>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defects: Option<Vec<JsonDefect>>,
+}
+
+/// Rectangular region of an object which cannot be part of any item, positioned by its corner closest to the coordinate origin
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonDefect {
+    pub x: u64,
+    pub y: u64,
+    pub length: u64,
+    pub height: u64,
+}
+
+/// Additional stock of a sheettype which becomes available at a later point in time
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonReplenishment {
+    pub arrival: u64,
+    pub quantity: usize,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -45,6 +77,20 @@ pub struct JsonPartType {
     pub value: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reference: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splittable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_pieces: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_rotations: Option<Vec<u16>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_per_pattern: Option<usize>,
+    /// Items with an earlier due date are more urgent, their cutting patterns are cut first in the `CuttingSequence`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<u64>,
+    /// Direction of the grain of the item, which has to be aligned with the grain of the object it is cut from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grain: Option<JsonGrain>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -53,11 +99,42 @@ pub struct JsonCP {
     pub object: usize,
     pub usage: f64,
     pub root: JsonCPNode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub book_height: Option<usize>,
+    #[serde(default)]
+    pub sheet_cost: u64,
+    /// Total value of the items produced, u64::MAX if it exceeds the range of the field
+    #[serde(default)]
+    pub part_value: u64,
+    pub used_bounding_box: JsonSize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cut_plan: Option<Vec<JsonCutInstruction>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonSize {
+    pub length: u64,
+    pub height: u64,
+}
+
+/// A single guillotine cut, spanning `length` along its orientation starting from (`x`, `y`)
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonCutInstruction {
+    pub stage: usize,
+    pub orientation: JsonOrientation,
+    pub x: u64,
+    pub y: u64,
+    pub length: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct JsonCPNode {
+    /// Unique within the cutting pattern, assigned in pre-order (the root has id 0)
+    #[serde(default)]
+    pub id: usize,
     pub length: u64,
     pub height: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -66,22 +143,39 @@ pub struct JsonCPNode {
     pub node_type: JsonCPNodeType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub item: Option<usize>,
+    /// Number of pieces into which the item is split, the node being one of them. Absent for items produced as a whole
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pieces: Option<usize>,
+    /// Whether the item is placed rotated by 90°, with its height along the length of the object. Absent for other nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotated: Option<bool>,
     pub children: Vec<JsonCPNode>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
 pub enum JsonOrientation {
     H,
     V,
 }
 
+/// Direction of the grain: along the length or the height
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "PascalCase")]
+pub enum JsonGrain {
+    Length,
+    Height,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub enum JsonCPNodeType {
     Structure,
     Item,
     Leftover,
+    Defect,
+    /// Material lost to the kerf of the cuts or reserved as spacing between the items, it cannot be reused
+    Waste,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -90,8 +184,80 @@ pub struct JsonSolutionStats {
     pub usage_pct: f32,
     pub part_area_included_pct: f32,
     pub n_objects_used: usize,
+    #[serde(default)]
+    pub n_distinct_patterns: usize,
+    #[serde(default)]
+    pub lower_bound: usize,
+    #[serde(default)]
+    pub gap_pct: f64,
     pub material_cost: u64,
-    pub run_time_ms: usize,
+    /// Left out of seeded runs, so their solutions are identical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_time_ms: Option<usize>,
     pub config_path: String,
+    /// The complete config with which the solution was generated (including presets and defaults), so it can be reproduced even if the config file or the defaults change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_parts: Vec<JsonExcludedPart>,
+    #[serde(default)]
+    pub placed_per_part: Vec<JsonPlacedPart>,
+}
+
+/// Number of units of an item produced by the solution, compared to its demand
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonPlacedPart {
+    pub item: usize,
+    pub placed: usize,
+    pub demand: usize,
+    /// Units placed beyond the demand, either allowed overproduction or the demand rounded up to whole books
+    #[serde(default)]
+    pub overproduced: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonExcludedPart {
+    pub item: usize,
+    pub quantity: usize,
+    pub reason: JsonExclusionReason,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub enum JsonExclusionReason {
+    NoGeometricFit,
+    StockExhausted,
+    NotPlaced,
+    UnderutilizedPattern,
+}
+
+/// Reconciliation of the demand and stock of an instance with what a solution produces and consumes
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonReconciliationReport {
+    pub name: String,
+    pub items: Vec<JsonItemReconciliation>,
+    pub objects: Vec<JsonObjectReconciliation>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonItemReconciliation {
+    pub item: usize,
+    pub demanded: usize,
+    pub produced: usize,
+    pub shortfall: usize,
+    #[serde(default)]
+    pub overproduced: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonObjectReconciliation {
+    pub object: usize,
+    /// Number of objects available within the stock horizon, None for unlimited stock
+    pub available: Option<usize>,
+    pub consumed: usize,
+}
@@ -1,3 +1,9 @@
 pub mod parser;
 pub mod json_format;
 pub mod html_export;
+pub mod svg_export;
+pub mod csv_export;
+pub mod cut_plan;
+pub mod output;
+#[cfg(feature = "repl")]
+pub mod repl;
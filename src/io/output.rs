@@ -0,0 +1,140 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use itertools::Itertools;
+
+use crate::io::json_format::{JsonCP, JsonSolution};
+use crate::optimization::config::SolutionOutput;
+
+/// Opens the sink to which the JSON solution is written, `None` if it should be discarded
+pub fn open_sink(output: &SolutionOutput) -> std::io::Result<Option<Box<dyn Write>>> {
+    match output {
+        SolutionOutput::File(path) => Ok(Some(Box::new(BufWriter::new(File::create(path)?)))),
+        SolutionOutput::Stdout => Ok(Some(Box::new(std::io::stdout()))),
+        SolutionOutput::Null => Ok(None),
+    }
+}
+
+/// Writes the JSON solution to any sink.
+/// If `pretty` is false, the solution is written on a single line, followed by a newline, so it can be picked out of a stream.
+pub fn write_json_solution(json_solution: &JsonSolution, sink: &mut dyn Write, pretty: bool) -> std::io::Result<()> {
+    match pretty {
+        true => serde_json::to_writer_pretty(&mut *sink, json_solution)?,
+        false => {
+            serde_json::to_writer(&mut *sink, json_solution)?;
+            writeln!(sink)?;
+        }
+    }
+    sink.flush()
+}
+
+/// Limits the serialized size of the JSON solution (in the same format as `write_json_solution`) to `max_bytes`.
+/// If the full solution is too large, only the cutting patterns with the largest part area which fit within the limit are kept
+/// (in their original order) and the solution is marked as `Truncated`. The statistics are left untouched.
+pub fn limit_json_size(mut json_solution: JsonSolution, max_bytes: usize, pretty: bool) -> JsonSolution {
+    if serialized_size(&json_solution, pretty) <= max_bytes {
+        return json_solution;
+    }
+    let cutting_patterns = std::mem::take(&mut json_solution.cutting_patterns);
+    let cutting_sequence = json_solution.cutting_sequence.take();
+    json_solution.truncated = Some(true);
+    let mut remaining_bytes = max_bytes.saturating_sub(serialized_size(&json_solution, pretty));
+
+    //Greedily select the largest patterns based on their individual size, the exact size is verified afterwards
+    let mut selected = cutting_patterns.iter().enumerate()
+        .sorted_by(|(_, a), (_, b)| part_area(b).total_cmp(&part_area(a)))
+        .filter(|(_, json_cp)| {
+            let size = serialized_size(*json_cp, pretty) + 1;
+            match size <= remaining_bytes {
+                true => {
+                    remaining_bytes -= size;
+                    true
+                }
+                false => false
+            }
+        })
+        .map(|(i, _)| i)
+        .collect_vec();
+
+    loop {
+        selected.sort_unstable();
+        json_solution.cutting_patterns = selected.iter().map(|i| cutting_patterns[*i].clone()).collect();
+        //The cutting sequence refers to the patterns by index
+        json_solution.cutting_sequence = cutting_sequence.as_ref().map(|sequence| {
+            sequence.iter().filter_map(|i| selected.iter().position(|s| s == i)).collect()
+        });
+        if selected.is_empty() || serialized_size(&json_solution, pretty) <= max_bytes {
+            return json_solution;
+        }
+        //Drop the smallest selected pattern and retry
+        let smallest = selected.iter().position_min_by(|a, b| part_area(&cutting_patterns[**a]).total_cmp(&part_area(&cutting_patterns[**b]))).unwrap();
+        selected.remove(smallest);
+    }
+}
+
+fn serialized_size<T: serde::Serialize>(value: &T, pretty: bool) -> usize {
+    match pretty {
+        true => serde_json::to_vec_pretty(value),
+        false => serde_json::to_vec(value)
+    }.expect("could not serialize JSON").len()
+}
+
+fn part_area(json_cp: &JsonCP) -> f64 {
+    (json_cp.root.length * json_cp.root.height) as f64 * json_cp.usage
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::io::json_format::JsonSolution;
+    use crate::io::output::{limit_json_size, open_sink, write_json_solution};
+    use crate::io::parser::generate_json_solution;
+    use crate::optimization::config::SolutionOutput;
+    use crate::util::test_util::{config_path, solve};
+
+    /// Solution with one cutting pattern per item
+    fn json_solution(n_items: usize) -> JsonSolution {
+        let (json_instance, _, config, solution) = solve(json!({
+            "Name": "output",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": n_items, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": n_items, "Value": 1}]
+        }), json!({}));
+        generate_json_solution(&json_instance, &solution, &config, &config_path())
+    }
+
+    #[test]
+    fn solution_written_to_a_buffer_is_parsed_back() {
+        let json_solution = json_solution(2);
+
+        for pretty in [false, true] {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_json_solution(&json_solution, &mut buffer, pretty).unwrap();
+            let written = String::from_utf8(buffer).unwrap();
+            //Compact solutions take up exactly one line
+            assert_eq!(written.lines().count() == 1, !pretty);
+            let parsed: JsonSolution = serde_json::from_str(&written).unwrap();
+            assert_eq!(parsed.name, "output");
+            assert_eq!(parsed.statistics.material_cost, json_solution.statistics.material_cost);
+            assert_eq!(parsed.cutting_patterns.len(), 2);
+        }
+        assert!(open_sink(&SolutionOutput::Null).unwrap().is_none());
+    }
+
+    #[test]
+    fn solution_exceeding_the_size_limit_is_truncated() {
+        let size = |json_solution: &JsonSolution| serde_json::to_vec(json_solution).unwrap().len() + 1;
+        let large = json_solution(6);
+        let max_bytes = size(&large) / 2;
+        let truncated = limit_json_size(large.clone(), max_bytes, false);
+        assert_eq!(truncated.truncated, Some(true));
+        assert!(size(&truncated) <= max_bytes);
+        assert!(!truncated.cutting_patterns.is_empty() && truncated.cutting_patterns.len() < 6);
+        assert_eq!(truncated.statistics.material_cost, large.statistics.material_cost);
+
+        let small = json_solution(1);
+        let untouched = limit_json_size(small.clone(), max_bytes, false);
+        assert_eq!(untouched.truncated, None);
+        assert_eq!(size(&untouched), size(&small));
+    }
+}
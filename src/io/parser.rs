@@ -1,40 +1,112 @@
-use std::path::PathBuf;
+use std::path::Path;
 
 use itertools::Itertools;
 
 use crate::{Instance, JsonInstance, Orientation, PartType, SheetType};
 use crate::core::entities::sendable_layout::SendableLayout;
+use crate::core::defect::Defect;
+use crate::core::grain::Grain;
 use crate::core::insertion::node_blueprint::NodeBlueprint;
-use crate::io::json_format::{JsonCP, JsonCPNode, JsonCPNodeType, JsonOrientation, JsonSolution, JsonSolutionStats};
-use crate::optimization::config::{Config, SheetValuationMode};
-use crate::optimization::solutions::sendable_solution::SendableSolution;
+use crate::io::cut_plan::{generate_cut_plan, generate_cutting_sequence};
+use crate::io::json_format::{JsonCP, JsonCPNode, JsonCPNodeType, JsonExcludedPart, JsonExclusionReason, JsonGrain, JsonOrientation, JsonPlacedPart, JsonSheetType, JsonSize, JsonSolution, JsonSolutionStats};
+use crate::optimization::config::{Config, CoordinateOrigin, CutPattern, Effort, SheetValuationMode};
+use crate::optimization::instance::{InstanceError, PieceSet};
+use crate::optimization::solutions::exclusion_reason::{determine_exclusion_reasons, ExclusionReason};
+use crate::optimization::solutions::sendable_solution::{SendableSolution, weighted_utilization};
 use crate::optimization::solutions::solution::Solution;
+use crate::optimization::solutions::solution_stats::gap_pct;
+use crate::Rotation;
 use crate::Rotation::Default;
 
-pub fn generate_instance(json_instance: &mut JsonInstance, config: &Config) -> Instance {
+pub fn generate_instance(json_instance: &mut JsonInstance, config: &Config) -> Result<Instance, InstanceError> {
+    //Every layout cuts a book of stacked sheets, so demand and stock are expressed in books
+    let book_height = config.book_height.unwrap_or(1).max(1);
+    //Every part is enlarged by the spacing, reserving the clearance to its neighbours on its right and bottom side
+    //Neither the spacing nor the kerf is required at the edges of the sheet, so the sheets are enlarged by it as well:
+    //n parts of length l then fit next to each other on a sheet of length L when n * (l + spacing) <= L + spacing
+    let kerf = config.kerf.unwrap_or(0);
+    let part_spacing = config.part_spacing.unwrap_or(0) + kerf;
+
     let mut part_id = 0;
     let mut parts = Vec::new();
+    let mut overproduction = Vec::new();
+    let max_overproduction = match config.overproduction_allowed.unwrap_or(false) {
+        true => config.max_overproduction.unwrap_or(1),
+        false => 0
+    };
     for json_part in json_instance.parttypes.iter_mut() {
         json_part.reference = Some(part_id);
+        if json_part.length == 0 || json_part.height == 0 {
+            return Err(InstanceError::EmptyItem(part_id));
+        }
         let parttype = PartType::new(
             part_id,
             json_part.length,
             json_part.height,
-            if config.rotation_allowed { None } else { Some(Default) },
-        );
-        let demand = json_part.demand;
+            fixed_rotation(json_part.allowed_rotations.as_ref(), config.rotation_allowed)
+                .ok_or(InstanceError::InvalidAllowedRotations(part_id))?,
+            json_part.max_per_pattern,
+            json_part.grain.map(convert_grain),
+        ).with_spacing(part_spacing);
+        let demand = json_part.demand / book_height + (json_part.demand % book_height != 0) as usize;
         parts.push((parttype, demand));
+        overproduction.push(max_overproduction / book_height + (max_overproduction % book_height != 0) as usize);
         part_id += 1;
     }
 
-    let mut sheet_id = 0;
-    let mut sheets = Vec::new();
-    for json_sheet in json_instance.sheettypes.iter_mut() {
+    //Splittable parts can also be produced as 2 up to `maxPieces` pieces along their length, which together cover the full part.
+    //The pieces are additional parttypes without demand of their own, units of the part are split into them during the search.
+    let mut piece_sets = Vec::new();
+    for (original, json_part) in json_instance.parttypes.iter().enumerate() {
+        //Without `maxPieces`, a splittable part can be split in two
+        let max_pieces = match json_part.splittable {
+            Some(true) => json_part.max_pieces.unwrap_or(2) as u64,
+            _ => 1
+        };
+        for n_pieces in (2..=max_pieces).take_while(|n| *n <= json_part.length) {
+            let regular_length = json_part.length / n_pieces;
+            let remainder_length = json_part.length - (n_pieces - 1) * regular_length;
+            let mut piece = |length: u64| {
+                let parttype = PartType::new(
+                    part_id,
+                    length,
+                    json_part.height,
+                    *parts[original].0.fixed_rotation(),
+                    None,
+                    json_part.grain.map(convert_grain),
+                ).as_piece_of(original, n_pieces as usize).with_spacing(part_spacing);
+                parts.push((parttype, 0));
+                overproduction.push(0);
+                part_id += 1;
+                part_id - 1
+            };
+            let regular = piece(regular_length);
+            let remainder = match remainder_length != regular_length {
+                true => Some(piece(remainder_length)),
+                false => None
+            };
+            piece_sets.push(PieceSet { original, n_pieces: n_pieces as usize, regular, remainder });
+        }
+    }
+
+    if let Some(max_total_parts) = config.max_total_parts {
+        let total = parts.iter().map(|(_, demand)| *demand).fold(0, usize::saturating_add);
+        if total > max_total_parts {
+            return Err(InstanceError::TooManyParts { total, max: max_total_parts });
+        }
+    }
+
+    for (sheet_id, json_sheet) in json_instance.sheettypes.iter_mut().enumerate() {
         json_sheet.reference = Some(sheet_id);
+    }
+
+    let mut sheets = Vec::new();
+    for (sheet_id, group) in group_sheettypes(json_instance, config).iter().enumerate() {
+        let json_sheet = &json_instance.sheettypes[group[0]];
         let sheet_value = match config.sheet_valuation_mode{
             SheetValuationMode::Area => json_sheet.length * json_sheet.height,
             SheetValuationMode::Cost => json_sheet.cost
-        };
+        } * book_height as u64;
 
         let sheettype = SheetType::new(
             sheet_id,
@@ -42,35 +114,217 @@ pub fn generate_instance(json_instance: &mut JsonInstance, config: &Config) -> I
             json_sheet.height,
             sheet_value,
             None,
-        );
-        let stock = match json_sheet.stock {
-            Some(stock) => stock,
-            None => usize::MAX
-        };
+            json_sheet.grain.map(convert_grain),
+            convert_defects(json_sheet, config.coordinate_origin).ok_or(InstanceError::DefectOutsideObject(group[0]))?,
+        ).with_spacing(part_spacing, kerf);
+        let stock = group.iter()
+            .map(|i| available_stock(&json_instance.sheettypes[*i], config, book_height))
+            .fold(0, usize::saturating_add);
         sheets.push((sheettype, stock));
-        sheet_id += 1;
     }
 
-    Instance::new(parts, sheets)
+    if sheets.iter().all(|(_, stock)| *stock == 0) {
+        return Err(InstanceError::NoSheetsAvailable);
+    }
+
+    Ok(Instance::new(parts, overproduction, sheets, book_height, part_spacing, kerf, piece_sets))
 }
 
-pub fn generate_json_solution(json_instance: &JsonInstance, solution: &SendableSolution, config_path: &PathBuf) -> JsonSolution {
+/// Combines the config embedded in the instance with the one from a separate config file.
+/// Both are optional, but together they have to define all required fields. Fields defined in the config file take precedence.
+pub fn generate_config(json_instance: &JsonInstance, config_file: Option<serde_json::Value>) -> Result<Config, serde_json::Error> {
+    let mut config = match json_instance.config.clone() {
+        Some(serde_json::Value::Object(embedded)) => embedded,
+        _ => serde_json::Map::new()
+    };
+    if let Some(serde_json::Value::Object(config_file)) = config_file {
+        config.extend(config_file);
+    }
+    //The effort preset only provides the parameters which are not explicitly configured
+    if let Some(effort) = config.get("effort") {
+        let effort: Effort = serde_json::from_value(effort.clone())?;
+        for (key, value) in effort.preset() {
+            config.entry(key).or_insert(value);
+        }
+    }
+    serde_json::from_value(serde_json::Value::Object(config))
+}
+
+/// Groups the sheettypes of the input (by index) which are represented by a single sheettype in the instance.
+/// If `coalesceSheetTypes` is enabled, sheettypes with identical dimensions and cost are merged, otherwise every sheettype has its own group.
+fn group_sheettypes(json_instance: &JsonInstance, config: &Config) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, json_sheet) in json_instance.sheettypes.iter().enumerate() {
+        let identical_group = match config.coalesce_sheet_types.unwrap_or(false) {
+            true => groups.iter_mut().find(|group| {
+                let other = &json_instance.sheettypes[group[0]];
+                (other.length, other.height, other.cost) == (json_sheet.length, json_sheet.height, json_sheet.cost)
+                    && other.grain.map(convert_grain) == json_sheet.grain.map(convert_grain)
+                    && convert_defects(other, config.coordinate_origin) == convert_defects(json_sheet, config.coordinate_origin)
+            }),
+            false => None
+        };
+        match identical_group {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i])
+        }
+    }
+    groups
+}
+
+fn convert_grain(json_grain: JsonGrain) -> Grain {
+    match json_grain {
+        JsonGrain::Length => Grain::Width,
+        JsonGrain::Height => Grain::Height,
+    }
+}
+
+/// Defects of a sheettype, positioned relative to its top left corner. None if a defect lies outside of the sheet.
+/// Defects without area do not affect any item and are left out.
+fn convert_defects(json_sheet: &JsonSheetType, origin: CoordinateOrigin) -> Option<Vec<Defect>> {
+    json_sheet.defects.iter().flatten()
+        .filter(|json_defect| json_defect.length * json_defect.height > 0)
+        .map(|json_defect| {
+            match json_defect.x + json_defect.length <= json_sheet.length && json_defect.y + json_defect.height <= json_sheet.height {
+                true => Some(Defect {
+                    x: json_defect.x,
+                    y: origin.transform_y(json_defect.y, json_defect.height, json_sheet.height),
+                    width: json_defect.length,
+                    height: json_defect.height,
+                }),
+                false => None
+            }
+        })
+        .collect()
+}
+
+/// Number of books of a sheettype available within the stock horizon, usize::MAX for unlimited stock
+pub fn available_stock(json_sheet: &JsonSheetType, config: &Config, book_height: usize) -> usize {
+    match json_sheet.stock {
+        Some(stock) => {
+            let horizon = config.stock_horizon.unwrap_or(0);
+            let replenished = json_sheet.replenishments.iter().flatten()
+                .filter(|r| r.arrival <= horizon)
+                .map(|r| r.quantity)
+                .sum::<usize>();
+            (stock + replenished) / book_height
+        }
+        None => usize::MAX
+    }
+}
+
+/// Determines the fixed rotation of a part (if any) from its allowed rotations (in degrees).
+/// Parts without explicitly allowed rotations can be rotated freely if rotation is allowed in the config.
+/// Returns None if the allowed rotations are invalid.
+fn fixed_rotation(allowed_rotations: Option<&Vec<u16>>, rotation_allowed: bool) -> Option<Option<Rotation>> {
+    let free_rotation = if rotation_allowed { None } else { Some(Default) };
+    match allowed_rotations {
+        None => Some(free_rotation),
+        Some(allowed_rotations) => {
+            let rotations = allowed_rotations.iter()
+                .map(|degrees| Rotation::from_degrees(*degrees))
+                .collect::<Option<Vec<Rotation>>>()?;
+            match (rotations.contains(&Rotation::Default), rotations.contains(&Rotation::Rotated)) {
+                (true, true) => Some(free_rotation),
+                (true, false) => Some(Some(Rotation::Default)),
+                (false, true) => Some(Some(Rotation::Rotated)),
+                (false, false) => None
+            }
+        }
+    }
+}
+
+pub fn generate_json_solution(json_instance: &JsonInstance, solution: &SendableSolution, config: &Config, config_path: &Path) -> JsonSolution {
     let name = json_instance.name.clone();
     let sheettypes = json_instance.sheettypes.clone();
     let parttypes = json_instance.parttypes.clone();
 
+    let sheettype_groups = group_sheettypes(json_instance, config);
+    let mut remaining_stock = json_instance.sheettypes.iter()
+        .map(|json_sheet| available_stock(json_sheet, config, solution.instance().book_height()))
+        .collect_vec();
+
     let cutting_patterns = solution.layouts().iter()
-        .sorted_by(|a, b| { a.usage().partial_cmp(&b.usage()).unwrap().reverse() })
-        .map(|l| { convert_layout_to_json_cp(l) }
-        ).collect::<Vec<JsonCP>>();
+        .sorted_by(|a, b| { a.utilization().partial_cmp(&b.utilization()).unwrap().reverse() })
+        .map(|l| {
+            let mut json_cp = convert_layout_to_json_cp(l, solution.instance(), json_instance);
+            if let Some(scrap_allowance) = config.scrap_allowance_area {
+                json_cp.usage = l.effective_usage(solution.instance().get_sheettype(json_cp.object).area(), scrap_allowance);
+            }
+            json_cp
+        })
+        .map(|mut json_cp| {
+            //Refer to the original sheettype in the input, taking into account the stock of coalesced sheettypes
+            let group = &sheettype_groups[json_cp.object];
+            let original = *group.iter().find(|i| remaining_stock[**i] > 0).unwrap_or(&group[0]);
+            remaining_stock[original] = remaining_stock[original].saturating_sub(1);
+            json_cp.object = original;
+
+            if config.cut_pattern == CutPattern::FreeWithinStage {
+                merge_levels(&mut json_cp.root);
+                assign_node_ids(&mut json_cp.root, &mut 0);
+            }
+            if config.export_cut_plan.unwrap_or(false) {
+                json_cp.cut_plan = Some(generate_cut_plan(&json_cp, config.coordinate_origin));
+            }
+            json_cp
+        })
+        .collect::<Vec<JsonCP>>();
+
+    let excluded_parts = determine_exclusion_reasons(solution, solution.instance(), config.min_pattern_utilization.unwrap_or(0.0))
+        .into_iter()
+        .map(|(item, quantity, reason)| {
+            let reason = match reason {
+                ExclusionReason::NoGeometricFit => JsonExclusionReason::NoGeometricFit,
+                ExclusionReason::StockExhausted => JsonExclusionReason::StockExhausted,
+                ExclusionReason::NotPlaced => JsonExclusionReason::NotPlaced,
+                ExclusionReason::UnderutilizedPattern => JsonExclusionReason::UnderutilizedPattern,
+            };
+            JsonExcludedPart { item, quantity, reason }
+        })
+        .collect();
+
+    //Every unit produced in a cutting pattern is produced once for every sheet in the book
+    //Demand which is not a multiple of the book height is rounded up to whole books, the extra units are overproduced
+    let placed_per_part = json_instance.parttypes.iter().enumerate()
+        .map(|(item, json_part)| {
+            let placed = solution.instance().produced_units(item, solution.parttype_qtys(), solution.parttype_demands()) * solution.instance().book_height();
+            JsonPlacedPart {
+                item,
+                placed,
+                demand: json_part.demand,
+                overproduced: placed.saturating_sub(json_part.demand),
+            }
+        })
+        .collect();
+
+    let usage = match config.scrap_allowance_area {
+        Some(scrap_allowance) => weighted_utilization(solution.layouts(), solution.instance(),
+            |l| l.effective_usage(solution.instance().get_sheettype(l.sheettype_id()).area(), scrap_allowance)),
+        None => weighted_utilization(solution.layouts(), solution.instance(), |l| l.utilization())
+    };
 
     let statistics = JsonSolutionStats {
-        usage_pct: (solution.usage() * 100.0) as f32,
+        usage_pct: (usage * 100.0) as f32,
         part_area_included_pct: (solution.cost().part_area_fraction_included() * 100.0) as f32,
-        n_objects_used: solution.n_layouts(),
+        n_objects_used: solution.n_layouts() * solution.instance().book_height(),
+        n_distinct_patterns: solution.n_distinct_patterns(&config.pattern_hash),
+        lower_bound: solution.instance().sheet_lower_bound() * solution.instance().book_height(),
+        gap_pct: gap_pct(solution.n_layouts(), solution.instance().sheet_lower_bound()),
         material_cost: solution.cost().material_cost,
-        run_time_ms: crate::EPOCH.elapsed().as_millis() as usize,
+        run_time_ms: match config.seed.is_some() || crate::DETERMINISTIC_MODE {
+            true => None,
+            false => Some(crate::EPOCH.elapsed().as_millis() as usize),
+        },
         config_path: config_path.to_str().unwrap().to_string(),
+        config: Some(serde_json::to_value(config.effective()).expect("config could not be serialized")),
+        excluded_parts,
+        placed_per_part,
+    };
+
+    let cutting_sequence = match config.export_cutting_sequence.unwrap_or(false) {
+        true => Some(generate_cutting_sequence(&cutting_patterns, &parttypes)),
+        false => None
     };
 
     JsonSolution {
@@ -79,55 +333,742 @@ pub fn generate_json_solution(json_instance: &JsonInstance, solution: &SendableS
         parttypes,
         cutting_patterns,
         statistics,
+        cutting_sequence,
+        truncated: None,
+    }
+}
+
+fn count_item_nodes(json_cp_node: &JsonCPNode, counts: &mut [usize]) {
+    if let Some(item) = json_cp_node.item {
+        counts[item] += 1;
+    }
+    json_cp_node.children.iter().for_each(|child| count_item_nodes(child, counts));
+}
+
+/// Item nodes refer to the parttype of the instance, pieces of splittable items are mapped to the item in the input
+fn refer_to_original_items(json_cp_node: &mut JsonCPNode, instance: &Instance) {
+    if let Some(parttype_id) = json_cp_node.item {
+        let parttype = instance.get_parttype(parttype_id);
+        json_cp_node.item = Some(parttype.original());
+        json_cp_node.pieces = (parttype.n_pieces() > 1).then(|| parttype.n_pieces());
     }
+    json_cp_node.children.iter_mut().for_each(|child| refer_to_original_items(child, instance));
 }
 
-pub fn convert_layout_to_json_cp(layout: &SendableLayout) -> JsonCP {
+pub fn convert_layout_to_json_cp(layout: &SendableLayout, instance: &Instance, json_instance: &JsonInstance) -> JsonCP {
     let object = layout.sheettype_id();
-    let root = convert_node_bp_to_json_cp_node(layout.top_node());
-    let usage = layout.usage();
+    let sheettype = instance.get_sheettype(object);
+    let mut root = convert_node_bp_to_json_cp_node(layout.top_node(), sheettype);
+    let usage = layout.utilization();
+    //The bounding box ends with the spacing of the last parts
+    let (used_length, used_height) = layout.used_bounding_box();
+    let used_bounding_box = JsonSize {
+        length: used_length.saturating_sub(sheettype.spacing()),
+        height: used_height.saturating_sub(sheettype.spacing()),
+    };
+
+    //The sheet value already covers the entire book, the parts are produced once for every sheet in it
+    let sheet_cost = sheettype.value();
+    let mut placed_qtys = vec![0; instance.parts().len()];
+    count_item_nodes(&root, &mut placed_qtys);
+    //Pieces of a splittable item are valued by their share of its area, summed per item to avoid rounding.
+    //Summed in u128, large values or quantities must not wrap around
+    let mut item_values = vec![0u128; json_instance.parttypes.len()];
+    placed_qtys.iter().enumerate()
+        .filter(|(_, qty)| **qty > 0)
+        .for_each(|(parttype_id, qty)| {
+            let parttype = instance.get_parttype(parttype_id);
+            item_values[parttype.original()] += json_instance.parttypes[parttype.original()].value as u128
+                * (qty * instance.book_height()) as u128 * parttype.area() as u128;
+        });
+    let part_value = item_values.iter().enumerate()
+        .map(|(item, value)| value.checked_div(instance.get_parttype(item).area() as u128).unwrap_or(0))
+        .sum::<u128>();
+    //A total beyond the range of the output field is reported as u64::MAX instead of a wrapped around value
+    let part_value = u64::try_from(part_value).unwrap_or(u64::MAX);
+    refer_to_original_items(&mut root, instance);
+
+    let book_height = match instance.book_height() {
+        1 => None,
+        book_height => Some(book_height)
+    };
 
     JsonCP {
         object,
         root,
         usage,
+        book_height,
+        sheet_cost,
+        part_value,
+        used_bounding_box,
+        cut_plan: None,
     }
 }
 
-pub fn convert_node_bp_to_json_cp_node(node: &NodeBlueprint) -> JsonCPNode {
-    let mut json_children = Vec::new();
-    for child in node.children().iter().sorted_by(|a, b| a.calculate_usage().partial_cmp(&b.calculate_usage()).unwrap().reverse()) {
-        json_children.push(convert_node_bp_to_json_cp_node(child));
+/// Converts the tree of nodes of a cutting pattern on a sheet of the `sheettype`, the nodes are numbered in pre-order.
+/// The nodes are enlarged by the spacing of the sheettype (see `SheetType::with_spacing`), which is exported as waste on their
+/// right and bottom side. At the right and bottom edge of the sheet, the spacing lies outside of the sheet and is left out.
+pub fn convert_node_bp_to_json_cp_node(node: &NodeBlueprint, sheettype: &SheetType) -> JsonCPNode {
+    let mut root = convert_node_bp(node, sheettype, (true, true))
+        .unwrap_or_else(|| leaf(JsonCPNodeType::Waste, 0, 0, None));
+    assign_node_ids(&mut root, &mut 0);
+    root
+}
+
+fn assign_node_ids(json_cp_node: &mut JsonCPNode, next_id: &mut usize) {
+    json_cp_node.id = *next_id;
+    *next_id += 1;
+    json_cp_node.children.iter_mut().for_each(|child| assign_node_ids(child, next_id));
+}
+
+/// `at_edge` states whether the node lies at the right and bottom edge of the sheet, where its spacing is left out.
+/// Returns None if nothing of the node remains.
+fn convert_node_bp(node: &NodeBlueprint, sheettype: &SheetType, at_edge: (bool, bool)) -> Option<JsonCPNode> {
+    let spacing = sheettype.spacing();
+    let length = node.width().saturating_sub(spacing * at_edge.0 as u64);
+    let height = node.height().saturating_sub(spacing * at_edge.1 as u64);
+    if length == 0 || height == 0 {
+        return None;
     }
-    let length = node.width();
-    let height = node.height();
 
-    let node_type = match (node.parttype_id(), node.children().is_empty()) {
-        (None, true) => JsonCPNodeType::Leftover,
-        (None, false) => JsonCPNodeType::Structure,
-        (Some(_), true) => JsonCPNodeType::Item,
-        (Some(_), false) => {
-            panic!("This should not happen")
+    if !node.children().is_empty() {
+        //Degenerate nodes without area are not exported
+        let children = node.sorted_children().into_iter().filter(|child| child.area() > 0).collect_vec();
+        let n_children = children.len();
+        //Only the last child along the cuts reaches the right or bottom side of the node
+        let json_children = children.into_iter().enumerate()
+            .filter_map(|(i, child)| {
+                let last = i + 1 == n_children;
+                let child_at_edge = match node.next_cut_orient() {
+                    Orientation::Horizontal => (at_edge.0, at_edge.1 && last),
+                    Orientation::Vertical => (at_edge.0 && last, at_edge.1),
+                };
+                convert_node_bp(child, sheettype, child_at_edge)
+            })
+            .collect_vec();
+        if json_children.is_empty() {
+            return Some(leaf(JsonCPNodeType::Waste, length, height, None));
         }
-    };
+        return Some(structure(node.next_cut_orient(), length, height, json_children));
+    }
 
-    let orientation = match (&node_type, node.next_cut_orient()) {
-        (JsonCPNodeType::Structure, Orientation::Horizontal) => Some(JsonOrientation::H),
-        (JsonCPNodeType::Structure, Orientation::Vertical) => Some(JsonOrientation::V),
-        (_, _) => None,
+    let inner = match (node.parttype_id(), node.is_defect()) {
+        (Some(item), _) => JsonCPNode {
+            rotated: Some(node.rotation() == Some(Rotation::Rotated)),
+            ..leaf(JsonCPNodeType::Item, node.width() - spacing, node.height() - spacing, Some(item))
+        },
+        (None, true) => leaf(JsonCPNodeType::Defect, length, height, None),
+        (None, false) => match sheettype.leftover_size(node.width(), node.height()) {
+            Some((leftover_length, leftover_height)) => leaf(JsonCPNodeType::Leftover, leftover_length, leftover_height, None),
+            None => leaf(JsonCPNodeType::Waste, length, height, None),
+        },
     };
+    Some(space_node(inner, length, height, node.next_cut_orient()))
+}
 
-    let item = match &node_type {
-        JsonCPNodeType::Item => Some(node.parttype_id().unwrap()),
-        _ => None,
-    };
+/// Leaves out the levels of the tree which only keep the alternation of cut orientations:
+/// structure nodes with a single child are replaced by it, children cut in the same orientation as their parent are merged into it
+fn merge_levels(json_cp_node: &mut JsonCPNode) {
+    let children = std::mem::take(&mut json_cp_node.children);
+    for mut child in children {
+        merge_levels(&mut child);
+        match json_cp_node.orientation.is_some() && child.orientation == json_cp_node.orientation {
+            true => json_cp_node.children.append(&mut child.children),
+            false => json_cp_node.children.push(child),
+        }
+    }
+    if json_cp_node.children.len() == 1 {
+        *json_cp_node = json_cp_node.children.pop().unwrap();
+    }
+}
 
+fn leaf(node_type: JsonCPNodeType, length: u64, height: u64, item: Option<usize>) -> JsonCPNode {
     JsonCPNode {
+        id: 0,
         length,
         height,
-        orientation,
+        orientation: None,
         node_type,
         item,
-        children: json_children,
+        pieces: None,
+        rotated: None,
+        children: vec![],
+    }
+}
+
+/// Structure node with the given children, of which those without area are left out
+fn structure(orientation: Orientation, length: u64, height: u64, children: Vec<JsonCPNode>) -> JsonCPNode {
+    JsonCPNode {
+        id: 0,
+        length,
+        height,
+        orientation: Some(match orientation {
+            Orientation::Horizontal => JsonOrientation::H,
+            Orientation::Vertical => JsonOrientation::V,
+        }),
+        node_type: JsonCPNodeType::Structure,
+        item: None,
+        pieces: None,
+        rotated: None,
+        children: children.into_iter().filter(|child| child.length * child.height > 0).collect(),
+    }
+}
+
+/// Fills a node of the given dimensions with the (smaller) `inner` node in its top left corner and strips of waste on its right and bottom side.
+/// The two extra levels of cuts keep the alternation of cut orientations intact, levels without a strip are left out.
+fn space_node(inner: JsonCPNode, length: u64, height: u64, next_cut_orient: Orientation) -> JsonCPNode {
+    debug_assert!(inner.length <= length && inner.height <= height);
+    let (inner_length, inner_height) = (inner.length, inner.height);
+    if (inner_length, inner_height) == (length, height) {
+        return inner;
+    }
+    let waste = |length, height| leaf(JsonCPNodeType::Waste, length, height, None);
+    match next_cut_orient {
+        Orientation::Horizontal => {
+            let row = match inner_length < length {
+                true => structure(Orientation::Vertical, length, inner_height, vec![inner, waste(length - inner_length, inner_height)]),
+                false => inner
+            };
+            structure(Orientation::Horizontal, length, height, vec![row, waste(length, height - inner_height)])
+        }
+        Orientation::Vertical => {
+            let column = match inner_height < height {
+                true => structure(Orientation::Horizontal, inner_length, height, vec![inner, waste(inner_length, height - inner_height)]),
+                false => inner
+            };
+            structure(Orientation::Vertical, length, height, vec![column, waste(length - inner_length, height)])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use serde_json::json;
+
+    use crate::core::insertion::node_blueprint::NodeBlueprint;
+    use crate::core::rotation::Rotation;
+    use crate::io::json_format::{JsonCPNode, JsonCPNodeType, JsonInstance, JsonOrientation, JsonSolution};
+    use crate::io::parser::{convert_node_bp_to_json_cp_node, generate_config, generate_instance, generate_json_solution};
+    use crate::optimization::config::{Config, CutPattern};
+    use crate::optimization::instance::InstanceError;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::Orientation;
+    use crate::util::test_util::{config_path, parse, solve, test_config};
+
+    /// All nodes of the tree, with their position relative to the top left corner of the root
+    fn flatten(node: &JsonCPNode, x: u64, y: u64, nodes: &mut Vec<(u64, u64, JsonCPNode)>) {
+        let (mut child_x, mut child_y) = (x, y);
+        for child in &node.children {
+            flatten(child, child_x, child_y, nodes);
+            match node.orientation {
+                Some(JsonOrientation::V) => child_x += child.length,
+                _ => child_y += child.height,
+            }
+        }
+        //Children exactly cover their parent
+        match node.orientation {
+            Some(JsonOrientation::V) => {
+                assert_eq!(child_x - x, node.length);
+                assert!(node.children.iter().all(|c| c.height == node.height));
+            }
+            Some(JsonOrientation::H) => {
+                assert_eq!(child_y - y, node.height);
+                assert!(node.children.iter().all(|c| c.length == node.length));
+            }
+            None => assert!(node.children.is_empty()),
+        }
+        nodes.push((x, y, node.clone()));
+    }
+
+    /// Leftovers (x, y, length, height) of the first cutting pattern of the solution, which are identical in the JSON solution
+    fn solve_leftovers(instance: serde_json::Value, config: serde_json::Value) -> Vec<(u64, u64, u64, u64)> {
+        let (json_instance, _, config, solution) = solve(instance, config);
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let root = &json_solution.cutting_patterns[0].root;
+        assert_eq!((root.length, root.height), (json_instance.sheettypes[0].length, json_instance.sheettypes[0].height));
+        let mut nodes = Vec::new();
+        flatten(root, 0, 0, &mut nodes);
+        let json_leftovers = nodes.iter()
+            .filter(|(_, _, node)| matches!(node.node_type, JsonCPNodeType::Leftover))
+            .map(|(x, y, node)| (*x, *y, node.length, node.height))
+            .collect::<Vec<_>>();
+        let leftovers = solution.leftovers().map(|l| (l.x, l.y, l.width, l.height)).collect::<Vec<_>>();
+        assert_eq!(json_leftovers, leftovers);
+        leftovers
+    }
+
+    /// Two items which exactly fill the length of the object, apart from the kerf between them
+    fn two_items_instance() -> serde_json::Value {
+        json!({
+            "Name": "spacing",
+            "Objects": [{"Length": 103, "Height": 50, "Stock": 2, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 2, "Value": 50}]
+        })
+    }
+
+    #[test]
+    fn part_spacing_is_required_between_parts() {
+        let (_, _, _, kerf_only) = solve(two_items_instance(), json!({"kerf": 3, "rotationAllowed": false}));
+        assert_eq!(kerf_only.n_layouts(), 1);
+
+        let (_, _, _, spaced) = solve(two_items_instance(), json!({"kerf": 3, "partSpacing": 2, "rotationAllowed": false}));
+        assert_eq!(spaced.n_layouts(), 2);
+        assert_eq!(spaced.parttype_qtys(), &vec![0]);
+    }
+
+    #[test]
+    fn part_spacing_is_not_required_at_the_sheet_edges() {
+        let instance = json!({
+            "Name": "spacing",
+            "Objects": [{"Length": 50, "Height": 50, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 50}]
+        });
+        let (_, _, _, solution) = solve(instance, json!({"kerf": 3, "partSpacing": 2}));
+        assert_eq!(solution.parttype_qtys(), &vec![0]);
+        assert_eq!(solution.usage(), 1.0);
+    }
+
+    #[test]
+    fn kerf_shrinks_leftovers() {
+        let instance = |length| json!({
+            "Name": "kerf",
+            "Objects": [{"Length": length, "Height": 50, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 50}]
+        });
+        let config = json!({"kerf": 3, "rotationAllowed": false});
+        assert_eq!(solve_leftovers(instance(100), config.clone()), vec![(53, 0, 47, 50)]);
+        //The cut would leave less than the kerf of usable material
+        assert_eq!(solve_leftovers(instance(55), config.clone()), vec![]);
+        assert_eq!(solve_leftovers(instance(56), config.clone()), vec![(53, 0, 3, 50)]);
+        assert_eq!(solve_leftovers(instance(100), json!({"rotationAllowed": false})), vec![(50, 0, 50, 50)]);
+    }
+
+    #[test]
+    fn usage_is_relative_to_the_actual_sheet() {
+        let instance = json!({
+            "Name": "kerf",
+            "Objects": [{"Length": 100, "Height": 50, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 50}]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({"kerf": 3, "rotationAllowed": false}));
+        assert_eq!(solution.usage(), 0.5);
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert_eq!(json_solution.cutting_patterns[0].usage, 0.5);
+        assert_eq!(json_solution.cutting_patterns[0].used_bounding_box.length, 50);
+    }
+
+    fn assert_alternating(node: &JsonCPNode) {
+        for child in &node.children {
+            if child.orientation.is_some() {
+                assert!(child.orientation != node.orientation);
+            }
+            assert_alternating(child);
+        }
+    }
+
+    fn cut_pattern_instance() -> serde_json::Value {
+        json!({
+            "Name": "cut_pattern",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [
+                {"Length": 45, "Height": 30, "Demand": 4, "Value": 1},
+                {"Length": 20, "Height": 25, "Demand": 5, "Value": 1},
+                {"Length": 60, "Height": 15, "Demand": 3, "Value": 1}
+            ]
+        })
+    }
+
+    /// Item nodes of all cutting patterns, as (pattern, x, y, length, height)
+    fn placed_items(json_solution: &JsonSolution) -> Vec<(usize, u64, u64, u64, u64)> {
+        json_solution.cutting_patterns.iter().enumerate()
+            .flat_map(|(i, json_cp)| {
+                let mut nodes = Vec::new();
+                flatten(&json_cp.root, 0, 0, &mut nodes);
+                nodes.into_iter()
+                    .filter(|(_, _, node)| matches!(node.node_type, JsonCPNodeType::Item))
+                    .map(move |(x, y, node)| (i, x, y, node.length, node.height))
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn strict_alternating_flips_the_orientation_at_every_level() {
+        let (json_instance, _, config, solution) = solve(cut_pattern_instance(), json!({"kerf": 2, "cutPattern": "strictAlternating"}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        json_solution.cutting_patterns.iter().for_each(|json_cp| assert_alternating(&json_cp.root));
+    }
+
+    #[test]
+    fn free_within_stage_leaves_out_levels_without_changing_the_items() {
+        let (json_instance, _, config, solution) = solve(cut_pattern_instance(), json!({"kerf": 2}));
+        let strict = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let free_config = Config { cut_pattern: CutPattern::FreeWithinStage, ..(*config).clone() };
+        let free = generate_json_solution(&json_instance, &solution, &free_config, &config_path());
+        assert_eq!(placed_items(&strict), placed_items(&free));
+
+        fn single_child_structures(node: &JsonCPNode) -> usize {
+            (node.children.len() == 1) as usize + node.children.iter().map(single_child_structures).sum::<usize>()
+        }
+        let count = |json_solution: &JsonSolution| json_solution.cutting_patterns.iter().map(|json_cp| single_child_structures(&json_cp.root)).sum::<usize>();
+        assert!(count(&strict) > 0);
+        assert_eq!(count(&free), 0);
+    }
+
+    #[test]
+    fn exported_config_contains_presets_and_defaults() {
+        let (json_instance, _, config, solution) = solve(two_items_instance(), json!({"effort": "quick", "kerf": 3}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let exported = json_solution.statistics.config.unwrap();
+        //Configured explicitly, from the effort preset and from the defaults respectively
+        assert_eq!(exported["kerf"], 3);
+        assert_eq!(exported["maxRunTime"], 10);
+        assert_eq!(exported["partSpacing"], 0);
+        assert_eq!(exported["bookHeight"], 1);
+        assert_eq!(exported["preferExistingLayouts"], true);
+        assert_eq!(exported["cutPattern"], "strictAlternating");
+        //Using the exported config as config file reproduces it
+        let reloaded: Config = serde_json::from_value(exported.clone()).unwrap();
+        assert_eq!(serde_json::to_value(reloaded.effective()).unwrap(), exported);
+    }
+
+    #[test]
+    fn items_without_area_are_rejected() {
+        let mut json_instance: JsonInstance = serde_json::from_value(json!({
+            "Name": "empty",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 1}, {"Length": 0, "Height": 50, "Demand": 1, "Value": 1}]
+        })).unwrap();
+        let config = generate_config(&json_instance, Some(test_config(json!({"kerf": 3})))).unwrap();
+        assert_eq!(generate_instance(&mut json_instance, &config).err(), Some(InstanceError::EmptyItem(1)));
+    }
+
+    #[test]
+    fn part_value_saturates_instead_of_wrapping_around() {
+        let instance = |value: u64| json!({
+            "Name": "value",
+            "Objects": [{"Length": 100, "Height": 50, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 2, "Value": value}]
+        });
+        let part_value = |value: u64| {
+            let (json_instance, _, config, solution) = solve(instance(value), json!({"rotationAllowed": false}));
+            generate_json_solution(&json_instance, &solution, &config, &config_path()).cutting_patterns[0].part_value
+        };
+        assert_eq!(part_value(u64::MAX / 2), u64::MAX - 1);
+        assert_eq!(part_value(u64::MAX / 2 + 1), u64::MAX);
+        assert_eq!(part_value(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn instance_without_sheets_is_rejected() {
+        let mut json_instance: JsonInstance = serde_json::from_value(json!({
+            "Name": "no_sheets",
+            "Objects": [],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 1}]
+        })).unwrap();
+        let config = generate_config(&json_instance, Some(test_config(json!({})))).unwrap();
+        assert_eq!(generate_instance(&mut json_instance, &config).err(), Some(InstanceError::NoSheetsAvailable));
+    }
+
+    /// Nodes of the item in the cutting patterns of the solution, as (length, height, pieces)
+    fn item_nodes(json_solution: &JsonSolution, item: usize) -> Vec<(u64, u64, Option<usize>)> {
+        let mut nodes = Vec::new();
+        json_solution.cutting_patterns.iter().for_each(|json_cp| flatten(&json_cp.root, 0, 0, &mut nodes));
+        nodes.into_iter()
+            .filter(|(_, _, node)| node.item == Some(item))
+            .map(|(_, _, node)| (node.length, node.height, node.pieces))
+            .sorted()
+            .collect()
+    }
+
+    fn splittable_instance(object_length: u64, max_pieces: Option<usize>) -> serde_json::Value {
+        let mut instance = json!({
+            "Name": "splittable",
+            "Objects": [{"Length": object_length, "Height": 20, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 101, "Height": 10, "Demand": 1, "Value": 50, "Splittable": true}]
+        });
+        if let Some(max_pieces) = max_pieces {
+            instance["Items"][0]["MaxPieces"] = json!(max_pieces);
+        }
+        instance
+    }
+
+    #[test]
+    fn splittable_part_satisfied_by_two_half_width_placements_when_no_full_width_space_exists() {
+        let (json_instance, instance, config, solution) = solve(splittable_instance(60, Some(2)), json!({"rotationAllowed": false}));
+        assert_eq!(instance.produced_units(0, solution.parttype_qtys(), solution.parttype_demands()), 1);
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert!(json_solution.statistics.excluded_parts.is_empty());
+        assert_eq!(json_solution.statistics.placed_per_part[0].placed, 1);
+        //The lengths of the pieces add up to that of the item, the remainder goes to the last piece
+        assert_eq!(item_nodes(&json_solution, 0), vec![(50, 10, Some(2)), (51, 10, Some(2))]);
+        assert_eq!(json_solution.cutting_patterns[0].part_value, 50);
+    }
+
+    #[test]
+    fn splittable_part_is_produced_as_a_whole_when_it_fits() {
+        let (json_instance, _, config, solution) = solve(splittable_instance(120, Some(2)), json!({"rotationAllowed": false}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert_eq!(item_nodes(&json_solution, 0), vec![(101, 10, None)]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn splittable_part_without_max_pieces_is_split_in_two() {
+        let (json_instance, _, config, solution) = solve(splittable_instance(60, None), json!({"rotationAllowed": false}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert!(json_solution.statistics.excluded_parts.is_empty());
+        assert_eq!(item_nodes(&json_solution, 0), vec![(50, 10, Some(2)), (51, 10, Some(2))]);
+    }
+
+    #[test]
+    fn book_multiplies_the_production_and_consumption_of_a_pattern() {
+        let instance = |demand: usize| json!({
+            "Name": "book",
+            "Objects": [{"Length": 100, "Height": 50, "Stock": 6, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": demand, "Value": 1}]
+        });
+        let (json_instance, _, config, solution) = solve(instance(6), json!({"bookHeight": 3, "rotationAllowed": false}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert_eq!(json_solution.cutting_patterns.len(), 1);
+        assert_eq!(json_solution.cutting_patterns[0].book_height, Some(3));
+        assert_eq!(json_solution.statistics.n_objects_used, 3);
+        let placed = &json_solution.statistics.placed_per_part[0];
+        assert_eq!((placed.placed, placed.demand, placed.overproduced), (6, 6, 0));
+
+        //The demand is rounded up to whole books
+        let (json_instance, _, config, solution) = solve(instance(4), json!({"bookHeight": 3, "rotationAllowed": false}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let placed = &json_solution.statistics.placed_per_part[0];
+        assert_eq!((placed.placed, placed.demand, placed.overproduced), (6, 4, 2));
+        assert!(json_solution.statistics.excluded_parts.is_empty());
+    }
+
+    #[test]
+    fn replenishments_beyond_the_stock_horizon_are_not_used() {
+        let instance = json!({
+            "Name": "replenishment",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100, "Replenishments": [{"Arrival": 10, "Quantity": 2}]}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 3, "Value": 1}]
+        });
+        let (_, _, _, solution) = solve(instance.clone(), json!({}));
+        assert_eq!(solution.n_layouts(), 1);
+        assert_eq!(solution.parttype_qtys(), &vec![2]);
+
+        let (_, _, _, solution) = solve(instance, json!({"stockHorizon": 10}));
+        assert_eq!(solution.n_layouts(), 3);
+        assert!(solution.is_complete());
+    }
+
+    #[test]
+    fn identical_sheettypes_are_coalesced_but_exported_as_the_originals() {
+        let instance = json!({
+            "Name": "coalesce",
+            "Objects": [
+                {"Length": 100, "Height": 100, "Stock": 1, "Cost": 100},
+                {"Length": 100, "Height": 100, "Stock": 2, "Cost": 100}
+            ],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 3, "Value": 1}]
+        });
+        let (_, separate, _) = parse(instance.clone(), json!({}));
+        assert_eq!(separate.sheets().iter().map(|(_, stock)| *stock).collect_vec(), vec![1, 2]);
+
+        let config = json!({"coalesceSheetTypes": true});
+        let (_, coalesced, _) = parse(instance.clone(), config.clone());
+        assert_eq!(coalesced.sheets().iter().map(|(_, stock)| *stock).collect_vec(), vec![3]);
+
+        let (json_instance, _, config, solution) = solve(instance, config);
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let objects = json_solution.cutting_patterns.iter().map(|json_cp| json_cp.object).sorted().collect_vec();
+        assert_eq!(objects, vec![0, 1, 1]);
+    }
+
+    #[test]
+    fn sheettypes_are_parsed_with_all_fields() {
+        let instance = json!({
+            "Name": "sheettypes",
+            "Objects": [
+                {"Length": 100, "Height": 50, "Stock": 2, "Cost": 70},
+                {"Length": 80, "Height": 60, "Cost": 30}
+            ],
+            "Items": [{"Length": 10, "Height": 10, "Demand": 1, "Value": 1}]
+        });
+        let (json_instance, by_cost, _) = parse(instance.clone(), json!({"sheetValuationMode": "cost"}));
+        let fields = by_cost.sheets().iter()
+            .map(|(sheettype, stock)| (sheettype.id(), sheettype.width(), sheettype.height(), sheettype.value(), *stock))
+            .collect_vec();
+        assert_eq!(fields, vec![(0, 100, 50, 70, 2), (1, 80, 60, 30, usize::MAX)]);
+        assert!(by_cost.sheets().iter().all(|(sheettype, _)| sheettype.fixed_first_cut_orientation().is_none()
+            && sheettype.grain().is_none() && sheettype.defects().is_empty()));
+        assert_eq!(json_instance.sheettypes.iter().map(|json_sheet| json_sheet.reference).collect_vec(), vec![Some(0), Some(1)]);
+
+        let (_, by_area, _) = parse(instance, json!({"sheetValuationMode": "area"}));
+        assert_eq!(by_area.sheets().iter().map(|(sheettype, _)| sheettype.value()).collect_vec(), vec![5000, 4800]);
+    }
+
+    #[test]
+    fn placed_quantities_match_the_item_nodes() {
+        let instance = json!({
+            "Name": "placed",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [
+                {"Length": 50, "Height": 50, "Demand": 3, "Value": 1},
+                {"Length": 60, "Height": 40, "Demand": 2, "Value": 1},
+                {"Length": 20, "Height": 10, "Demand": 4, "Value": 1}
+            ]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({}));
+        assert!(!solution.is_complete());
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let placed_per_part = &json_solution.statistics.placed_per_part;
+        assert_eq!(placed_per_part.iter().map(|placed| (placed.item, placed.demand)).collect_vec(), vec![(0, 3), (1, 2), (2, 4)]);
+        for placed in placed_per_part {
+            assert_eq!(placed.placed, item_nodes(&json_solution, placed.item).len());
+        }
+        assert!(placed_per_part.iter().any(|placed| placed.placed < placed.demand));
+    }
+
+    #[test]
+    fn pattern_reports_the_value_of_its_sheettype_and_parts() {
+        let instance = json!({
+            "Name": "attribution",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 70}, {"Length": 60, "Height": 60, "Stock": 2, "Cost": 30}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 4, "Value": 5}, {"Length": 50, "Height": 10, "Demand": 3, "Value": 2}]
+        });
+        let (json_instance, instance, config, solution) = solve(instance, json!({"sheetValuationMode": "cost"}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        for json_cp in &json_solution.cutting_patterns {
+            assert_eq!(json_cp.sheet_cost, instance.get_sheettype(json_cp.object).value());
+            assert_eq!(json_cp.sheet_cost, json_instance.sheettypes[json_cp.object].cost);
+        }
+        let total_part_value = json_solution.cutting_patterns.iter().map(|json_cp| json_cp.part_value).sum::<u64>();
+        let placed_value = json_solution.statistics.placed_per_part.iter()
+            .map(|placed| placed.placed as u64 * json_instance.parttypes[placed.item].value)
+            .sum::<u64>();
+        assert_eq!(total_part_value, placed_value);
+    }
+
+    #[test]
+    fn config_embedded_in_the_instance_is_overridden_by_the_config_file() {
+        let mut instance = two_items_instance();
+        instance["Config"] = test_config(json!({"avgNodesRemoved": 8, "historyLength": 50, "kerf": 2}));
+        let json_instance: JsonInstance = serde_json::from_value(instance).unwrap();
+
+        let embedded_only = generate_config(&json_instance, None).unwrap();
+        assert_eq!(embedded_only.avg_nodes_removed, 8);
+        assert_eq!(embedded_only.history_length, 50);
+        assert_eq!(embedded_only.kerf, Some(2));
+
+        let overridden = generate_config(&json_instance, Some(json!({"historyLength": 75}))).unwrap();
+        assert_eq!(overridden.avg_nodes_removed, 8);
+        assert_eq!(overridden.history_length, 75);
+        assert_eq!(overridden.kerf, Some(2));
+    }
+
+    #[test]
+    fn perfectly_packed_solution_has_no_optimality_gap() {
+        let instance = json!({
+            "Name": "perfect",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 8, "Value": 1}]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert_eq!(json_solution.statistics.lower_bound, 2);
+        assert_eq!(json_solution.cutting_patterns.len(), 2);
+        assert_eq!(json_solution.statistics.gap_pct, 0.0);
+    }
+
+    #[test]
+    fn node_ids_are_unique_and_assigned_in_pre_order() {
+        fn pre_order_ids(node: &JsonCPNode, ids: &mut Vec<usize>) {
+            ids.push(node.id);
+            node.children.iter().for_each(|child| pre_order_ids(child, ids));
+        }
+        let (json_instance, _, config, solution) = solve(cut_pattern_instance(), json!({}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        for json_cp in &json_solution.cutting_patterns {
+            let mut ids = Vec::new();
+            pre_order_ids(&json_cp.root, &mut ids);
+            assert!(ids.len() > 1);
+            assert_eq!(ids, (0..ids.len()).collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn total_demand_beyond_the_max_total_parts_is_rejected() {
+        let mut json_instance: JsonInstance = serde_json::from_value(json!({
+            "Name": "max_total_parts",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [
+                {"Length": 10, "Height": 10, "Demand": 600, "Value": 1},
+                {"Length": 20, "Height": 20, "Demand": 500, "Value": 1}
+            ]
+        })).unwrap();
+        let config = generate_config(&json_instance, Some(test_config(json!({"maxTotalParts": 1000})))).unwrap();
+        let error = generate_instance(&mut json_instance, &config).err().unwrap();
+        assert_eq!(error, InstanceError::TooManyParts { total: 1100, max: 1000 });
+        assert_eq!(error.to_string(), "instance contains 1100 parts to place, more than the maximum of 1000 (maxTotalParts)");
+
+        let config = generate_config(&json_instance, Some(test_config(json!({"maxTotalParts": 1100})))).unwrap();
+        assert!(generate_instance(&mut json_instance, &config).is_ok());
+    }
+
+    #[test]
+    fn overproduction_is_capped_by_max_overproduction() {
+        //The sheet has room for four copies of the item
+        let placed = |config: serde_json::Value| {
+            let (json_instance, _, config, solution) = solve(json!({
+                "Name": "overproduction",
+                "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+                "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 1}]
+            }), config);
+            let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+            let placed_part = &json_solution.statistics.placed_per_part[0];
+            (placed_part.placed, placed_part.overproduced)
+        };
+        assert_eq!(placed(json!({})), (1, 0));
+        assert_eq!(placed(json!({"overproductionAllowed": true, "maxOverproduction": 2})), (3, 2));
+    }
+
+    #[test]
+    fn kerf_filling_the_gap_at_the_edge_leaves_no_zero_area_node() {
+        //After the kerf of the second item, the remaining 3 units of the length are the kerf at the edge of the sheet
+        let (_, instance, _) = parse(json!({
+            "Name": "zero_gap",
+            "Objects": [{"Length": 106, "Height": 50, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 2, "Value": 1}]
+        }), json!({"kerf": 3}));
+        let (sheettype, parttype) = (instance.get_sheettype(0), instance.get_parttype(0));
+        let mut top_node = NodeBlueprint::new(sheettype.width(), sheettype.height(), None, Orientation::Vertical);
+        for _ in 0..2 {
+            top_node.add_child(NodeBlueprint::new(parttype.width(), parttype.height(), Some((parttype, Rotation::Default)), Orientation::Horizontal));
+        }
+        top_node.add_child(NodeBlueprint::new(sheettype.width() - 2 * parttype.width(), sheettype.height(), None, Orientation::Horizontal));
+        //Zero-area children are pruned right away
+        top_node.add_child(NodeBlueprint::new(0, sheettype.height(), None, Orientation::Horizontal));
+        assert_eq!(top_node.children().len(), 3);
+
+        let json_cp_node = convert_node_bp_to_json_cp_node(&top_node, sheettype);
+        let mut nodes = Vec::new();
+        flatten(&json_cp_node, 0, 0, &mut nodes);
+        assert!(nodes.iter().all(|(_, _, node)| node.length > 0 && node.height > 0));
+        assert!(!nodes.iter().any(|(_, _, node)| matches!(node.node_type, JsonCPNodeType::Leftover)));
+        assert_eq!(nodes.iter().filter(|(_, _, node)| matches!(node.node_type, JsonCPNodeType::Item)).count(), 2);
+    }
+
+    #[test]
+    fn thorough_effort_explores_more_than_quick_effort() {
+        let json_instance: JsonInstance = serde_json::from_value(two_items_instance()).unwrap();
+        let resolve = |config: serde_json::Value| generate_config(&json_instance, Some(config)).unwrap();
+        let quick = resolve(json!({"effort": "quick", "rotationAllowed": true, "sheetValuationMode": "area"}));
+        let thorough = resolve(json!({"effort": "thorough", "rotationAllowed": true, "sheetValuationMode": "area"}));
+        assert_eq!((quick.history_length, quick.exploration_ratio, quick.adaptive_ruin), (200, None, None));
+        assert_eq!((thorough.history_length, thorough.exploration_ratio, thorough.adaptive_ruin), (1000, Some(0.5), Some(true)));
+        assert!(thorough.run_time_limit() > quick.run_time_limit());
+
+        //Explicit settings override the preset
+        let overridden = resolve(json!({"effort": "thorough", "rotationAllowed": true, "sheetValuationMode": "area", "historyLength": 42}));
+        assert_eq!((overridden.history_length, overridden.exploration_ratio), (42, Some(0.5)));
+    }
+}
@@ -0,0 +1,120 @@
+//! Interactive mode for what-if analysis: the instance and config stay in memory and can be adjusted between runs.
+//! Every `solve` command re-runs the optimization on the adjusted instance and prints the stats of the best solution.
+
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+use crate::io::json_format::JsonInstance;
+use crate::io::parser;
+use crate::optimization::config::Config;
+use crate::optimization::optimize::optimize;
+use crate::optimization::sol_collectors::global_sol_collector::GlobalSolCollector;
+use crate::optimization::sol_collectors::reporter::ConsoleReporter;
+use crate::util::util;
+
+const HELP: &str = "Commands:
+  solve                  optimize the current instance
+  time <seconds>         set the maximum run time
+  rotation <on|off>      allow or forbid rotating the items
+  demand <item> <qty>    set the demand of an item (index in the input)
+  show                   print the current settings and demands
+  help                   print this message
+  quit                   leave the interactive mode";
+
+/// Reads commands line by line from `input` until `quit` or the end of the input, writing all responses to `output`
+pub fn run_repl(mut json_instance: JsonInstance, mut config: Config, input: &mut dyn BufRead, output: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(output, "{}", HELP)?;
+    let mut line = String::new();
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        let args = line.split_whitespace().collect::<Vec<&str>>();
+        match args.as_slice() {
+            [] => (),
+            ["quit"] | ["exit"] => break,
+            ["help"] => writeln!(output, "{}", HELP)?,
+            ["show"] => {
+                let run_time = match config.run_time_limit() {
+                    Some(limit) => format!("{:.3}s", limit.as_secs_f64()),
+                    None => "unlimited".to_string()
+                };
+                writeln!(output, "run time: {}, rotation allowed: {}", run_time, config.rotation_allowed)?;
+                for (item, json_part) in json_instance.parttypes.iter().enumerate() {
+                    writeln!(output, "item {}: {}x{}, demand {}", item, json_part.length, json_part.height, json_part.demand)?;
+                }
+            }
+            ["time", seconds] => match seconds.parse::<f64>() {
+                Ok(seconds) if seconds >= 0.0 => {
+                    config.max_run_time_ms = Some((seconds * 1000.0) as u64);
+                    writeln!(output, "run time set to {:.3}s", seconds)?;
+                }
+                _ => writeln!(output, "invalid run time: {}", seconds)?
+            },
+            ["rotation", setting @ ("on" | "off")] => {
+                config.rotation_allowed = *setting == "on";
+                writeln!(output, "rotation allowed: {}", config.rotation_allowed)?;
+            }
+            ["demand", item, qty] => match (item.parse::<usize>(), qty.parse::<usize>()) {
+                (Ok(item), Ok(qty)) if item < json_instance.parttypes.len() => {
+                    json_instance.parttypes[item].demand = qty;
+                    writeln!(output, "demand of item {} set to {}", item, qty)?;
+                }
+                _ => writeln!(output, "invalid item or quantity: {} {}", item, qty)?
+            },
+            ["solve"] => solve(&json_instance, &config, output)?,
+            _ => writeln!(output, "unknown command: {} (type help for the list of commands)", line.trim())?
+        }
+    }
+    Ok(())
+}
+
+fn solve(json_instance: &JsonInstance, config: &Config, output: &mut dyn Write) -> std::io::Result<()> {
+    //The instance is generated from a copy, as the parser assigns references to the items and objects
+    let mut json_instance = json_instance.clone();
+    let instance = match parser::generate_instance(&mut json_instance, config) {
+        Ok(instance) => Arc::new(instance),
+        Err(err) => return writeln!(output, "invalid instance: {}", err)
+    };
+    //A Ctrl-C during a previous run only terminated that run
+    GlobalSolCollector::clear_manual_termination();
+    match optimize(instance, Arc::new(config.clone()), Box::new(ConsoleReporter)) {
+        Ok(Some(solution)) => writeln!(output, "{}", util::solution_stats_string(&solution)),
+        Ok(None) => writeln!(output, "no solution found"),
+        Err(err) => writeln!(output, "{}", err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::io::json_format::JsonInstance;
+    use crate::io::parser::generate_config;
+    use crate::io::repl::run_repl;
+    use crate::util::test_util::test_config;
+
+    #[test]
+    fn re_solve_reflects_the_changed_demand() {
+        let json_instance: JsonInstance = serde_json::from_value(json!({
+            "Name": "repl",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 100, "Height": 100, "Demand": 1, "Value": 1}]
+        })).unwrap();
+        let config = generate_config(&json_instance, Some(test_config(json!({})))).unwrap();
+        let mut input = "solve\ndemand 0 3\nsolve\ndemand 1 3\nquit\n".as_bytes();
+        let mut output = Vec::new();
+        run_repl(json_instance, config, &mut input, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let stats = output.lines().filter(|line| line.contains("sheets:")).collect::<Vec<_>>();
+        assert_eq!(stats.len(), 2);
+        assert!(stats[0].contains("sheets: 1,"));
+        assert!(stats[1].contains("sheets: 3,"));
+        assert!(output.contains("demand of item 0 set to 3"));
+        assert!(output.contains("invalid item or quantity: 1 3"));
+    }
+}
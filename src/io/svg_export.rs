@@ -0,0 +1,156 @@
+use itertools::Itertools;
+use svg::Document;
+use svg::node::element::{Group, Rectangle, Title};
+use svg::node::Text;
+
+use crate::io::json_format::{JsonCPNode, JsonCPNodeType, JsonInstance, JsonOrientation};
+use crate::io::parser::convert_layout_to_json_cp;
+use crate::optimization::config::CoordinateOrigin;
+use crate::optimization::solutions::sendable_solution::SendableSolution;
+
+const ITEM_COLOR: &str = "#BFBFBF";
+const LEFTOVER_COLOR: &str = "#A9D18E";
+const STRUCTURE_COLOR: &str = "#DEEBF7";
+const DEFECT_COLOR: &str = "#C00000";
+const WASTE_COLOR: &str = "#7F7F7F";
+
+/// Standalone SVG of all cutting patterns of a solution, stacked below each other in the order of the JSON solution.
+/// Every pattern is a group containing a rectangle per node: structure nodes (blue) underneath their children,
+/// items (gray, labelled with their id), leftovers (green), defects (red) and waste (dark gray).
+/// Nodes without area are not drawn, the stroke of thin nodes is reduced so they remain visible.
+pub fn generate_solution_svg(solution: &SendableSolution, json_instance: &JsonInstance, origin: CoordinateOrigin) -> String {
+    let cutting_patterns = solution.layouts().iter()
+        .sorted_by(|a, b| a.utilization().partial_cmp(&b.utilization()).unwrap().reverse())
+        .map(|layout| convert_layout_to_json_cp(layout, solution.instance(), json_instance))
+        .collect_vec();
+    let max_length = cutting_patterns.iter().map(|cp| cp.root.length).max().unwrap_or(0);
+    let max_height = cutting_patterns.iter().map(|cp| cp.root.height).max().unwrap_or(0);
+    let stroke_width = 0.002 * u64::max(max_length, max_height) as f64;
+    let spacing = 0.05 * max_height as f64;
+
+    let mut document = Document::new();
+    let mut offset_y = 0.0;
+    for (i, json_cp) in cutting_patterns.iter().enumerate() {
+        let sheet_height = json_cp.root.height;
+        let mut group = Group::new()
+            .set("id", format!("pattern_{}", i))
+            .add(Title::new().add(Text::new(format!("Pattern {}: Object {} [{}x{}]", i, json_cp.object, json_cp.root.length, sheet_height))));
+        let mut rects = Vec::new();
+        generate_node(&json_cp.root, (0, 0), &mut rects, stroke_width, origin, sheet_height);
+        for rect in rects {
+            group = group.add(rect);
+        }
+        document = document.add(group.set("transform", format!("translate(0 {})", offset_y)));
+        offset_y += sheet_height as f64 + spacing;
+    }
+    let total_height = f64::max(offset_y - spacing, 0.0);
+    document = document.set("viewBox", (-stroke_width, -stroke_width, max_length as f64 + stroke_width * 2.0, total_height + stroke_width * 2.0));
+
+    document.to_string()
+}
+
+fn generate_node(node: &JsonCPNode, reference: (u64, u64), groups: &mut Vec<Group>, stroke_width: f64, origin: CoordinateOrigin, sheet_height: u64) {
+    if node.length == 0 || node.height == 0 {
+        return;
+    }
+    let color = match (&node.node_type, node.children.is_empty()) {
+        (JsonCPNodeType::Item, _) => ITEM_COLOR,
+        (JsonCPNodeType::Leftover, _) | (JsonCPNodeType::Structure, true) => LEFTOVER_COLOR,
+        (JsonCPNodeType::Structure, false) => STRUCTURE_COLOR,
+        (JsonCPNodeType::Defect, _) => DEFECT_COLOR,
+        (JsonCPNodeType::Waste, _) => WASTE_COLOR,
+    };
+    //The coordinates are rendered top left, the y coordinate is only transformed for the label of the item
+    let (x, y) = (reference.0 as f64, reference.1 as f64);
+    let (width, height) = (node.length as f64, node.height as f64);
+    let node_stroke_width = f64::min(stroke_width, 0.25 * f64::min(width, height));
+    let mut group = Group::new().add(Rectangle::new()
+        .set("x", x)
+        .set("y", y)
+        .set("width", width)
+        .set("height", height)
+        .set("fill", color)
+        .set("stroke", "black")
+        .set("stroke-width", node_stroke_width));
+
+    if let (JsonCPNodeType::Item, Some(item)) = (&node.node_type, node.item) {
+        let font_size = 0.5 * f64::min(width, height);
+        let mut text = svg::node::element::Text::new()
+            .set("x", x + width * 0.5)
+            .set("y", y + height * 0.5)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "middle")
+            .set("font-size", font_size)
+            .set("fill", "black")
+            .add(Text::new(item.to_string()));
+        if node.height > node.length {
+            text = text.set("transform", format!("rotate(-90 {} {})", x + width * 0.5, y + height * 0.5));
+        }
+        let y_origin = origin.transform_y(reference.1, node.height, sheet_height);
+        group = group.add(text).add(Title::new().add(Text::new(format!("{}: [{}x{}] at ({}, {})", item, node.length, node.height, reference.0, y_origin))));
+    }
+    groups.push(group);
+
+    //Children are drawn after (on top of) their parent, subdividing it in the direction of the cut.
+    //Without an orientation the children cannot be positioned, only the parent is drawn
+    let Some(orientation) = &node.orientation else {
+        return;
+    };
+    let mut reference = reference;
+    for child in &node.children {
+        generate_node(child, reference, groups, stroke_width, origin, sheet_height);
+        match orientation {
+            JsonOrientation::H => reference.1 += child.height,
+            JsonOrientation::V => reference.0 += child.length,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::io::json_format::{JsonCPNode, JsonCPNodeType, JsonOrientation};
+    use crate::io::parser::generate_json_solution;
+    use crate::io::svg_export::{generate_node, generate_solution_svg, ITEM_COLOR};
+    use crate::optimization::config::CoordinateOrigin;
+    use crate::util::test_util::{config_path, solve};
+
+    fn count_nodes(node: &JsonCPNode) -> usize {
+        1 + node.children.iter().map(count_nodes).sum::<usize>()
+    }
+
+    #[test]
+    fn two_part_solution_has_a_rect_per_node() {
+        let instance = json!({
+            "Name": "svg",
+            "Objects": [{"Length": 100, "Height": 60, "Stock": 1, "Cost": 100}],
+            "Items": [
+                {"Length": 50, "Height": 60, "Demand": 1, "Value": 1},
+                {"Length": 30, "Height": 40, "Demand": 1, "Value": 1}
+            ]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({}));
+        let svg = generate_solution_svg(&solution, &json_instance, CoordinateOrigin::TopLeft);
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+
+        assert_eq!(json_solution.cutting_patterns.len(), 1);
+        let n_nodes = count_nodes(&json_solution.cutting_patterns[0].root);
+        assert_eq!(svg.matches("<rect").count(), n_nodes);
+        assert_eq!(svg.matches(&format!("fill=\"{}\"", ITEM_COLOR)).count(), 2);
+    }
+
+    #[test]
+    fn children_without_orientation_are_not_drawn() {
+        let leaf = JsonCPNode { id: 1, length: 10, height: 10, orientation: None, node_type: JsonCPNodeType::Item, item: Some(0), pieces: None, rotated: None, children: vec![] };
+        let node = JsonCPNode { id: 0, length: 10, height: 20, orientation: None, node_type: JsonCPNodeType::Structure, item: None, pieces: None, rotated: None, children: vec![leaf.clone(), leaf] };
+        let mut groups = Vec::new();
+        generate_node(&node, (0, 0), &mut groups, 0.1, CoordinateOrigin::TopLeft, 20);
+        assert_eq!(groups.len(), 1);
+
+        let node = JsonCPNode { orientation: Some(JsonOrientation::H), ..node };
+        let mut groups = Vec::new();
+        generate_node(&node, (0, 0), &mut groups, 0.1, CoordinateOrigin::TopLeft, 20);
+        assert_eq!(groups.len(), 3);
+    }
+}
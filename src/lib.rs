@@ -0,0 +1,41 @@
+extern crate alloc;
+
+use std::cmp::Ordering;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+
+use crate::core::{entities::parttype::PartType, orientation::Orientation};
+use crate::core::cost::Cost;
+use crate::core::entities::sheettype::SheetType;
+use crate::core::rotation::Rotation;
+use crate::io::json_format::JsonInstance;
+use crate::optimization::config::Config;
+use crate::optimization::instance::Instance;
+
+//The modules are public on purpose, to give access to the internals (e.g. a `Problem` and its layouts) for experiments.
+//They come without any stability guarantees, the supported API is the prelude.
+pub mod util;
+pub mod io;
+pub mod optimization;
+pub mod core;
+pub mod prelude;
+
+pub static EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
+/// Suppresses all log output of `timed_println!` and `timed_thread_println!` (set from the `quiet` config option by `run_optimization`)
+pub static QUIET: AtomicBool = AtomicBool::new(false);
+pub const COST_COMPARATOR: fn(&Cost, &Cost) -> Ordering = |a: &Cost, b: &Cost| { //其实在比较的时候只用了excluded part area和leftover value.
+    match a.part_area_excluded.cmp(&b.part_area_excluded) {
+        Ordering::Equal => a.leftover_value.partial_cmp(&b.leftover_value).unwrap().reverse(),
+        other => other
+    }
+};
+/// Used with the CutLength objective: solutions which include the same part area are ranked by total cut length, then by material cost
+pub const CUT_LENGTH_COST_COMPARATOR: fn(&Cost, &Cost) -> Ordering = |a: &Cost, b: &Cost| {
+    a.part_area_excluded.cmp(&b.part_area_excluded)
+        .then(a.cut_length.cmp(&b.cut_length))
+        .then(a.material_cost.cmp(&b.material_cost))
+        .then(a.leftover_value.partial_cmp(&b.leftover_value).unwrap().reverse())
+};
+pub const DETERMINISTIC_MODE: bool = false; //fixes seed
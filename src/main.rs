@@ -1,128 +1,172 @@
-use std::{env, thread};
-use std::cmp::Ordering;
+use std::env;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::mpsc::channel;
-use std::time::Instant;
-
-use once_cell::sync::Lazy;
-
-use crate::core::{entities::parttype::PartType, orientation::Orientation};
-use crate::core::cost::Cost;
-use crate::core::entities::sheettype::SheetType;
-use crate::core::rotation::Rotation;
-use crate::io::html_export::generate_solution;
-use crate::io::json_format::JsonInstance;
-use crate::io::parser;
-use crate::optimization::config::Config;
-use crate::optimization::gdrr::GDRR;
-use crate::optimization::instance::Instance;
-use crate::optimization::sol_collectors::global_sol_collector::GlobalSolCollector;
-use crate::optimization::sol_collectors::local_sol_collector::LocalSolCollector;
-use crate::util::macros::timed_println;
-
-pub mod util;
-pub mod io;
-pub mod optimization;
-pub mod core;
-
-static EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
-const COST_COMPARATOR: fn(&Cost, &Cost) -> Ordering = |a: &Cost, b: &Cost| { //其实在比较的时候只用了excluded part area和leftover value.
-    match a.part_area_excluded.cmp(&b.part_area_excluded) {
-        Ordering::Equal => a.leftover_value.partial_cmp(&b.leftover_value).unwrap().reverse(),
-        other => other
-    }
-};
-const DETERMINISTIC_MODE: bool = false; //fixes seed
+
+use gdrr_2bp::prelude::*;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let input_file_path = PathBuf::from(args.get(1).expect("First cmd argument needs to be path to input file"));
-    let config_file_path = PathBuf::from(args.get(2).expect("Second cmd argument needs to be path to config file"));
-    let json_solution_path = match args.len() > 3 {
-        true => Some(PathBuf::from(args.get(3).unwrap())),
-        false => {
-            timed_println!("No JSON solution file path defined, not writing JSON file");
-            None
-        }
-    };
-    let html_solution_path = match args.len() > 4 {
-        true => Some(PathBuf::from(args.get(4).unwrap())),
-        false => {
-            timed_println!("No HTML solution file path defined, not writing HTML file");
-            None
+    let mut args: Vec<String> = env::args().collect();
+    //In interactive mode, the remaining arguments are the input and config file
+    let repl = match args.iter().position(|arg| arg == "--repl") {
+        Some(index) => {
+            args.remove(index);
+            true
         }
+        None => false
     };
+    let input_file_path = PathBuf::from(args.get(1).expect("First cmd argument needs to be path to input file"));
+    //Without a config file, the config has to be embedded in the input file
+    let config_file_path = args.get(2).map(PathBuf::from);
+    let json_solution_path = args.get(3).map(PathBuf::from);
+    let html_solution_path = args.get(4).map(PathBuf::from);
 
     let input_file = File::open(&input_file_path).expect("input file could not be opened");
-    let config_file = File::open(&config_file_path).expect("config file could not be opened");
+    let config_file = config_file_path.as_ref().map(|config_file_path| {
+        let config_file = File::open(config_file_path).expect("config file could not be opened");
+        serde_json::from_reader(BufReader::new(&config_file)).expect("config file could not be parsed")
+    });
 
     let mut json_instance: JsonInstance = serde_json::from_reader(BufReader::new(&input_file)).unwrap();
-    let config: Config = serde_json::from_reader(BufReader::new(&config_file)).unwrap();
+    let config: Config = generate_config(&json_instance, config_file).unwrap_or_else(|err| {
+        eprintln!("Invalid config: {}", err);
+        std::process::exit(1);
+    });
+    let config_file_path = config_file_path.unwrap_or_else(|| input_file_path.clone());
+    if config.quiet.unwrap_or(false) {
+        gdrr_2bp::QUIET.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    if json_solution_path.is_none() {
+        timed_println!("No JSON solution file path defined, not writing JSON file");
+    }
+    if html_solution_path.is_none() {
+        timed_println!("No HTML solution file path defined, not writing HTML file");
+    }
 
     timed_println!("Config file loaded: {}", serde_json::to_string(&config).unwrap());
 
-    let instance = parser::generate_instance(&mut json_instance, &config);
-    timed_println!("Starting optimization of {} parts of {} different types for {} seconds", instance.total_part_qty(), instance.parts().len(), config.max_run_time.unwrap_or(usize::MAX));
-    timed_println!("Press Ctrl+C to terminate manually");
+    if repl {
+        interactive(json_instance, config);
+        return;
+    }
 
+    let instance = generate_instance(&mut json_instance, &config).unwrap_or_else(|err| {
+        eprintln!("Invalid instance: {}", err);
+        std::process::exit(1);
+    });
     let instance = Arc::new(instance);
     let config = Arc::new(config);
 
-    let mut gdrr_thread_handlers = Vec::new();
-
-
-    let mut tx_syncs = Vec::new();
-    let (tx_solution_report, rx_solution_report) = channel();
-
-    for i in 0..config.n_threads {
-        let instance_thread = instance.clone();
-        let config_thread = config.clone();
-        let thread_name = format!("T{}", i);
-        let (tx_sync, rx_sync) = channel();
-        let tx_solution_report_thread = tx_solution_report.clone();
-        tx_syncs.push(tx_sync);
-
+    //Like an invalid config, an unusable output directory is reported before the optimization starts
+    let reporter: Box<dyn Reporter> = match config.incremental_output_dir.as_ref() {
+        Some(output_dir) => match IncrementalOutputReporter::new(Box::new(ConsoleReporter), output_dir.clone(), json_instance.clone(), config.clone(), config_file_path.clone()) {
+            Ok(reporter) => Box::new(reporter),
+            Err(err) => {
+                eprintln!("Invalid config: incremental output directory {} could not be created: {}", output_dir.display(), err);
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(ConsoleReporter)
+    };
 
-        let handle = thread::Builder::new().name(thread_name).spawn(move || {
-            let local_sol_collector = LocalSolCollector::new(instance_thread.clone(), rx_sync, tx_solution_report_thread, COST_COMPARATOR);
-            let mut gdrr = GDRR::new(&instance_thread, &config_thread, local_sol_collector);
-            gdrr.lahc();
+    timed_println!("Starting optimization of {} parts of {} different types for {}", instance.total_part_qty(), instance.parts().len(),
+        match config.run_time_limit() {
+            Some(limit) => format!("{:.3} seconds", limit.as_secs_f64()),
+            None => "an unlimited time".to_string()
         });
-        gdrr_thread_handlers.push(handle.expect("could not spawn thread"));
-    }
-
-    let mut global_sol_collector = GlobalSolCollector::new(instance, config, tx_syncs, rx_solution_report, COST_COMPARATOR);
-
-    global_sol_collector.monitor(gdrr_thread_handlers);
+    timed_println!("Press Ctrl+C to terminate manually");
+    let global_sol_collector = run_optimization(instance, config.clone(), reporter);
 
-    let json_solution = match (global_sol_collector.best_complete_solution().as_ref(), global_sol_collector.best_incomplete_solution().as_ref()) {
-        (Some(best_complete_solution), _) => {
-            Some(parser::generate_json_solution(&json_instance, best_complete_solution, &config_file_path))
-        }
-        (None, Some(best_incomplete_solution)) => {
-            Some(parser::generate_json_solution(&json_instance, best_incomplete_solution, &config_file_path))
-        }
-        (None, None) => {
-            None
-        }
+    let final_solution = match (global_sol_collector.best_complete_solution().as_ref(), global_sol_collector.best_incomplete_solution().as_ref()) {
+        (Some(best_complete_solution), _) => Some(best_complete_solution),
+        (None, Some(best_incomplete_solution)) => Some(best_incomplete_solution),
+        (None, None) => None
     };
+    let json_solution = final_solution.map(|solution| generate_json_solution(&json_instance, solution, &config, &config_file_path));
 
     if json_solution.is_some() {
-        if let Some(json_solution_path) = json_solution_path {
-            let mut json_file = File::create(&json_solution_path).expect("JSON solution file could not be created");
-            serde_json::to_writer_pretty(&mut json_file, json_solution.as_ref().unwrap()).expect("could not write JSON solution");
-            timed_println!("JSON solution written to {}", json_solution_path.display());
+        let solution_output = match (config.solution_output.as_ref(), json_solution_path) {
+            (Some(solution_output), _) => Some(solution_output.clone()),
+            (None, Some(json_solution_path)) => Some(SolutionOutput::File(json_solution_path)),
+            (None, None) => None
+        };
+        if let Some(solution_output) = solution_output {
+            let sink = open_sink(&solution_output).expect("JSON solution output could not be opened");
+            if let Some(mut sink) = sink {
+                let pretty = solution_output != SolutionOutput::Stdout;
+                let limited_json_solution = config.max_json_output_bytes
+                    .map(|max_bytes| limit_json_size(json_solution.clone().unwrap(), max_bytes, pretty));
+                let written_json_solution = limited_json_solution.as_ref().unwrap_or(json_solution.as_ref().unwrap());
+                write_json_solution(written_json_solution, &mut sink, pretty).expect("could not write JSON solution");
+            }
+            match solution_output {
+                SolutionOutput::File(path) => { timed_println!("JSON solution written to {}", path.display()); }
+                SolutionOutput::Stdout => { timed_println!("JSON solution written to stdout"); }
+                SolutionOutput::Null => {}
+            }
         }
         if let Some(html_solution_path) = html_solution_path {
             let mut html_file = File::create(&html_solution_path).expect("HTML solution file could not be created");
-            write!(html_file, "{}", &generate_solution(json_solution.as_ref().unwrap())).expect("could not write HTML solution");
+            write!(html_file, "{}", &generate_html_solution(json_solution.as_ref().unwrap(), config.coordinate_origin)).expect("could not write HTML solution");
             timed_println!("HTML solution written to {}", html_solution_path.display());
         }
     } else {
         timed_println!("No solution available");
     }
+
+    if let (Some(json_solution), Some(markdown_path)) = (json_solution.as_ref(), config.markdown_summary_path.as_ref()) {
+        let mut markdown_file = File::create(markdown_path).expect("Markdown summary file could not be created");
+        write!(markdown_file, "{}", markdown_summary(json_solution, global_sol_collector.best_solution_time(), &config)).expect("could not write Markdown summary");
+        timed_println!("Markdown summary written to {}", markdown_path.display());
+    }
+
+    if let (Some(solution), Some(svg_path)) = (final_solution, config.svg_output_path.as_ref()) {
+        let mut svg_file = File::create(svg_path).expect("SVG file could not be created");
+        write!(svg_file, "{}", generate_solution_svg(solution, &json_instance, config.coordinate_origin)).expect("could not write SVG");
+        timed_println!("SVG solution written to {}", svg_path.display());
+    }
+
+    if let (Some(json_solution), Some(csv_path)) = (json_solution.as_ref(), config.csv_output_path.as_ref()) {
+        let mut csv_file = File::create(csv_path).expect("CSV file could not be created");
+        write!(csv_file, "{}", generate_solution_csv(json_solution, config.coordinate_origin)).expect("could not write CSV");
+        timed_println!("CSV solution written to {}", csv_path.display());
+    }
+
+    if let (Some(json_solution), Some(report_path)) = (json_solution.as_ref(), config.reconciliation_report_path.as_ref()) {
+        let mut report_file = File::create(report_path).expect("reconciliation report file could not be created");
+        serde_json::to_writer_pretty(&mut report_file, &reconciliation_report(json_solution, &config)).expect("could not write reconciliation report");
+        timed_println!("Reconciliation report written to {}", report_path.display());
+    }
+
+    if let Some(baseline_path) = config.regression_baseline.as_ref() {
+        let baseline_file = File::open(baseline_path).expect("regression baseline file could not be opened");
+        let baseline: JsonSolution = serde_json::from_reader(BufReader::new(baseline_file)).expect("regression baseline could not be parsed");
+        let result = match final_solution {
+            Some(solution) => regression_check(solution, &baseline, config.regression_tolerance_pct.unwrap_or(0.0)),
+            None => RegressionResult::PartAreaRegression { current_pct: 0.0, baseline_pct: baseline.statistics.part_area_included_pct as f64 }
+        };
+        timed_println!("Regression check against {}: {}", baseline_path.display(), result);
+        if result.is_regression() {
+            std::process::exit(1);
+        }
+    }
+
+    //The solutions of the failed threads have been written, but the run is still reported as failed
+    if !global_sol_collector.failed_workers().is_empty() {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(feature = "repl")]
+fn interactive(json_instance: JsonInstance, config: Config) {
+    let stdin = std::io::stdin();
+    run_repl(json_instance, config, &mut stdin.lock(), &mut std::io::stdout()).expect("interactive mode failed");
+}
+
+#[cfg(not(feature = "repl"))]
+fn interactive(_json_instance: JsonInstance, _config: Config) {
+    eprintln!("interactive mode is not available, build with the `repl` feature");
+    std::process::exit(1);
 }
@@ -1,13 +1,24 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::core::cost::Cost;
+use crate::optimization::instance::Instance;
+use crate::optimization::rr::ruin_controller::MIN_AVG_NODES_REMOVED;
+use crate::optimization::sol_collectors::global_sol_collector::{DEFAULT_MONITOR_INTERVAL_MS, DEFAULT_REPORT_CHANNEL_CAPACITY};
 
 /// Contains all the configurable parameters of the algorithm
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
     pub avg_nodes_removed: usize,
     pub blink_rate: f32,
     pub max_run_time: Option<usize>,
+    /// Maximum run time in milliseconds, takes precedence over max_run_time (seconds)
+    pub max_run_time_ms: Option<u64>,
     #[serde(rename = "maxRRIterations")]
     pub max_rr_iterations: Option<usize>,
     pub leftover_valuation_power: f32,
@@ -15,11 +26,470 @@ pub struct Config {
     pub rotation_allowed: bool,
     pub n_threads: usize,
     pub sheet_valuation_mode : SheetValuationMode,
+    /// Replenishments of sheet stock arriving at or before this point in time are considered available (default: 0)
+    pub stock_horizon: Option<u64>,
+    #[serde(default)]
+    pub packing_bias: PackingBias,
+    /// Number of identical sheets which are stacked and cut together (default: 1)
+    pub book_height: Option<usize>,
+    #[serde(default)]
+    pub initial_heuristic: InitialHeuristic,
+    /// A GDRR thread which has not found an improvement for this long restarts from its initial solution
+    pub worker_restart_stagnation_ms: Option<u64>,
+    /// Restart stagnated GDRR threads from the best complete solution of all threads instead of their initial solution (default: false)
+    pub worker_restart_from_incumbent: Option<bool>,
+    /// Layouts with a lower utilization than this fraction are not considered to produce their parts
+    pub min_pattern_utilization: Option<f64>,
+    #[serde(default)]
+    pub coordinate_origin: CoordinateOrigin,
+    /// Path to a previously generated JSON solution against which the final solution is compared
+    pub regression_baseline: Option<PathBuf>,
+    /// Allowed increase of material cost relative to the regression baseline, in percent (default: 0)
+    pub regression_tolerance_pct: Option<f64>,
+    /// Include a list of cuts, grouped by guillotine stage, in every cutting pattern of the JSON solution (default: false)
+    pub export_cut_plan: Option<bool>,
+    /// Export the recommended order in which to cut the patterns, based on the due dates of the items (default: false)
+    pub export_cutting_sequence: Option<bool>,
+    /// Merge sheettypes with identical dimensions and cost into a single sheettype, summing their stock (default: false)
+    pub coalesce_sheet_types: Option<bool>,
+    /// Seeds the material limits of the workers (except the first) at increasing steps of this percentage above an estimated lower bound
+    pub staggered_material_limit_pct: Option<f64>,
+    /// Leftovers with a higher ratio between their longest and shortest side are valued as waste
+    pub max_leftover_aspect_ratio: Option<f64>,
+    /// Only open new layouts for a part if it cannot be inserted in any of the existing layouts (default: true)
+    pub prefer_existing_layouts: Option<bool>,
+    /// Amount of waste area per layout which is accepted without penalty in the reported usage
+    pub scrap_allowance_area: Option<u64>,
+    /// Path to write a Markdown summary of the run to at termination
+    pub markdown_summary_path: Option<PathBuf>,
+    /// Maximum number of solution reports waiting to be processed by the global collector
+    pub report_channel_capacity: Option<usize>,
+    /// Minimum distance required between parts (e.g. for handling), independent of the cuts
+    pub part_spacing: Option<u64>,
+    /// Maximum number of sheets a solution may use, regardless of the available stock
+    pub fixed_sheet_count: Option<usize>,
+    /// Terminate as soon as the first complete solution is found
+    pub stop_on_first_complete: Option<bool>,
+    /// Defines which cutting patterns are considered identical when counting the distinct patterns
+    #[serde(default)]
+    pub pattern_hash: HashConfig,
+    /// Adapt the average number of removed nodes to the progress of the search (default: false)
+    pub adaptive_ruin: Option<bool>,
+    /// Bounds of the average number of removed nodes with adaptive ruin (default: 3 and 2 * avg_nodes_removed)
+    pub adaptive_ruin_min_nodes: Option<usize>,
+    pub adaptive_ruin_max_nodes: Option<usize>,
+    /// Directory to which every new best complete solution is written during the optimization
+    pub incremental_output_dir: Option<PathBuf>,
+    /// Fraction of the threads dedicated to exploration, the others exploit (default: no roles, all threads use the same parameters)
+    pub exploration_ratio: Option<f64>,
+    /// Layouts which have not been changed for this many iterations are removed entirely, forcing their parts to be consolidated elsewhere
+    pub max_layout_age: Option<usize>,
+    /// Path to write a JSON report reconciling the demand and stock with the final solution to
+    pub reconciliation_report_path: Option<PathBuf>,
+    /// Of two complete solutions which tie on all metrics, keep the one with the lexicographically smaller structure (default: false)
+    pub deterministic_tiebreak_hash: Option<bool>,
+    /// Only reinsert the parts released by the last ruin, instead of all parts which are not included (default: false)
+    pub focused_recreate: Option<bool>,
+    /// Secondary objective by which solutions using the same material are ranked (default: material)
+    #[serde(default)]
+    pub objective: Objective,
+    /// Terminate once no complete solution was found, but an incomplete one includes this fraction of the part area and all of its excluded parts are out of stock
+    pub min_completion_fraction: Option<f64>,
+    /// Interval in milliseconds at which every worker reports its current best cost, and the monitor prints them (default: disabled)
+    pub worker_status_interval_ms: Option<u64>,
+    /// Instances with more parts to place in total are rejected (default: no maximum)
+    pub max_total_parts: Option<usize>,
+    /// Probability of inserting a part with a random blueprint instead of the best one (default: 0)
+    pub exploration_epsilon: Option<f32>,
+    /// Terminate as soon as a complete solution reaches this usage, in percent
+    pub target_usage_pct: Option<f64>,
+    /// Layouts with at least this utilization (between 0 and 1) are not selected for the removal of nodes (default: none are frozen)
+    pub freeze_utilization_threshold: Option<f64>,
+    /// Fraction of the run time dedicated to finding a first complete solution, before improving it (default: no separate construction phase)
+    pub construction_budget_fraction: Option<f64>,
+    /// Where the JSON solution is written, takes precedence over the path given on the command line
+    pub solution_output: Option<SolutionOutput>,
+    /// Minimum decrease of the excluded part area for a new best incomplete solution to be reported (default: every improvement is reported)
+    pub min_report_improvement: Option<ReportImprovement>,
+    /// Allows placing more copies of an item than its demand (default: false)
+    pub overproduction_allowed: Option<bool>,
+    /// Maximum number of copies of an item placed on top of its demand when overproduction is allowed (default: 1)
+    pub max_overproduction: Option<usize>,
+    /// Maximum size in bytes of the JSON solution output, larger solutions are truncated to their largest cutting patterns (default: no maximum)
+    pub max_json_output_bytes: Option<usize>,
+    /// Preset for the parameters of the search which are not explicitly configured, see `Effort::preset`
+    pub effort: Option<Effort>,
+    /// Suppresses all log output, only the configured outputs (JSON, HTML, reports) are written (default: false)
+    /// Applied through the process-wide `QUIET` flag when a run starts: concurrent runs in the same process share it, the last one started decides.
+    pub quiet: Option<bool>,
+    /// Seed for the random decisions of the workers, worker `i` uses `seed + i` (default: random).
+    /// Runs are only reproducible with a single thread and an iteration limit instead of a time limit.
+    pub seed: Option<u64>,
+    /// Path to write an SVG drawing of all cutting patterns of the final solution to
+    pub svg_output_path: Option<PathBuf>,
+    /// Width of the material lost to the saw blade in every cut (default: 0)
+    pub kerf: Option<u64>,
+    /// Maximum number of guillotine stages in which every part has to be cut out of the sheet (default: unlimited).
+    /// Only cuts which divide a node count as a stage, a flip of the cut orientation without a cut does not (see `Layout::node_stage`)
+    pub max_stages: Option<usize>,
+    /// Path to write a CSV file with one row per item placed in the final solution to
+    pub csv_output_path: Option<PathBuf>,
+    /// Number of best distinct complete solutions to keep as alternatives (default: 0)
+    pub solution_pool_size: Option<usize>,
+    /// Memory in megabytes which the solution pool may take up, it keeps fewer solutions than `solution_pool_size` if they would not fit (default: unlimited)
+    pub memory_budget_mb: Option<f64>,
+    /// Interval in milliseconds at which the monitor processes the reports of the GDRR threads (default: 10)
+    pub monitor_interval_ms: Option<u64>,
+    /// Minimum time in milliseconds between two printed incomplete improvements, the ones in between are coalesced (default: disabled)
+    pub report_throttle_ms: Option<u64>,
+    /// The optimization stops when no new best complete solution has been found for this long, in milliseconds (default: disabled)
+    pub max_stagnation_time_ms: Option<u64>,
+    /// How the cut orientations of the nodes in the exported cutting patterns relate to those of their parents
+    #[serde(default)]
+    pub cut_pattern: CutPattern,
+}
+
+impl Config {
+    /// The maximum run time of the algorithm, if any
+    pub fn run_time_limit(&self) -> Option<Duration> {
+        match (self.max_run_time_ms, self.max_run_time) {
+            (Some(ms), _) => Some(Duration::from_millis(ms)),
+            (None, Some(s)) => Some(Duration::from_secs(s as u64)),
+            (None, None) => None
+        }
+    }
+
+    /// The config as it is applied by the algorithm: options left out of the config are set to the default they fall back to.
+    /// Options without a default value (limits, output paths, ...) remain disabled.
+    pub fn effective(&self) -> Config {
+        Config {
+            stock_horizon: Some(self.stock_horizon.unwrap_or(0)),
+            book_height: Some(self.book_height.unwrap_or(1)),
+            min_pattern_utilization: Some(self.min_pattern_utilization.unwrap_or(0.0)),
+            regression_tolerance_pct: Some(self.regression_tolerance_pct.unwrap_or(0.0)),
+            export_cut_plan: Some(self.export_cut_plan.unwrap_or(false)),
+            export_cutting_sequence: Some(self.export_cutting_sequence.unwrap_or(false)),
+            coalesce_sheet_types: Some(self.coalesce_sheet_types.unwrap_or(false)),
+            prefer_existing_layouts: Some(self.prefer_existing_layouts.unwrap_or(true)),
+            report_channel_capacity: Some(self.report_channel_capacity.unwrap_or(DEFAULT_REPORT_CHANNEL_CAPACITY)),
+            part_spacing: Some(self.part_spacing.unwrap_or(0)),
+            stop_on_first_complete: Some(self.stop_on_first_complete.unwrap_or(false)),
+            adaptive_ruin: Some(self.adaptive_ruin.unwrap_or(false)),
+            adaptive_ruin_min_nodes: Some(self.adaptive_ruin_min_nodes.unwrap_or(MIN_AVG_NODES_REMOVED)),
+            adaptive_ruin_max_nodes: Some(self.adaptive_ruin_max_nodes.unwrap_or(self.avg_nodes_removed * 2)),
+            deterministic_tiebreak_hash: Some(self.deterministic_tiebreak_hash.unwrap_or(false)),
+            focused_recreate: Some(self.focused_recreate.unwrap_or(false)),
+            overproduction_allowed: Some(self.overproduction_allowed.unwrap_or(false)),
+            max_overproduction: Some(self.max_overproduction.unwrap_or(1)),
+            quiet: Some(self.quiet.unwrap_or(false)),
+            kerf: Some(self.kerf.unwrap_or(0)),
+            solution_pool_size: Some(self.solution_pool_size.unwrap_or(0)),
+            monitor_interval_ms: Some(self.monitor_interval_ms.unwrap_or(DEFAULT_MONITOR_INTERVAL_MS)),
+            ..self.clone()
+        }
+    }
+
+    /// Comparator of solution costs corresponding to the configured objective
+    pub fn cost_comparator(&self) -> fn(&Cost, &Cost) -> Ordering {
+        match self.objective {
+            Objective::Material => crate::COST_COMPARATOR,
+            Objective::CutLength => crate::CUT_LENGTH_COST_COMPARATOR,
+        }
+    }
+
+    /// Role of a worker (GDRR thread), based on its index and the exploration ratio.
+    /// The last `round(nThreads * explorationRatio)` workers explore, the others exploit.
+    pub fn worker_role(&self, worker_index: usize) -> Option<WorkerRole> {
+        self.exploration_ratio.map(|ratio| {
+            let n_explorers = (self.n_threads as f64 * ratio.clamp(0.0, 1.0)).round() as usize;
+            match worker_index >= self.n_threads - n_explorers {
+                true => WorkerRole::Exploration,
+                false => WorkerRole::Exploitation
+            }
+        })
+    }
+
+    /// Config of a single worker, with the parameters adjusted to its role
+    pub fn for_worker(&self, worker_index: usize) -> Config {
+        let config = Config {
+            seed: self.seed.map(|seed| seed.wrapping_add(worker_index as u64)),
+            ..self.clone()
+        };
+        match config.worker_role(worker_index) {
+            None => config,
+            Some(WorkerRole::Exploitation) => Config {
+                history_length: usize::max(config.history_length / EXPLOITATION_HISTORY_DIVISOR, 1),
+                ..config
+            },
+            Some(WorkerRole::Exploration) => Config {
+                history_length: config.history_length * EXPLORATION_HISTORY_FACTOR,
+                avg_nodes_removed: config.avg_nodes_removed + config.avg_nodes_removed / 2,
+                ..config
+            },
+        }
+    }
+
+    /// The material limit a worker starts with, if any.
+    /// This is a heuristic to diversify the early search: with staggered seeding, worker `i` (i > 0) starts with a limit of
+    /// (1 + i * staggeredMaterialLimitPct/100) times the material required to cover the total part area with the cheapest sheettype (per area).
+    /// The first worker is never limited, so it can always find the solution closest to complete.
+    pub fn initial_material_limit(&self, instance: &Instance, worker_index: usize) -> Option<u64> {
+        match (self.staggered_material_limit_pct, worker_index) {
+            (None, _) | (_, 0) => None,
+            (Some(pct), i) => {
+                let min_value_per_area = instance.sheets().iter()
+                    .filter(|(_, qty)| *qty > 0)
+                    .map(|(sheettype, _)| sheettype.value() as f64 / sheettype.area() as f64)
+                    .fold(f64::INFINITY, f64::min);
+                let estimate = instance.total_part_area() as f64 * min_value_per_area;
+                Some((estimate * (1.0 + pct / 100.0 * i as f64)).ceil() as u64)
+            }
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum SheetValuationMode {
     Area,
     Cost
-}
\ No newline at end of file
+}
+
+/// Defines towards which corner of a sheet parts are preferably packed, when insertion costs are tied.
+/// Coordinates are relative to the top left corner of the sheet, the first child of a node is placed closest to it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PackingBias {
+    BottomLeft,
+    TopLeft,
+    #[default]
+    None,
+}
+
+/// Objective by which the solutions are ranked, in addition to the material limit which is lowered with every complete solution
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Objective {
+    /// Maximize the value of the leftovers
+    #[default]
+    Material,
+    /// Minimize the total length of the guillotine cuts, for cutting processes whose cost is proportional to it (e.g. laser or plasma)
+    CutLength,
+}
+
+/// Minimum improvement of the excluded part area, either absolute or as a fraction of the total part area
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportImprovement {
+    Absolute(u64),
+    Fraction(f64),
+}
+
+/// Preset trading off run time and solution quality, for users who do not want to tune the individual parameters
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Effort {
+    Quick,
+    Balanced,
+    Thorough,
+}
+
+impl Effort {
+    /// Values (JSON config fields) of the parameters of the search implied by the preset.
+    /// Only fields missing from the config are filled in from the preset, explicitly configured ones take precedence.
+    pub fn preset(&self) -> serde_json::Map<String, serde_json::Value> {
+        let n_cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let preset = match self {
+            Effort::Quick => serde_json::json!({
+                "maxRunTime": 10,
+                "nThreads": 1,
+                "avgNodesRemoved": 4,
+                "blinkRate": 0.02,
+                "leftoverValuationPower": 2,
+                "historyLength": 200,
+                "initialHeuristic": "decreasingAreaFirstFit",
+            }),
+            Effort::Balanced => serde_json::json!({
+                "maxRunTime": 60,
+                "nThreads": usize::max(1, n_cores / 2),
+                "avgNodesRemoved": 6,
+                "blinkRate": 0.01,
+                "leftoverValuationPower": 2,
+                "historyLength": 500,
+                "explorationRatio": 0.25,
+                "workerRestartStagnationMs": 20000,
+            }),
+            Effort::Thorough => serde_json::json!({
+                "maxRunTime": 300,
+                "nThreads": n_cores,
+                "avgNodesRemoved": 8,
+                "blinkRate": 0.01,
+                "leftoverValuationPower": 2,
+                "historyLength": 1000,
+                "explorationRatio": 0.5,
+                "explorationEpsilon": 0.02,
+                "adaptiveRuin": true,
+                "workerRestartStagnationMs": 60000,
+            }),
+        };
+        match preset {
+            serde_json::Value::Object(preset) => preset,
+            _ => unreachable!()
+        }
+    }
+}
+
+/// Sink of the JSON solution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SolutionOutput {
+    File(PathBuf),
+    /// Written on a single line to standard output, for streaming into other tools
+    Stdout,
+    /// Not written at all
+    Null,
+}
+
+/// Exploiting workers accept less deteriorations, by using a shorter late acceptance history
+const EXPLOITATION_HISTORY_DIVISOR: usize = 10;
+/// Exploring workers use a longer late acceptance history and remove 50% more nodes per iteration
+const EXPLORATION_HISTORY_FACTOR: usize = 2;
+
+/// Role of a GDRR thread within the pool of workers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkerRole {
+    /// Greedy search around the best solutions: tight acceptance
+    Exploitation,
+    /// Diversifying search: loose acceptance and stronger ruin
+    Exploration,
+}
+
+/// Heuristic used to construct the starting solution of every GDRR thread
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum InitialHeuristic {
+    DecreasingAreaFirstFit,
+    /// Start from an empty solution
+    #[default]
+    None,
+}
+/// Attributes of the nodes which are considered when comparing the structure of cutting patterns
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HashConfig {
+    pub dimensions: bool,
+    /// Dimensions are rounded to a multiple of this value, so negligible differences are ignored
+    pub dimension_precision: u64,
+    pub orientation: bool,
+    pub part_ids: bool,
+    /// If false, leftover nodes and their subdivision are ignored
+    pub leftovers: bool,
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        Self {
+            dimensions: true,
+            dimension_precision: 1,
+            orientation: true,
+            part_ids: true,
+            leftovers: true,
+        }
+    }
+}
+
+/// Relation between the cut orientation of a node and that of its parent in the exported cutting pattern trees
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum CutPattern {
+    /// The orientation flips at every level of the tree
+    #[default]
+    StrictAlternating,
+    /// Consecutive cuts in the same orientation within a stage are siblings, levels which would only keep the alternation are left out.
+    /// A node can have the same orientation as its parent
+    FreeWithinStage,
+}
+
+/// Corner of the sheet relative to which absolute coordinates are expressed in the output
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum CoordinateOrigin {
+    BottomLeft,
+    #[default]
+    TopLeft,
+}
+
+impl CoordinateOrigin {
+    /// Converts the y coordinate of a rectangle, relative to the top left corner of the sheet, to this origin
+    pub fn transform_y(&self, y: u64, height: u64, sheet_height: u64) -> u64 {
+        match self {
+            CoordinateOrigin::TopLeft => y,
+            CoordinateOrigin::BottomLeft => sheet_height - y - height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::core::cost::Cost;
+    use crate::optimization::config::{Config, WorkerRole};
+    use crate::util::test_util::{parse, test_config};
+
+    fn config(overrides: serde_json::Value) -> Config {
+        serde_json::from_value(test_config(overrides)).unwrap()
+    }
+
+    #[test]
+    fn cut_length_objective_orders_equal_material_solutions_by_cut_length() {
+        let short_cuts = Cost::new(100, 5.0, 0, 50, 30);
+        let long_cuts = Cost::new(100, 8.0, 0, 50, 40);
+        assert_eq!(config(json!({"objective": "cutLength"})).cost_comparator()(&short_cuts, &long_cuts), Ordering::Less);
+        //The material objective ranks them by the value of their leftovers instead
+        assert_eq!(config(json!({})).cost_comparator()(&short_cuts, &long_cuts), Ordering::Greater);
+    }
+
+    #[test]
+    fn run_time_limit_in_milliseconds_takes_precedence() {
+        assert_eq!(config(json!({"maxRunTime": 2})).run_time_limit(), Some(Duration::from_secs(2)));
+        assert_eq!(config(json!({"maxRunTime": 2, "maxRunTimeMs": 200})).run_time_limit(), Some(Duration::from_millis(200)));
+        assert_eq!(config(json!({})).run_time_limit(), None);
+    }
+
+    #[test]
+    fn staggered_seeding_gives_workers_increasing_material_limits() {
+        let limits = |overrides: serde_json::Value| {
+            let (_, instance, config) = parse(json!({
+                "Name": "staggered",
+                "Objects": [{"Length": 100, "Height": 100, "Stock": 4, "Cost": 100}],
+                "Items": [{"Length": 50, "Height": 50, "Demand": 4, "Value": 1}]
+            }), overrides);
+            (0..3).map(|i| config.initial_material_limit(&instance, i)).collect::<Vec<_>>()
+        };
+        //The sheets are valued by their area, so the lower bound estimate equals the total part area.
+        //A solution using 11500 is within the initial limit of the third worker, but not of the second.
+        assert_eq!(limits(json!({"nThreads": 3, "staggeredMaterialLimitPct": 10})), vec![None, Some(11000), Some(12000)]);
+        assert_eq!(limits(json!({"nThreads": 3})), vec![None, None, None]);
+    }
+
+    #[test]
+    fn half_of_the_workers_explore_with_an_even_split() {
+        let split = config(json!({"nThreads": 4, "explorationRatio": 0.5, "historyLength": 100, "avgNodesRemoved": 6}));
+        let roles = (0..4).map(|i| split.worker_role(i)).collect::<Vec<_>>();
+        assert_eq!(roles, vec![Some(WorkerRole::Exploitation), Some(WorkerRole::Exploitation), Some(WorkerRole::Exploration), Some(WorkerRole::Exploration)]);
+
+        let workers = (0..4).map(|i| split.for_worker(i)).collect::<Vec<_>>();
+        for greedy in &workers[..2] {
+            assert_eq!((greedy.history_length, greedy.avg_nodes_removed), (10, 6));
+        }
+        for exploratory in &workers[2..] {
+            assert_eq!((exploratory.history_length, exploratory.avg_nodes_removed), (200, 9));
+        }
+        //Without a ratio, all workers use the configured parameters
+        assert!((0..4).all(|i| config(json!({"nThreads": 4})).for_worker(i).history_length == 100));
+    }
+}
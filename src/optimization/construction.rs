@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+
+use itertools::Itertools;
+
+use crate::core::cost::Cost;
+use crate::core::layout_index::LayoutIndex;
+use crate::optimization::problem::Problem;
+use crate::optimization::rr::insertion_option_cache::InsertionOptionCache;
+
+/// Constructive heuristic to generate a starting solution before the ruin and recreate phase.
+/// Decreasing area first fit:
+/// The parts are inserted one by one, largest first.
+/// Every part is inserted into the first existing layout in which it fits (in the smallest fitting empty node, within the stage limit).
+/// If it does not fit in any existing layout, a new layout is opened from the first available sheettype in which it fits.
+/// Parts which do not fit anywhere are left out.
+/// Only the demand of the parts is inserted, allowed overproduction is left for the ruin and recreate phase.
+pub fn decreasing_area_first_fit(problem: &mut Problem, cost_comparator: fn(&Cost, &Cost) -> Ordering) {
+    let instance = problem.instance();
+    let sorted_parttypes = instance.parts().iter()
+        .map(|(parttype, _)| parttype)
+        .sorted_by(|a, b| a.area().cmp(&b.area()).reverse())
+        .collect_vec();
+
+    for parttype in sorted_parttypes {
+        while problem.parttype_qtys()[parttype.id()] > instance.get_parttype_overproduction(parttype.id()) {
+            let existing_layouts = problem.layouts().iter()
+                .map(|(i, l)| (LayoutIndex::Existing(i), l));
+            let empty_layouts = problem.empty_layouts().iter().enumerate()
+                .filter(|(_, l)| problem.empty_layout_available(l))
+                .map(|(i, l)| (LayoutIndex::Empty(i), l));
+
+            //First layout with an empty node in which the part can be inserted, the smallest such node is chosen
+            let blueprint = existing_layouts.chain(empty_layouts)
+                .filter(|(_, layout)| layout.accepts_part(parttype))
+                .find_map(|(layout_i, layout)| {
+                    layout.sorted_empty_nodes().iter().rev().find_map(|node_i| {
+                        let node = &layout.nodes()[*node_i];
+                        InsertionOptionCache::generate_insertion_option(node, parttype, layout_i, *node_i, layout.sheettype())
+                            .and_then(|option| {
+                                //A stage limit can rule out all blueprints of an option
+                                option.generate_blueprints(problem).into_iter()
+                                    .min_by(|a, b| cost_comparator(a.cost(), b.cost()))
+                            })
+                    })
+                });
+
+            match blueprint {
+                Some(blueprint) => {
+                    problem.implement_insertion_blueprint(&blueprint);
+                }
+                None => {
+                    //part does not fit anywhere
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::core::leftover_valuator;
+    use crate::optimization::construction::decreasing_area_first_fit;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::util::test_util::{parse, solve};
+
+    #[test]
+    fn largest_parts_are_placed_first() {
+        let (_, instance, config) = parse(json!({
+            "Name": "construction",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [
+                {"Length": 40, "Height": 40, "Demand": 3, "Value": 1},
+                {"Length": 70, "Height": 70, "Demand": 1, "Value": 1},
+                {"Length": 30, "Height": 30, "Demand": 2, "Value": 1}
+            ]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let mut problem = Problem::new(&instance, &config);
+        decreasing_area_first_fit(&mut problem, config.cost_comparator());
+
+        //Next to the largest part, only the smallest ones fit on the single sheet
+        assert_eq!(problem.layouts().len(), 1);
+        assert_eq!(problem.parttype_qtys(), &vec![3, 0, 0]);
+        assert!(problem.usage() > 0.0);
+    }
+
+    #[test]
+    fn initial_solution_is_reported_before_any_iteration() {
+        let instance = json!({
+            "Name": "construction",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 2, "Cost": 100}],
+            "Items": [{"Length": 70, "Height": 70, "Demand": 2, "Value": 1}, {"Length": 30, "Height": 30, "Demand": 2, "Value": 1}]
+        });
+        let (_, _, _, solution) = solve(instance, json!({"initialHeuristic": "decreasingAreaFirstFit", "maxRRIterations": 0}));
+        assert!(solution.is_complete());
+        assert!(solution.usage() > 0.0);
+    }
+}
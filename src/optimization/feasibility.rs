@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use crate::optimization::config::Config;
+use crate::optimization::instance::Instance;
+use crate::optimization::optimize::run_optimization;
+use crate::optimization::sol_collectors::reporter::ConsoleReporter;
+
+/// Decides whether all parts of the instance can be produced using at most `k` sheets.
+/// The solver runs with a `fixedSheetCount` of `k` until the first complete solution is found, or the run time limit of the config is reached.
+/// Being a heuristic, `false` means that no such solution was found within the limit, not that none exists.
+pub fn feasible_in(instance: Arc<Instance>, config: &Config, k: usize) -> bool {
+    let config = Arc::new(Config {
+        fixed_sheet_count: Some(k),
+        stop_on_first_complete: Some(true),
+        ..config.clone()
+    });
+
+    run_optimization(instance, config, Box::new(ConsoleReporter)).best_complete_solution().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::optimization::feasibility::feasible_in;
+    use crate::util::test_util::parse;
+
+    #[test]
+    fn feasibility_depends_on_the_number_of_sheets() {
+        //Two parts, each one only fitting on a sheet of its own
+        let instance = json!({
+            "Name": "feasibility",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 2, "Value": 1}]
+        });
+        let (_, instance, config) = parse(instance, json!({}));
+        assert!(feasible_in(instance.clone(), &config, 2));
+        assert!(!feasible_in(instance, &config, 1));
+    }
+}
@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use colored::*;
 use itertools::Itertools;
@@ -7,22 +7,26 @@ use ordered_float::NotNan;
 use rand::prelude::SliceRandom;
 use rand::Rng;
 use rand::rngs::SmallRng;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::{Instance, PartType};
 use crate::core::cost::Cost;
 use crate::core::insertion::insertion_blueprint::InsertionBlueprint;
 use crate::core::layout_index::LayoutIndex;
 use crate::core::leftover_valuator;
-use crate::optimization::config::Config;
+use crate::optimization::config::{Config, InitialHeuristic, PackingBias};
+use crate::optimization::construction;
 use crate::optimization::problem::Problem;
+use crate::optimization::rr::blueprint_buffers::BlueprintBuffers;
 use crate::optimization::rr::insertion_option_cache::InsertionOptionCache;
+use crate::optimization::rr::ruin_controller::RuinController;
 use crate::optimization::sol_collectors::local_sol_collector::LocalSolCollector;
 use crate::optimization::solutions::problem_solution::ProblemSolution;
 use crate::optimization::solutions::solution::Solution;
 use crate::util::{assertions, blink};
 use crate::util::biased_sampler::{BiasedSampler, BiasMode};
 use crate::util::macros::timed_thread_println;
+use crate::util::messages::WorkerStatus;
 use crate::util::util;
 
 /// Goal-Driven Ruin and Recreate algorithm
@@ -33,21 +37,32 @@ pub struct GDRR<'a> {
     problem: Problem<'a>,
     cost_comparator: fn(&Cost, &Cost) -> Ordering,
     local_sol_collector: LocalSolCollector<'a>,
+    ruin_controller: RuinController,
+    /// Iteration in which every layout (by id) was last changed by an accepted iteration
+    layout_last_change: HashMap<usize, usize>,
+    blueprint_buffers: BlueprintBuffers<'a>,
 }
 
 
 impl<'a> GDRR<'a> {
     pub fn new(instance: &'a Instance, config: &'a Config, local_sol_collector: LocalSolCollector<'a>) -> Self {
-        let problem = Problem::new(instance);
+        let problem = match config.seed {
+            Some(seed) => Problem::new_seeded(instance, config, seed),
+            None => Problem::new(instance, config)
+        };
         //problem 和 instance 其实是不一样的，problem是包含了一些layout的，problem更适合一般化问题
         leftover_valuator::set_power(config.leftover_valuation_power);
-        let cost_comparator = crate::COST_COMPARATOR;
+        leftover_valuator::set_max_aspect_ratio(config.max_leftover_aspect_ratio);
+        let cost_comparator = config.cost_comparator();
         Self {
             config,
             instance,
             problem,
             cost_comparator,
             local_sol_collector,
+            ruin_controller: RuinController::new(config),
+            layout_last_change: HashMap::new(),
+            blueprint_buffers: BlueprintBuffers::new(),
         }
     }
 
@@ -57,7 +72,7 @@ impl<'a> GDRR<'a> {
 
         let max_rr_iterations = self.config.max_rr_iterations.unwrap_or(usize::MAX);
 
-        let empty_problem_cost = Cost::new(0, 0.0, self.instance.total_part_area(), 0);
+        let empty_problem_cost = Cost::new(0, 0.0, self.instance.total_part_area(), 0, 0);
 
         let mut lahc_history: VecDeque<Cost> = VecDeque::with_capacity(self.config.history_length);
         //这里用了一个双端队列来维护历史记录中的cost，注意lahc history只记录了历史的cost
@@ -66,7 +81,13 @@ impl<'a> GDRR<'a> {
         let mut n_accepted = 0;
         let mut n_improved = 0;
         let mut mat_limit = self.local_sol_collector.material_limit();
-        let mut local_optimum: Option<ProblemSolution> = None;
+        let mut local_optimum: Option<ProblemSolution> = self.construct_initial_solution();
+        let worker_restart_stagnation = self.config.worker_restart_stagnation_ms.map(Duration::from_millis);
+        let mut last_improvement = Instant::now();
+        let mut n_restarts = 0;
+        let mut n_iterations_since_restart = 0;
+        let status_interval = self.config.worker_status_interval_ms.map(Duration::from_millis);
+        let mut last_status = Instant::now();
 
         while n_iterations < max_rr_iterations && !self.local_sol_collector.terminate() {
             let mat_limit_budget: i128 = match local_optimum.as_ref() {
@@ -75,7 +96,7 @@ impl<'a> GDRR<'a> {
             };
             //mat_limit_budget <0 说明最优解或者当前的problem已经比限制能用的material要多了
 
-            let mat_limit_budget = self.ruin(mat_limit_budget);
+            let mat_limit_budget = self.ruin(mat_limit_budget, n_iterations);
             let max_part_area_not_included = match local_optimum.as_ref() { //返回最大的没有被include的面积
                 Some(local_optimum) => u64::max(lahc_history.front().unwrap().part_area_excluded, local_optimum.cost().part_area_excluded),
                 None => lahc_history.front().unwrap().part_area_excluded
@@ -85,9 +106,17 @@ impl<'a> GDRR<'a> {
 
             let cost = self.problem.cost();
 
+            #[cfg(feature = "trace-moves")]
+            GDRR::trace_move(n_iterations, &cost, lahc_history.front().unwrap(), local_optimum.as_ref().map(|s| s.cost()), self.cost_comparator);
+
             if (self.cost_comparator)(&cost, lahc_history.front().unwrap()) <= Ordering::Equal ||
                 (local_optimum.is_some() && (self.cost_comparator)(&cost, local_optimum.as_ref().unwrap().cost()) <= Ordering::Equal) {
                 //Solution is better or equivalent to the last entry in the history queue or the local optimum.
+                if self.config.max_layout_age.is_some() {
+                    for layout_id in self.problem.changed_layouts() {
+                        self.layout_last_change.insert(*layout_id, n_iterations);
+                    }
+                }
 
                 local_optimum = Some(self.problem.create_solution(&local_optimum, Some(cost.clone())));
 
@@ -100,6 +129,7 @@ impl<'a> GDRR<'a> {
                     }
                     self.local_sol_collector.report_problem_solution(local_optimum.as_ref().unwrap());
                     n_improved += 1;
+                    last_improvement = Instant::now();
                 } else {
                     //Current local optimum is not better, add the best cost to the history queue
                     for _ in 0..(self.config.history_length - lahc_history.len()) {
@@ -108,8 +138,10 @@ impl<'a> GDRR<'a> {
                     }
                 }
                 n_accepted += 1;
+                self.ruin_controller.accepted();
             } else {
                 self.problem.restore_from_problem_solution(local_optimum.as_ref().unwrap());
+                self.ruin_controller.rejected();
             }
 
             if self.local_sol_collector.material_limit() < mat_limit {
@@ -118,13 +150,42 @@ impl<'a> GDRR<'a> {
                 lahc_history.clear();
                 lahc_history.push_back(empty_problem_cost.clone());
             }
+            if let Some(worker_restart_stagnation) = worker_restart_stagnation {
+                if last_improvement.elapsed() > worker_restart_stagnation {
+                    //This worker has not improved for too long, restart it
+                    //The best solutions found so far are preserved in the solution collectors
+                    n_restarts += 1;
+                    timed_thread_println!("{}:\t #{} after {} iterations", "Restarting GDRR".bright_blue(), n_restarts, n_iterations);
+                    local_optimum = self.restart();
+                    lahc_history.clear();
+                    lahc_history.push_back(empty_problem_cost.clone());
+                    last_improvement = Instant::now();
+                    n_iterations_since_restart = 0;
+                }
+            }
             n_iterations += 1;
+            n_iterations_since_restart += 1;
             if n_iterations % 100 == 0 {
-                self.local_sol_collector.rx_sync()
+                self.local_sol_collector.rx_sync();
+                if let Some(status_interval) = status_interval {
+                    if last_status.elapsed() >= status_interval {
+                        self.local_sol_collector.report_status(WorkerStatus {
+                            material_limit: mat_limit,
+                            best_cost: local_optimum.as_ref().map(|s| s.cost().clone()),
+                            n_iterations,
+                            n_restarts,
+                            n_iterations_since_restart,
+                        });
+                        last_status = Instant::now();
+                    }
+                }
             }
 
             debug_assert!(lahc_history.len() <= self.config.history_length, "{}", lahc_history.len());
         }
+        //Termination is only observed between iterations, the problem is never left with a partially implemented insertion
+        debug_assert!(assertions::problem_quantities_consistent(&self.problem));
+        self.local_sol_collector.flush();
         timed_thread_println!("{}:\t ({:.2} iter/s, {:.2} acc/s, {} impr)",
                 "GDRR finished".bright_magenta(),
                  (n_iterations as f64 / (std::time::Instant::now() - start_time).as_millis() as f64 * 1000.0),
@@ -140,15 +201,75 @@ impl<'a> GDRR<'a> {
             });
     }
 
-    fn ruin(&mut self, mut mat_limit_budget: i128) -> i128 {
-        let n_nodes_to_remove = self.problem.rng().gen_range(2..(self.config.avg_nodes_removed - 2) * 2 + 1) + 2;
+    /// Logs the cost of the result of a recreate and why it is accepted or rejected by the late acceptance criterion.
+    /// Nothing is evaluated unless the trace level is enabled for this module.
+    #[cfg(feature = "trace-moves")]
+    fn trace_move(n_iterations: usize, cost: &Cost, history_cost: &Cost, local_optimum_cost: Option<&Cost>, cost_comparator: fn(&Cost, &Cost) -> Ordering) {
+        if !log::log_enabled!(log::Level::Trace) {
+            return;
+        }
+        let not_worse_than_local_optimum = local_optimum_cost.is_some_and(|optimum_cost| cost_comparator(cost, optimum_cost) <= Ordering::Equal);
+        let (decision, reason) = match (cost_comparator(cost, history_cost) <= Ordering::Equal, not_worse_than_local_optimum) {
+            (true, _) => ("accepted", "not worse than the history"),
+            (false, true) => ("accepted", "not worse than the local optimum"),
+            (false, false) => ("rejected", "worse than the history and the local optimum"),
+        };
+        log::trace!("#{} {}: {} ({}, history: {})", n_iterations, decision, cost, reason, history_cost);
+    }
+
+    /// Constructs the starting solution of the problem according to the configured initial heuristic
+    fn construct_initial_solution(&mut self) -> Option<ProblemSolution<'a>> {
+        match self.config.initial_heuristic {
+            InitialHeuristic::DecreasingAreaFirstFit => {
+                construction::decreasing_area_first_fit(&mut self.problem, self.cost_comparator);
+                let initial_solution = self.problem.create_solution(&None, None);
+                timed_thread_println!("{}:\t {}", "Initial solution".bright_blue(), util::solution_stats_string(&initial_solution));
+                match initial_solution.cost().material_cost < self.local_sol_collector.material_limit() {
+                    true => {
+                        self.local_sol_collector.report_problem_solution(&initial_solution);
+                        Some(initial_solution)
+                    }
+                    //Exceeds the material limit, the ruin phase will first bring it back within the limit
+                    false => None
+                }
+            }
+            InitialHeuristic::None => None
+        }
+    }
+
+    /// Restarts the search from the initial solution, or from the best complete solution of all threads if `worker_restart_from_incumbent` is set and one is known.
+    /// Returns the new local optimum, if any.
+    fn restart(&mut self) -> Option<ProblemSolution<'a>> {
+        match (self.config.worker_restart_from_incumbent.unwrap_or(false), self.local_sol_collector.global_incumbent()) {
+            (true, Some(incumbent)) => {
+                self.problem.restore_from_instance_solution(incumbent);
+                //The incumbent is at the material limit, the ruin phase will first bring it back below
+                None
+            }
+            _ => {
+                self.problem.clear();
+                self.construct_initial_solution()
+            }
+        }
+    }
+
+    fn ruin(&mut self, mut mat_limit_budget: i128, n_iterations: usize) -> i128 {
+        self.problem.clear_released_parttypes();
+        if let Some(max_layout_age) = self.config.max_layout_age {
+            mat_limit_budget += self.ruin_oldest_layout(max_layout_age, n_iterations);
+        }
+        let avg_nodes_removed = self.ruin_controller.avg_nodes_removed();
+        let n_nodes_to_remove = self.problem.rng().gen_range(2..(avg_nodes_removed - 2) * 2 + 1) + 2;
         //当avg_nodes_removed=6时，生成一个4-10的随机数
+        //Layouts packed at least this well are frozen: they are not selected for the removal of nodes
+        let freeze_threshold = self.config.freeze_utilization_threshold.unwrap_or(f64::INFINITY);
         if mat_limit_budget >= 0 {
             for _i in 0..n_nodes_to_remove {
                 //The bias sampler allows us to select a random layout for removing a node, but with a bias towards layouts with a low usage.
                 //This is done to preserve 'good' layouts and give 'bad' layouts more opportunity to improve
                 let entries = self.problem.layouts_mut().iter_mut()
                     .map(|(i, l)| (i, NotNan::new(l.usage(false)).expect("layout usage is NaN")))
+                    .filter(|(_, usage)| usage.into_inner() < freeze_threshold)
                     .collect_vec();
                 //这里的layout其实就是我们说的pattern
                 //从所有的layout里面随机选择3个，进行排序，排序之后再按权重随机取一个layout出来。
@@ -202,32 +323,89 @@ impl<'a> GDRR<'a> {
         mat_limit_budget
     }
 
-    fn recreate(&mut self, mut mat_limit_budget: i128, max_part_area_excluded: u64) {
-        let start = Instant::now();
-        let mut parttypes_to_consider: Vec<&PartType> = self.problem.parttype_qtys().iter().enumerate()
-            .filter(|(_i, q)| { **q > 0 })
-            .map(|(i, _q)| -> &PartType { self.problem.instance().get_parttype(i) }).collect(); //返回数量大于0的part的集合
+    /// Removes the layout which has not been changed by an accepted iteration for the longest time, if this is longer than `max_layout_age` iterations.
+    /// This forces the parts of unproductive layouts to be consolidated in other layouts. Returns the value of the removed sheet.
+    fn ruin_oldest_layout(&mut self, max_layout_age: usize, n_iterations: usize) -> i128 {
+        if self.layout_last_change.len() > 4 * self.problem.layouts().len() + 16 {
+            //forget the layouts which no longer exist
+            let layout_ids = self.problem.layouts().iter().map(|(_, l)| l.id()).collect::<HashSet<usize>>();
+            self.layout_last_change.retain(|id, _| layout_ids.contains(id));
+        }
+        let layout_last_change = &mut self.layout_last_change;
+        let oldest_layout = self.problem.layouts().iter()
+            .map(|(i, l)| (i, *layout_last_change.entry(l.id()).or_insert(n_iterations)))
+            .min_by_key(|(_, last_change)| *last_change);
+
+        match oldest_layout {
+            Some((layout_index, last_change)) if n_iterations - last_change > max_layout_age => {
+                let layout = &self.problem.layouts()[layout_index];
+                //If the iteration is rejected, the layout is restored with a fresh age
+                self.layout_last_change.insert(layout.id(), n_iterations);
+                let top_node = *layout.top_node_index();
+                let removed_layout = self.problem.remove_node(top_node, LayoutIndex::Existing(layout_index))
+                    .expect("Top node should remove entire layout!");
+                removed_layout.sheettype().value() as i128
+            }
+            _ => 0
+        }
+    }
+
+    fn recreate(&mut self, mat_limit_budget: i128, max_part_area_excluded: u64) {
+        let mat_limit_budget = self.recreate_parts(mat_limit_budget, max_part_area_excluded, false);
+        if self.instance.overproduction_allowed() {
+            //Overproduction only fills the remaining space in the existing layouts, no sheets are opened for it
+            self.recreate_parts(mat_limit_budget.min(0), u64::MAX, true);
+        }
+    }
+
+    /// Minimum remaining quantity of a parttype for it to still be inserted in the (overproduction) pass
+    fn min_remaining_qty(instance: &Instance, parttype_id: usize, overproduction: bool) -> usize {
+        match overproduction {
+            true => 0,
+            false => instance.get_parttype_overproduction(parttype_id)
+        }
+    }
+
+    /// Parttypes of which parts remain to be inserted.
+    /// With focused recreate, only the parts released by the last ruin are reinserted. If none were released, all parts are considered.
+    fn parttypes_to_consider(&self, overproduction: bool) -> Vec<&'a PartType> {
+        let released_parttypes = match self.config.focused_recreate.unwrap_or(false) && !self.problem.released_parttypes().is_empty() {
+            true => Some(self.problem.released_parttypes().iter().copied().collect::<HashSet<usize>>()),
+            false => None
+        };
+        self.problem.parttype_qtys().iter().enumerate()
+            .filter(|(i, q)| { **q > GDRR::min_remaining_qty(self.instance, *i, overproduction) })
+            .filter(|(i, _q)| match released_parttypes.as_ref() {
+                Some(released_parttypes) => released_parttypes.contains(i),
+                None => true
+            })
+            .map(|(i, _q)| self.instance.get_parttype(i)).collect() //返回数量大于0的part的集合
+    }
+
+    /// Inserts the demand of the parts, or the copies allowed on top of it with `overproduction`.
+    /// Returns the remaining material limit budget.
+    fn recreate_parts(&mut self, mut mat_limit_budget: i128, max_part_area_excluded: u64, overproduction: bool) -> i128 {
+        let instance = self.instance;
+        let min_remaining_qty = |parttype_id: usize| GDRR::min_remaining_qty(instance, parttype_id, overproduction);
+        //Pieces of splittable parts remain needed as long as units of the part can be split into them
+        let still_needed = |problem: &Problem, parttype_id: usize| {
+            problem.parttype_qtys()[parttype_id] > min_remaining_qty(parttype_id) || (!overproduction && instance.get_piece_set(parttype_id)
+                .is_some_and(|piece_set| problem.parttype_qtys()[piece_set.original] > min_remaining_qty(piece_set.original)))
+        };
+        let mut parttypes_to_consider = self.parttypes_to_consider(overproduction);
 
 
         let mut insertion_option_cache = InsertionOptionCache::new();
         //multimap:对于1个键，可以有很多个值
         let mut part_area_not_included: u64 = 0;
 
-        //Collect all the layouts which should be considered during this recreate iteration
-        let layouts_to_consider = self.problem.layouts().iter().map(|(i, l)| (LayoutIndex::Existing(i), l))
-            .chain(self.problem.empty_layouts().iter().enumerate()
-                .filter(|(_, l)| self.problem.sheettype_qtys()[l.sheettype().id()] > 0)
-                .map(|(i, l)| (LayoutIndex::Empty(i), l))
-            )
-            .collect_vec(); //把所有的可用空间找出来，
-
         //Generate insertion options for all relevant parttypes and layouts
-        insertion_option_cache.add_for_parttypes(&parttypes_to_consider, &layouts_to_consider);
+        insertion_option_cache.add_for_parttypes(&parttypes_to_consider, &self.problem.layouts_to_consider());
         debug_assert!(assertions::insertion_option_cache_is_valid(&self.problem, &insertion_option_cache, &parttypes_to_consider));
 
         while !parttypes_to_consider.is_empty() && part_area_not_included <= max_part_area_excluded {
             let elected_parttype = GDRR::select_next_parttype(&parttypes_to_consider, &insertion_option_cache, self.problem.rng(), &self.config);
-            let elected_blueprint = GDRR::select_insertion_blueprint(elected_parttype, &insertion_option_cache, mat_limit_budget, &mut self.problem, &self.config, &self.cost_comparator);
+            let elected_blueprint = GDRR::select_insertion_blueprint(elected_parttype, &insertion_option_cache, mat_limit_budget, &mut self.problem, self.config, &self.cost_comparator, &mut self.blueprint_buffers);
 
             if let Some(elected_blueprint) = elected_blueprint.as_ref() {
                 let cache_updates = self.problem.implement_insertion_blueprint(elected_blueprint);
@@ -240,14 +418,23 @@ impl<'a> GDRR<'a> {
                     mat_limit_budget -= empty_layout.sheettype().value() as i128;
                     let sheettype_id = empty_layout.sheettype().id();
 
-                    if self.problem.sheettype_qtys()[sheettype_id] == 0 {
-                        insertion_option_cache.remove_all_for_layout(&elected_blueprint.layout_index(), empty_layout);
+                    if !self.problem.new_layouts_allowed() {
+                        //the maximum number of layouts is reached, no empty layout is available anymore
+                        for (i, empty_layout) in self.problem.empty_layouts().iter().enumerate() {
+                            insertion_option_cache.remove_all_for_layout(&LayoutIndex::Empty(i), empty_layout);
+                        }
+                    } else if self.problem.sheettype_qtys()[sheettype_id] == 0 {
+                        //all empty layouts of this sheettype (one per first cut orientation) are no longer available
+                        for (i, empty_layout) in self.problem.empty_layouts().iter().enumerate() {
+                            if empty_layout.sheettype().id() == sheettype_id {
+                                insertion_option_cache.remove_all_for_layout(&LayoutIndex::Empty(i), empty_layout);
+                            }
+                        }
                     }
                 }
-                if *self.problem.parttype_qtys().get(elected_parttype.id()).unwrap() == 0 {
-                    //if the parttype is not needed anymore, remove it from the cache
-                    parttypes_to_consider.retain(|pt| { pt.id() != elected_parttype.id() });
-                }
+                //if the parttype is not needed anymore, remove it from the cache
+                //(placing a piece can split a unit of its original parttype, which then may no longer be needed either)
+                parttypes_to_consider.retain(|pt| still_needed(&self.problem, pt.id()));
 
                 if insertion_option_cache.is_empty() {
                     break;
@@ -259,18 +446,35 @@ impl<'a> GDRR<'a> {
                 //panic!("debug");
                 debug_assert!(assertions::insertion_option_cache_is_valid(&self.problem, &insertion_option_cache, &parttypes_to_consider), "{:#?}\n{:#?}", elected_blueprint, cache_updates);
             } else {
-                //if there is no insertion blueprint, the part cannot be added to the problem
-                part_area_not_included += *self.problem.parttype_qtys().get(elected_parttype.id()).unwrap() as u64
-                    * elected_parttype.area();
-
                 parttypes_to_consider.retain(|pt| { pt.id() != elected_parttype.id() });
 
+                //A splittable part which cannot be inserted as a whole is split into pieces instead
+                let splittable = !overproduction && self.instance.piece_sets_of(elected_parttype.id()).next().is_some();
+                if splittable {
+                    let pieces = self.instance.piece_sets_of(elected_parttype.id())
+                        .flat_map(|piece_set| piece_set.pieces())
+                        .map(|(piece_id, _)| self.instance.get_parttype(piece_id))
+                        .filter(|piece| !parttypes_to_consider.contains(piece))
+                        .collect_vec();
+                    insertion_option_cache.add_for_parttypes(&pieces, &self.problem.layouts_to_consider());
+                    parttypes_to_consider.extend(pieces);
+                } else {
+                    //if there is no insertion blueprint, the part cannot be added to the problem (copies allowed as overproduction are not missed)
+                    let remaining_qty = *self.problem.parttype_qtys().get(elected_parttype.id()).unwrap();
+                    part_area_not_included += remaining_qty.saturating_sub(self.instance.get_parttype_overproduction(elected_parttype.id())) as u64
+                        * elected_parttype.area();
+                    //Once none of its pieces can be inserted anymore, the remaining units of a split part are missed as well
+                    let original = elected_parttype.original();
+                    if original != elected_parttype.id() && parttypes_to_consider.iter().all(|pt| pt.original() != original) {
+                        part_area_not_included += self.problem.parttype_qtys()[original].saturating_sub(min_remaining_qty(original)) as u64
+                            * self.instance.get_parttype(original).area();
+                    }
+                }
+
                 debug_assert!(assertions::insertion_option_cache_is_valid(&self.problem, &insertion_option_cache, &parttypes_to_consider), "{:#?}", elected_blueprint);
             }
         }
-        println!("time cost: {:?} us", start.elapsed().as_micros());// us
-        println!("{}", self.problem.layouts().len());
-        panic!("debug");
+        mat_limit_budget
     }
 
     fn select_next_parttype(parttypes: &[&'a PartType], insertion_option_cache: &InsertionOptionCache<'a>, rand: &mut SmallRng, config: &Config) -> &'a PartType {
@@ -287,42 +491,62 @@ impl<'a> GDRR<'a> {
         parttypes[parttype_index]
     }
 
-    fn select_insertion_blueprint(parttype: &'a PartType, insertion_option_cache: &InsertionOptionCache<'a>, mat_limit_budget: i128, problem: &mut Problem, config: &Config, cost_comparator: &fn(&Cost, &Cost) -> Ordering) -> Option<InsertionBlueprint<'a>> {
+    fn select_insertion_blueprint(parttype: &'a PartType, insertion_option_cache: &InsertionOptionCache<'a>, mat_limit_budget: i128, problem: &mut Problem, config: &Config, cost_comparator: &fn(&Cost, &Cost) -> Ordering, blueprint_buffers: &mut BlueprintBuffers<'a>) -> Option<InsertionBlueprint<'a>> {
         let insertion_options = insertion_option_cache.get_for_parttype(parttype);
         match insertion_options {
             Some(options) => {
                 //Collect the blueprints
-                let mut existing_layout_blueprints: Vec<InsertionBlueprint<'a>> = Vec::new();
-                let mut new_layout_blueprints: Vec<InsertionBlueprint<'a>> = Vec::new();
+                let (existing_layout_blueprints, new_layout_blueprints) = blueprint_buffers.cleared();
+
+                //Options targeting existing layouts are evaluated first
+                let existing_layout_options = options.iter()
+                    .filter(|option| matches!(option.layout_index(), LayoutIndex::Existing(_)));
+                let empty_layout_options = options.iter()
+                    .filter(|option| matches!(option.layout_index(), LayoutIndex::Empty(_)));
 
-                for option in options {
+                for option in existing_layout_options {
                     if existing_layout_blueprints.len() > 20 {
                         break; //enough blueprints to consider
                     }
-                    match option.layout_index() {
-                        LayoutIndex::Existing(_) => {
-                            existing_layout_blueprints.extend(option.generate_blueprints(problem))
-                        }
-                        LayoutIndex::Empty(i) => {
+                    option.append_blueprints(problem, existing_layout_blueprints);
+                }
+
+                let prefer_existing_layouts = config.prefer_existing_layouts.unwrap_or(true);
+                if existing_layout_blueprints.is_empty() || !prefer_existing_layouts {
+                    for option in empty_layout_options {
+                        if let LayoutIndex::Empty(i) = option.layout_index() {
                             if mat_limit_budget >= problem.empty_layouts()[*i].sheettype().value() as i128 {
-                                new_layout_blueprints.extend(option.generate_blueprints(problem));
+                                option.append_blueprints(problem, new_layout_blueprints);
                             }
                         }
                     }
                 }
+                if !prefer_existing_layouts {
+                    //New layouts compete with the existing ones on cost
+                    existing_layout_blueprints.append(new_layout_blueprints);
+                }
                 match existing_layout_blueprints.is_empty() {
                     false => {
-                        //Sort the blueprints by cost
+                        //Sort the blueprints by cost, ties are broken by the configured packing bias
                         existing_layout_blueprints.sort_by(|a, b| {
-                            cost_comparator(a.cost(), b.cost())
+                            cost_comparator(a.cost(), b.cost()).then_with(|| {
+                                let key_a = GDRR::packing_bias_key(config.packing_bias, a, problem);
+                                let key_b = GDRR::packing_bias_key(config.packing_bias, b, problem);
+                                key_a.cmp(&key_b)
+                            })
                         });
                         // for blueprint in &existing_layout_blueprints {
                         //     println!("{:?}", blueprint.cost());
                         // }
                         // println!();
-                        //Select the best (blinked) one
-                        let selected_blinked_index = blink::select_lowest_in_range(0..existing_layout_blueprints.len(), config.blink_rate, problem.rng());
-                        Some(existing_layout_blueprints.remove(selected_blinked_index))
+                        //Select the best (blinked) one, or with a probability of epsilon a random one
+                        let selected_index = match config.exploration_epsilon {
+                            Some(epsilon) if epsilon > 0.0 && problem.rng().gen::<f32>() < epsilon => {
+                                problem.rng().gen_range(0..existing_layout_blueprints.len())
+                            }
+                            _ => blink::select_lowest_in_range(0..existing_layout_blueprints.len(), config.blink_rate, problem.rng())
+                        };
+                        Some(existing_layout_blueprints.swap_remove(selected_index))
                     }
                     true => {
                         //No blueprints for existing layouts, try new layouts
@@ -334,7 +558,7 @@ impl<'a> GDRR<'a> {
                             false => {
                                 //Select a random blueprint from the new layout blueprints
                                 let selected_index = problem.rng().gen_range(0..new_layout_blueprints.len());
-                                Some(new_layout_blueprints.remove(selected_index))
+                                Some(new_layout_blueprints.swap_remove(selected_index))
                             }
                         }
                     }
@@ -345,4 +569,433 @@ impl<'a> GDRR<'a> {
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Distance of the blueprint's node to the corner towards which parts are packed (lower is preferred)
+    fn packing_bias_key(packing_bias: PackingBias, blueprint: &InsertionBlueprint, problem: &Problem) -> u64 {
+        let layout = problem.get_layout(blueprint.layout_index());
+        let node_index = *blueprint.original_node_index();
+        match packing_bias {
+            PackingBias::None => 0,
+            PackingBias::TopLeft => {
+                let (x, y) = layout.node_position(node_index);
+                x + y
+            }
+            PackingBias::BottomLeft => {
+                let (x, y) = layout.node_position(node_index);
+                let node_height = layout.nodes()[node_index].height();
+                x + (layout.sheettype().height() - y - node_height)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, mpsc};
+
+    use serde_json::json;
+
+    use crate::core::entities::layout::Layout;
+    use crate::core::insertion::insertion_blueprint::InsertionBlueprint;
+    use crate::core::insertion::node_blueprint::NodeBlueprint;
+    use crate::core::layout_index::LayoutIndex;
+    use crate::core::leftover_valuator;
+    use crate::core::rotation::Rotation;
+    use crate::optimization::config::{Config, PackingBias};
+    use crate::optimization::gdrr::GDRR;
+    use crate::optimization::instance::Instance;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::rr::blueprint_buffers::BlueprintBuffers;
+    use crate::optimization::rr::insertion_option_cache::InsertionOptionCache;
+    use crate::optimization::sol_collectors::local_sol_collector::LocalSolCollector;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::Orientation;
+    use crate::util::assertions;
+    use crate::util::messages::{SolutionReportMessage, SyncMessage};
+    use crate::util::test_util::{parse, solve};
+
+    /// GDRR thread of which the sync and report channels are disconnected
+    fn gdrr<'a>(instance: &'a Arc<Instance>, config: &'a Config) -> GDRR<'a> {
+        let (_tx_sync, rx_sync) = mpsc::channel();
+        let (tx_solution_report, _rx_solution_report) = mpsc::sync_channel(1);
+        let local_sol_collector = LocalSolCollector::new(instance.clone(), rx_sync, tx_solution_report, config.cost_comparator(), None);
+        GDRR::new(instance, config, local_sol_collector)
+    }
+
+    #[test]
+    fn packing_bias_breaks_ties_towards_its_corner() {
+        let (_, instance, config) = parse(json!({
+            "Name": "packing_bias",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 3, "Value": 1}]
+        }), json!({"rotationAllowed": false}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let parttype = instance.get_parttype(0);
+
+        //Two strips each holding a part, leaving a free square in the bottom left and the top right corner
+        let strip = |part_on_top: bool| {
+            let mut strip = NodeBlueprint::new(50, 100, None, Orientation::Horizontal);
+            let part = NodeBlueprint::new(50, 50, Some((parttype, Rotation::Default)), Orientation::Vertical);
+            let leftover = NodeBlueprint::new(50, 50, None, Orientation::Vertical);
+            match part_on_top {
+                true => [part, leftover],
+                false => [leftover, part],
+            }.into_iter().for_each(|child| strip.add_child(child));
+            strip
+        };
+        let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Vertical);
+        top_node.add_child(strip(true));
+        top_node.add_child(strip(false));
+        let mut problem = Problem::new(&instance, &config);
+        let (sheettype, _) = &instance.sheets()[0];
+        let layout_i = LayoutIndex::Existing(problem.register_layout(Layout::from_top_node_blueprint(0, sheettype, &top_node, &instance)));
+
+        let layout = problem.get_layout(&layout_i);
+        let blueprints = layout.sorted_empty_nodes().iter()
+            .filter_map(|node_i| InsertionOptionCache::generate_insertion_option(&layout.nodes()[*node_i], parttype, layout_i, *node_i, sheettype))
+            .flat_map(|option| option.generate_blueprints(&problem))
+            .collect::<Vec<InsertionBlueprint>>();
+        assert_eq!(blueprints.len(), 2);
+        assert_eq!(blueprints[0].cost(), blueprints[1].cost());
+
+        let preferred_position = |packing_bias: PackingBias| {
+            let blueprint = blueprints.iter().min_by_key(|blueprint| GDRR::packing_bias_key(packing_bias, blueprint, &problem)).unwrap();
+            layout.node_position(*blueprint.original_node_index())
+        };
+        assert_eq!(preferred_position(PackingBias::BottomLeft), (0, 50));
+        //Without a bias, or with both nodes equally far from the corner, all keys are equal and the order of generation is kept
+        let keys = |packing_bias: PackingBias| blueprints.iter()
+            .map(|blueprint| GDRR::packing_bias_key(packing_bias, blueprint, &problem))
+            .collect::<Vec<u64>>();
+        assert_eq!(keys(PackingBias::TopLeft), vec![50, 50]);
+        assert_eq!(keys(PackingBias::None), vec![0, 0]);
+    }
+
+    #[test]
+    fn no_new_layout_is_opened_while_an_existing_one_has_room() {
+        let (_, instance, config) = parse(json!({
+            "Name": "prefer_existing",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 3, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 4, "Value": 1}]
+        }), json!({"blinkRate": 0.5}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let parttype = instance.get_parttype(0);
+        //A part in the top left corner of the sheet
+        let mut strip = NodeBlueprint::new(100, 50, None, Orientation::Vertical);
+        strip.add_child(NodeBlueprint::new(50, 50, Some((parttype, Rotation::Default)), Orientation::Horizontal));
+        strip.add_child(NodeBlueprint::new(50, 50, None, Orientation::Horizontal));
+        let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+        top_node.add_child(strip);
+        top_node.add_child(NodeBlueprint::new(100, 50, None, Orientation::Vertical));
+        let mut problem = Problem::new(&instance, &config);
+        problem.register_layout(Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance));
+
+        //The three remaining parts all fit in the open layout
+        let mut blueprint_buffers = BlueprintBuffers::new();
+        for _ in 0..3 {
+            let mut insertion_option_cache = InsertionOptionCache::new();
+            insertion_option_cache.add_for_parttypes(&[parttype], &problem.layouts_to_consider());
+            let blueprint = GDRR::select_insertion_blueprint(parttype, &insertion_option_cache, i128::MAX, &mut problem, &config,
+                &config.cost_comparator(), &mut blueprint_buffers).unwrap();
+            assert!(matches!(blueprint.layout_index(), LayoutIndex::Existing(_)));
+            problem.implement_insertion_blueprint(&blueprint);
+        }
+        assert_eq!(problem.layouts().len(), 1);
+    }
+
+    #[test]
+    fn unproductive_layouts_are_ruined_once_they_exceed_the_max_age() {
+        //Number of iterations out of 100 in which the only layout is removed.
+        //It is restored in the next iteration with the same id, as happens when the iteration is rejected.
+        let n_removals = |overrides: serde_json::Value| {
+            let (_, instance, config) = parse(json!({
+                "Name": "layout_age",
+                "Objects": [{"Length": 100, "Height": 100, "Stock": 100, "Cost": 100}],
+                "Items": [{"Length": 50, "Height": 50, "Demand": 100, "Value": 1}]
+            }), overrides);
+            let mut gdrr = gdrr(&instance, &config);
+
+            let mut strip = NodeBlueprint::new(100, 50, None, Orientation::Vertical);
+            strip.add_child(NodeBlueprint::new(50, 50, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Horizontal));
+            strip.add_child(NodeBlueprint::new(50, 50, None, Orientation::Horizontal));
+            let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+            top_node.add_child(strip);
+            top_node.add_child(NodeBlueprint::new(100, 50, None, Orientation::Vertical));
+            (0..100).filter(|n_iterations| {
+                if gdrr.problem.layouts().is_empty() {
+                    gdrr.problem.register_layout(Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance));
+                }
+                gdrr.ruin(0, *n_iterations);
+                gdrr.problem.layouts().is_empty()
+            }).count()
+        };
+        //The layout is frozen, so it is never selected for the removal of individual nodes
+        let frozen = json!({"freezeUtilizationThreshold": 0.0});
+        assert_eq!(n_removals(frozen.clone()), 0);
+        let mut with_max_age = frozen;
+        with_max_age["maxLayoutAge"] = json!(10);
+        //Removed in iterations 11, 22, 33, ..., 99
+        assert_eq!(n_removals(with_max_age), 9);
+    }
+
+    #[test]
+    fn focused_recreate_only_considers_the_released_parts() {
+        //Number of parttypes considered for reinsertion after the only placed part is removed
+        let n_considered = |focused_recreate: bool| {
+            let (_, instance, config) = parse(json!({
+                "Name": "focused_recreate",
+                "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+                "Items": (1..=5).map(|i| json!({"Length": 50, "Height": 10 * i, "Demand": 1, "Value": 1})).collect::<Vec<_>>()
+            }), json!({"focusedRecreate": focused_recreate}));
+            let mut gdrr = gdrr(&instance, &config);
+            let mut strip = NodeBlueprint::new(100, 10, None, Orientation::Vertical);
+            strip.add_child(NodeBlueprint::new(50, 10, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Horizontal));
+            strip.add_child(NodeBlueprint::new(50, 10, None, Orientation::Horizontal));
+            let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+            top_node.add_child(strip);
+            top_node.add_child(NodeBlueprint::new(100, 90, None, Orientation::Vertical));
+            let layout_i = gdrr.problem.register_layout(Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance));
+
+            gdrr.problem.clear_released_parttypes();
+            let top_node_i = *gdrr.problem.layouts()[layout_i].top_node_index();
+            gdrr.problem.remove_node(top_node_i, LayoutIndex::Existing(layout_i));
+            assert_eq!(gdrr.problem.released_parttypes(), &vec![0]);
+            gdrr.parttypes_to_consider(false).len()
+        };
+        assert_eq!(n_considered(true), 1);
+        assert_eq!(n_considered(false), 5);
+    }
+
+    #[test]
+    fn exploration_epsilon_occasionally_selects_a_blueprint_other_than_the_best() {
+        //Number of selections out of 200 of a different node than the best one, which fits the part exactly
+        let n_non_best = |exploration_epsilon: f32| {
+            let (_, instance, config) = parse(json!({
+                "Name": "exploration_epsilon",
+                "Objects": [{"Length": 100, "Height": 100, "Stock": 3, "Cost": 100}],
+                "Items": [{"Length": 50, "Height": 50, "Demand": 2, "Value": 1}]
+            }), json!({"blinkRate": 0.0, "rotationAllowed": false, "explorationEpsilon": exploration_epsilon}));
+            leftover_valuator::set_power(config.leftover_valuation_power);
+            let parttype = instance.get_parttype(0);
+            //A part in the top left corner, leaving a square next to it and a strip below
+            let mut strip = NodeBlueprint::new(100, 50, None, Orientation::Vertical);
+            strip.add_child(NodeBlueprint::new(50, 50, Some((parttype, Rotation::Default)), Orientation::Horizontal));
+            strip.add_child(NodeBlueprint::new(50, 50, None, Orientation::Horizontal));
+            let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+            top_node.add_child(strip);
+            top_node.add_child(NodeBlueprint::new(100, 50, None, Orientation::Vertical));
+            let mut problem = Problem::new_seeded(&instance, &config, 0);
+            problem.register_layout(Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance));
+
+            let mut insertion_option_cache = InsertionOptionCache::new();
+            insertion_option_cache.add_for_parttypes(&[parttype], &problem.layouts_to_consider());
+            let mut blueprint_buffers = BlueprintBuffers::new();
+            (0..200).filter(|_| {
+                let blueprint = GDRR::select_insertion_blueprint(parttype, &insertion_option_cache, i128::MAX, &mut problem, &config,
+                    &config.cost_comparator(), &mut blueprint_buffers).unwrap();
+                problem.get_layout(blueprint.layout_index()).node_position(*blueprint.original_node_index()) != (50, 0)
+            }).count()
+        };
+        assert_eq!(n_non_best(0.0), 0);
+        let n_explored = n_non_best(0.5);
+        assert!(n_explored > 0 && n_explored < 100, "{} of 200 selections explored", n_explored);
+    }
+
+    #[test]
+    fn fully_packed_layouts_are_not_ruined_above_the_freeze_threshold() {
+        //Number of iterations out of 50 in which the fully packed layout is ruined, both layouts are reopened after every ruin
+        let n_full_ruined = |overrides: serde_json::Value| {
+            let (_, instance, config) = parse(json!({
+                "Name": "freeze",
+                "Objects": [{"Length": 100, "Height": 100, "Stock": 100, "Cost": 100}],
+                "Items": [
+                    {"Length": 100, "Height": 100, "Demand": 50, "Value": 1},
+                    {"Length": 50, "Height": 50, "Demand": 50, "Value": 1}
+                ]
+            }), overrides);
+            let mut gdrr = gdrr(&instance, &config);
+            let mut full = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+            full.add_child(NodeBlueprint::new(100, 100, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Vertical));
+            let mut strip = NodeBlueprint::new(100, 50, None, Orientation::Vertical);
+            strip.add_child(NodeBlueprint::new(50, 50, Some((instance.get_parttype(1), Rotation::Default)), Orientation::Horizontal));
+            strip.add_child(NodeBlueprint::new(50, 50, None, Orientation::Horizontal));
+            let mut partial = NodeBlueprint::new(100, 100, None, Orientation::Horizontal);
+            partial.add_child(strip);
+            partial.add_child(NodeBlueprint::new(100, 50, None, Orientation::Vertical));
+
+            (0..50).filter(|n_iterations| {
+                let open_ids = gdrr.problem.layouts().iter().map(|(_, l)| l.id()).collect::<Vec<usize>>();
+                for (id, top_node) in [(0, &full), (1, &partial)] {
+                    if !open_ids.contains(&id) {
+                        gdrr.problem.register_layout(Layout::from_top_node_blueprint(id, instance.get_sheettype(0), top_node, &instance));
+                    }
+                }
+                gdrr.ruin(0, *n_iterations);
+                !gdrr.problem.layouts().iter().any(|(_, l)| l.id() == 0)
+            }).count()
+        };
+        assert!(n_full_ruined(json!({})) > 0);
+        assert_eq!(n_full_ruined(json!({"freezeUtilizationThreshold": 0.99})), 0);
+    }
+
+    #[test]
+    fn terminate_sent_during_a_recreate_leaves_the_problem_consistent() {
+        let (_, instance, config) = parse(json!({
+            "Name": "terminate",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 10, "Cost": 100}],
+            "Items": [
+                {"Length": 35, "Height": 20, "Demand": 12, "Value": 7},
+                {"Length": 15, "Height": 40, "Demand": 9, "Value": 6}
+            ]
+        }), json!({}));
+        let (tx_sync, rx_sync) = mpsc::channel();
+        let (tx_solution_report, _rx_solution_report) = mpsc::sync_channel(64);
+        let local_sol_collector = LocalSolCollector::new(instance.clone(), rx_sync, tx_solution_report, config.cost_comparator(), None);
+        let mut gdrr = GDRR::new(&instance, &config, local_sol_collector);
+        gdrr.construct_initial_solution();
+
+        for n_iterations in 0..20 {
+            let mat_limit_budget = u64::MAX as i128 - 1 - gdrr.problem.cost().material_cost as i128;
+            let mat_limit_budget = gdrr.ruin(mat_limit_budget, n_iterations);
+            if n_iterations == 10 {
+                //Arrives while the parts are being reinserted
+                tx_sync.send(SyncMessage::Terminate).unwrap();
+            }
+            gdrr.recreate(mat_limit_budget, u64::MAX);
+            assert!(assertions::problem_quantities_consistent(&gdrr.problem));
+            //Only observed between iterations
+            assert!(!gdrr.local_sol_collector.terminate());
+            gdrr.local_sol_collector.rx_sync();
+            if gdrr.local_sol_collector.terminate() {
+                assert_eq!(n_iterations, 10);
+                break;
+            }
+        }
+        assert!(gdrr.local_sol_collector.terminate());
+        assert!(assertions::problem_quantities_consistent(&gdrr.problem));
+    }
+
+    #[test]
+    fn only_a_worker_exceeding_the_stagnation_threshold_restarts() {
+        //Fits on a single sheet, so the workers stop improving right away
+        let instance_json = json!({
+            "Name": "restart",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 2, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 4, "Value": 1}]
+        });
+        let (_, instance, restarting) = parse(instance_json.clone(), json!({"maxRRIterations": null, "workerStatusIntervalMs": 0, "workerRestartStagnationMs": 10}));
+        let (_, _, continuing) = parse(instance_json, json!({"maxRRIterations": null, "workerStatusIntervalMs": 0}));
+        let (tx_solution_report, rx_solution_report) = mpsc::sync_channel(100_000);
+        let mut tx_syncs = vec![];
+        std::thread::scope(|scope| {
+            for (i, config) in [&restarting, &continuing].into_iter().enumerate() {
+                let (tx_sync, rx_sync) = mpsc::channel();
+                tx_syncs.push(tx_sync);
+                let (instance, tx_solution_report) = (&instance, tx_solution_report.clone());
+                std::thread::Builder::new().name(format!("T{}", i)).spawn_scoped(scope, move || {
+                    let local_sol_collector = LocalSolCollector::new(instance.clone(), rx_sync, tx_solution_report, config.cost_comparator(), None);
+                    GDRR::new(instance, config, local_sol_collector).lahc();
+                }).unwrap();
+            }
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            for tx_sync in &tx_syncs {
+                tx_sync.send(SyncMessage::Terminate).unwrap();
+            }
+        });
+        drop(tx_solution_report);
+
+        let mut last_statuses = HashMap::new();
+        for message in rx_solution_report.iter() {
+            if let SolutionReportMessage::WorkerStatus(thread_name, status) = message {
+                last_statuses.insert(thread_name, status);
+            }
+        }
+        let (restarted, continued) = (&last_statuses["T0"], &last_statuses["T1"]);
+        assert!(restarted.n_restarts > 0);
+        assert!(restarted.n_iterations_since_restart < restarted.n_iterations);
+        assert_eq!(continued.n_restarts, 0);
+        assert_eq!(continued.n_iterations_since_restart, continued.n_iterations);
+    }
+
+    #[test]
+    fn worker_restarts_from_the_incumbent_if_configured() {
+        let instance = json!({
+            "Name": "incumbent",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 3, "Cost": 100}],
+            "Items": [
+                {"Length": 50, "Height": 50, "Demand": 4, "Value": 1},
+                {"Length": 100, "Height": 30, "Demand": 2, "Value": 1}
+            ]
+        });
+        let (_, _, _, incumbent) = solve(instance.clone(), json!({}));
+        let restarted_layouts = |config: serde_json::Value| {
+            let (_, instance, config) = parse(instance.clone(), config);
+            let (tx_sync, rx_sync) = mpsc::channel();
+            let (tx_solution_report, _rx_solution_report) = mpsc::sync_channel(64);
+            let local_sol_collector = LocalSolCollector::new(instance.clone(), rx_sync, tx_solution_report, config.cost_comparator(), None);
+            let mut gdrr = GDRR::new(&instance, &config, local_sol_collector);
+            tx_sync.send(SyncMessage::SyncIncumbent(incumbent.clone())).unwrap();
+            gdrr.local_sol_collector.rx_sync();
+            gdrr.restart();
+            assert!(assertions::problem_quantities_consistent(&gdrr.problem));
+            (gdrr.problem.layouts().len(), gdrr.problem.parttype_qtys().clone())
+        };
+        assert_eq!(restarted_layouts(json!({"workerRestartFromIncumbent": true})), (incumbent.layouts().len(), incumbent.parttype_qtys().clone()));
+        //Without the option, the worker starts over from an empty problem (there is no initial heuristic)
+        assert_eq!(restarted_layouts(json!({})).0, 0);
+    }
+
+    /// Collects the trace records of the `trace_moves` thread
+    #[cfg(feature = "trace-moves")]
+    struct MoveLogger(std::sync::Mutex<Vec<String>>);
+
+    #[cfg(feature = "trace-moves")]
+    impl log::Log for MoveLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Trace
+        }
+
+        fn log(&self, record: &log::Record) {
+            if std::thread::current().name() == Some("trace_moves") {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[cfg(feature = "trace-moves")]
+    #[test]
+    fn every_recreate_result_is_logged_as_accepted_or_rejected() {
+        static LOGGER: MoveLogger = MoveLogger(std::sync::Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let (_, instance, config) = parse(json!({
+            "Name": "trace_moves",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 10, "Cost": 100}],
+            "Items": [
+                {"Length": 35, "Height": 20, "Demand": 12, "Value": 7},
+                {"Length": 15, "Height": 40, "Demand": 9, "Value": 6}
+            ]
+        }), json!({"maxRRIterations": 200, "seed": 0}));
+        std::thread::scope(|scope| {
+            std::thread::Builder::new().name("trace_moves".to_string()).spawn_scoped(scope, || {
+                let (_tx_sync, rx_sync) = mpsc::channel();
+                let (tx_solution_report, _rx_solution_report) = mpsc::sync_channel(1000);
+                let local_sol_collector = LocalSolCollector::new(instance.clone(), rx_sync, tx_solution_report, config.cost_comparator(), None);
+                GDRR::new(&instance, &config, local_sol_collector).lahc();
+            }).unwrap().join().unwrap();
+        });
+
+        let records = LOGGER.0.lock().unwrap().clone();
+        assert_eq!(records.len(), 200);
+        for (n_iterations, record) in records.iter().enumerate() {
+            assert!(record.starts_with(&format!("#{} accepted: ", n_iterations)) || record.starts_with(&format!("#{} rejected: ", n_iterations)), "{}", record);
+            assert!(record.contains("history: "));
+        }
+        assert!(records.iter().any(|record| record.contains(" accepted: ")));
+        assert!(records.iter().any(|record| record.contains(" rejected: ")));
+    }
+}
@@ -1,3 +1,6 @@
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+
 use crate::core::entities::parttype::PartType;
 use crate::core::entities::sheettype::SheetType;
 use crate::util::assertions;
@@ -6,27 +9,110 @@ use crate::util::assertions;
 /// Instance is a immutable representation of the collection of parts and sheets that need to be optimized.
 pub struct Instance {
     parts: Vec<(PartType, usize)>,
+    overproduction: Vec<usize>,
     sheets: Vec<(SheetType, usize)>,
     total_part_area: u64,
     total_part_qty: usize,
+    book_height: usize,
+    part_spacing: u64,
+    kerf: u64,
+    piece_sets: Vec<PieceSet>,
+    piece_set_indices: Vec<Option<usize>>,
+    sheet_lower_bound: usize,
+}
+
+/// The pieces into which a unit of a splittable parttype (`original`) can be split: `n_pieces` pieces along its length.
+/// All but the last piece are of the `regular` parttype, the last one (the `remainder` parttype) covers the rest of the length,
+/// so the pieces sum up to exactly the length of the part. Without a remainder, all pieces are regular.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceSet {
+    pub original: usize,
+    pub n_pieces: usize,
+    pub regular: usize,
+    pub remainder: Option<usize>,
+}
+
+impl PieceSet {
+    /// The parttypes of the pieces, with the number of pieces of each in a unit
+    pub fn pieces(&self) -> impl Iterator<Item=(usize, usize)> {
+        let n_regular = self.n_pieces - self.remainder.is_some() as usize;
+        std::iter::once((self.regular, n_regular)).chain(self.remainder.map(|remainder| (remainder, 1)))
+    }
+
+    /// Number of complete units which can be assembled from the placed pieces, given the number placed of every parttype
+    pub fn complete_units(&self, placed: impl Fn(usize) -> usize) -> usize {
+        self.pieces().map(|(parttype_id, per_unit)| placed(parttype_id) / per_unit).min().unwrap()
+    }
 }
 
 impl Instance {
-    pub fn new(parts: Vec<(PartType, usize)>, sheets: Vec<(SheetType, usize)>) -> Self {
+    /// When sheets are cut in books, every layout represents `book_height` stacked sheets cut at once.
+    /// The quantities of parts and sheets in the instance are then expressed in books instead of individual parts and sheets.
+    /// The dimensions of the parttypes include the `part_spacing`, the clearance required next to every part (including the `kerf`).
+    /// The dimensions of the sheettypes include it as well, as no clearance is required at the edges of a sheet.
+    /// The areas of both exclude the spacing, see `PartType::with_spacing` and `SheetType::with_spacing`.
+    /// `overproduction` contains for every parttype the number of copies which may be placed on top of its demand.
+    /// The parttypes of the `piece_sets` of splittable parttypes have no demand of their own, units of the original parttype are split into them during the search.
+    pub fn new(parts: Vec<(PartType, usize)>, overproduction: Vec<usize>, sheets: Vec<(SheetType, usize)>, book_height: usize, part_spacing: u64, kerf: u64, piece_sets: Vec<PieceSet>) -> Self {
         // The ID's of parts and sheets must match their respective indices in the vectors.
         assert!(assertions::instance_parttypes_and_sheettypes_ids_correct(&parts, &sheets));
+        assert_eq!(parts.len(), overproduction.len());
+
+        let mut piece_set_indices = vec![None; parts.len()];
+        for (i, piece_set) in piece_sets.iter().enumerate() {
+            for (parttype_id, _) in piece_set.pieces() {
+                assert_eq!(parts[parttype_id].1, 0, "pieces have no demand of their own");
+                piece_set_indices[parttype_id] = Some(i);
+            }
+        }
 
         let total_part_area = parts.iter().map(|(parttype, qty)| parttype.area() * (*qty as u64)).sum();
         let total_part_qty = parts.iter().map(|(_, qty)| *qty).sum();
+        let sheet_lower_bound = Instance::calculate_sheet_lower_bound(&parts, &sheets, &piece_sets, total_part_area);
 
         Self {
             parts,
+            overproduction,
             sheets,
             total_part_area,
             total_part_qty,
+            book_height,
+            part_spacing,
+            kerf,
+            piece_sets,
+            piece_set_indices,
+            sheet_lower_bound,
         }
     }
 
+    /// The ways in which the splittable parttypes can be split into pieces
+    pub fn piece_sets(&self) -> &[PieceSet] {
+        &self.piece_sets
+    }
+
+    /// The piece set the parttype is a piece of, if any
+    pub fn get_piece_set(&self, parttype_id: usize) -> Option<&PieceSet> {
+        self.piece_set_indices[parttype_id].map(|i| &self.piece_sets[i])
+    }
+
+    /// The piece sets into which units of the parttype can be split, none if it is not splittable
+    pub fn piece_sets_of(&self, original: usize) -> impl Iterator<Item=&PieceSet> {
+        self.piece_sets.iter().filter(move |piece_set| piece_set.original == original)
+    }
+
+    /// Number of units of a parttype which are produced, as a whole or assembled from pieces.
+    /// `parttype_qtys` and `parttype_demands` are the remaining quantities and current demands of all parttypes (see `Problem::parttype_demands`).
+    pub fn produced_units(&self, original: usize, parttype_qtys: &[usize], parttype_demands: &[usize]) -> usize {
+        let placed = |parttype_id: usize| parttype_demands[parttype_id] - parttype_qtys[parttype_id];
+        placed(original) + self.piece_sets_of(original).map(|piece_set| piece_set.complete_units(placed)).sum::<usize>()
+    }
+
+    /// Whether the parttype fits on an empty sheet of the sheettype, as a whole or split into pieces
+    pub fn parttype_fits_sheet(&self, parttype_id: usize, sheettype: &SheetType) -> bool {
+        self.get_parttype(parttype_id).fits_sheet(sheettype) ||
+            self.piece_sets_of(parttype_id).any(|piece_set| piece_set.pieces().all(|(piece_id, _)| self.get_parttype(piece_id).fits_sheet(sheettype)))
+    }
+
     pub fn parts(&self) -> &Vec<(PartType, usize)> {
         &self.parts
     }
@@ -46,6 +132,20 @@ impl Instance {
         }
     }
 
+    /// Number of copies of the parttype which may be placed on top of its demand
+    pub fn get_parttype_overproduction(&self, index: usize) -> usize {
+        self.overproduction[index]
+    }
+
+    /// Maximum number of copies of the parttype which may be placed: its demand plus the allowed overproduction
+    pub fn get_parttype_max_qty(&self, index: usize) -> usize {
+        self.parts[index].1 + self.overproduction[index]
+    }
+
+    pub fn overproduction_allowed(&self) -> bool {
+        self.overproduction.iter().any(|qty| *qty > 0)
+    }
+
     pub fn get_sheettype(&self, index: usize) -> &SheetType {
         &self.sheets.get(index).as_ref().unwrap().0
     }
@@ -68,4 +168,240 @@ impl Instance {
     pub fn total_part_qty(&self) -> usize {
         self.total_part_qty
     }
-}
\ No newline at end of file
+
+    pub fn book_height(&self) -> usize {
+        self.book_height
+    }
+
+    pub fn part_spacing(&self) -> u64 {
+        self.part_spacing
+    }
+
+    pub fn kerf(&self) -> u64 {
+        self.kerf
+    }
+
+    /// Lower bound on the number of layouts required to produce all parts which fit on a sheet
+    pub fn sheet_lower_bound(&self) -> usize {
+        self.sheet_lower_bound
+    }
+
+    /// Continuous lower bound on the material cost of a complete solution: the total part area covered by the sheettypes
+    /// with the lowest value per area first (within their stock), where only a fraction of the last sheet is paid for.
+    /// Rounding up to whole sheets would not be valid with heterogeneous sheet values, as a smaller sheet could cover the remainder for less.
+    /// Returns None if no complete solution exists: a part does not fit on any available sheettype or the stock cannot cover the total part area.
+    pub fn material_lower_bound(&self) -> Option<u64> {
+        let mut available_sheets = self.sheets.iter()
+            .filter(|(sheettype, qty)| *qty > 0 && sheettype.area() > 0)
+            .collect::<Vec<_>>();
+        let all_parts_fit = self.parts.iter()
+            .filter(|(_, qty)| *qty > 0)
+            .all(|(parttype, _)| available_sheets.iter().any(|(sheettype, _)| self.parttype_fits_sheet(parttype.id(), sheettype)));
+        if !all_parts_fit {
+            return None;
+        }
+        available_sheets.sort_by(|(a, _), (b, _)| (a.value() as u128 * b.area() as u128).cmp(&(b.value() as u128 * a.area() as u128)));
+
+        let mut bound = 0u128;
+        let mut remaining_area = self.total_part_area as u128;
+        for (sheettype, qty) in available_sheets {
+            if remaining_area == 0 {
+                break;
+            }
+            let covered_area = u128::min(remaining_area, sheettype.area() as u128 * *qty as u128);
+            bound += (covered_area * sheettype.value() as u128).div_ceil(sheettype.area() as u128);
+            remaining_area -= covered_area;
+        }
+        match remaining_area {
+            0 => u64::try_from(bound).ok(),
+            _ => None
+        }
+    }
+
+    /// Upper bound on the usage (as a fraction) achievable on a single sheet of every sheettype, indexed by sheettype id.
+    /// Only takes the areas of the parts into account: it is the largest total area of parts (which individually fit on the sheet,
+    /// limited by their quantities and `max_per_pattern`) not exceeding the area of the sheet.
+    /// Pieces of splittable parts are available for every unit of the part.
+    /// Independent of the search, intended for diagnostics.
+    pub fn max_usage_per_sheet(&self) -> Vec<f64> {
+        let qty = |parttype: &PartType| match self.get_piece_set(parttype.id()) {
+            Some(piece_set) => {
+                let per_unit = piece_set.pieces().find(|(id, _)| *id == parttype.id()).unwrap().1;
+                self.parts[piece_set.original].1 * per_unit
+            }
+            None => self.parts[parttype.id()].1
+        };
+        self.sheets.iter().map(|(sheettype, _)| {
+            let fitting_parts = self.parts.iter()
+                .map(|(parttype, _)| (parttype, qty(parttype)))
+                .filter(|(parttype, qty)| *qty > 0 && parttype.fits_sheet(sheettype))
+                .map(|(parttype, qty)| {
+                    let max_qty = usize::min(qty, parttype.max_per_pattern().unwrap_or(usize::MAX));
+                    let max_qty = u64::min(max_qty as u64, sheettype.area().checked_div(parttype.area()).unwrap_or(0));
+                    (parttype.area(), max_qty)
+                })
+                .collect::<Vec<_>>();
+            match sheettype.area() {
+                0 => 0.0,
+                sheet_area => Instance::max_covered_area(&fitting_parts, sheet_area) as f64 / sheet_area as f64
+            }
+        }).collect()
+    }
+
+    /// Largest sum of areas (each available `qty` times) not exceeding `capacity`, a bounded subset sum.
+    /// All areas are divided by their greatest common divisor with the capacity.
+    /// If the scaled capacity is too large to solve exactly, the total area (capped at the capacity) is returned instead, which is still an upper bound.
+    fn max_covered_area(areas: &[(u64, u64)], capacity: u64) -> u64 {
+        const MAX_SCALED_CAPACITY: u64 = 1 << 24;
+
+        let total_area = areas.iter().map(|(area, qty)| area.saturating_mul(*qty)).fold(0u64, |acc, a| acc.saturating_add(a));
+        if total_area <= capacity {
+            return total_area;
+        }
+        let divisor = areas.iter().fold(capacity, |acc, (area, _)| gcd(acc, *area));
+        let scaled_capacity = capacity / divisor;
+        if scaled_capacity > MAX_SCALED_CAPACITY {
+            return capacity;
+        }
+
+        //reachable[i] is set if a combination of parts with a total (scaled) area of exactly i exists
+        let n_bits = scaled_capacity as usize + 1;
+        let mut reachable = vec![0u64; n_bits.div_ceil(64)];
+        reachable[0] = 1;
+        for (area, qty) in areas {
+            let scaled_area = (area / divisor) as usize;
+            //binary splitting of the quantity: 1, 2, 4, ..., remainder
+            let mut remaining = *qty as usize;
+            let mut chunk = 1;
+            while remaining > 0 {
+                let n = usize::min(chunk, remaining);
+                shift_or(&mut reachable, scaled_area * n, n_bits);
+                remaining -= n;
+                chunk *= 2;
+            }
+        }
+
+        let max_reachable = (0..n_bits).rev().find(|i| reachable[i / 64] & (1 << (i % 64)) != 0).unwrap();
+        max_reachable as u64 * divisor
+    }
+
+    /// Maximum of two lower bounds:
+    /// - area: the minimum number of (the largest available) sheets required to cover the total part area
+    /// - big parts: parts which fit neither in half the width nor in half the height of the largest sheet dimensions can never share a sheet,
+    ///   unless they are splittable
+    fn calculate_sheet_lower_bound(parts: &[(PartType, usize)], sheets: &[(SheetType, usize)], piece_sets: &[PieceSet], total_part_area: u64) -> usize {
+        let available_sheets = sheets.iter().filter(|(_, qty)| *qty > 0).collect::<Vec<_>>();
+        let max_width = available_sheets.iter().map(|(s, _)| s.width()).max().unwrap_or(0);
+        let max_height = available_sheets.iter().map(|(s, _)| s.height()).max().unwrap_or(0);
+
+        let mut sheet_areas = available_sheets.iter().map(|(s, qty)| (s.area(), *qty)).collect::<Vec<_>>();
+        sheet_areas.sort_by_key(|(area, _)| Reverse(*area));
+        let mut area_bound = 0;
+        let mut covered_area = 0;
+        for (area, qty) in sheet_areas {
+            if covered_area >= total_part_area {
+                break;
+            }
+            let n_needed = (total_part_area - covered_area - 1) / area + 1;
+            let n_used = usize::min(qty, usize::try_from(n_needed).unwrap_or(usize::MAX));
+            area_bound += n_used;
+            covered_area = covered_area.saturating_add(area.saturating_mul(n_used as u64));
+        }
+
+        let big_parts_bound = parts.iter()
+            .filter(|(parttype, _)| piece_sets.iter().all(|piece_set| piece_set.original != parttype.id()))
+            .filter(|(parttype, _)| available_sheets.iter().any(|(s, _)| parttype.fits_sheet(s)))
+            .filter(|(parttype, _)| !parttype.fits(max_width / 2, max_height) && !parttype.fits(max_width, max_height / 2))
+            .map(|(_, qty)| *qty)
+            .sum();
+
+        usize::max(area_bound, big_parts_bound)
+    }
+}
+
+/// Reasons why an Instance cannot be generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstanceError {
+    /// The instance does not contain any sheettype with stock, nothing could ever be placed
+    NoSheetsAvailable,
+    /// The allowed rotations of a parttype (referenced by its index in the input) are empty or contain a value other than 0, 90, 180 or 270
+    InvalidAllowedRotations(usize),
+    /// The total demand of all parttypes (in parts to place) exceeds the configured maximum
+    TooManyParts { total: usize, max: usize },
+    /// A defect of a sheettype (referenced by its index in the input) lies (partially) outside of it
+    DefectOutsideObject(usize),
+    /// A parttype (referenced by its index in the input) has a length or height of 0
+    EmptyItem(usize),
+}
+
+impl Display for InstanceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstanceError::NoSheetsAvailable => write!(f, "instance contains no sheets with available stock"),
+            InstanceError::InvalidAllowedRotations(part) => write!(f, "item {} has invalid allowed rotations, only 0, 90, 180 and 270 are supported", part),
+            InstanceError::TooManyParts { total, max } => write!(f, "instance contains {} parts to place, more than the maximum of {} (maxTotalParts)", total, max),
+            InstanceError::DefectOutsideObject(sheet) => write!(f, "object {} has a defect which lies outside of it", sheet),
+            InstanceError::EmptyItem(part) => write!(f, "item {} has no area, its length and height have to be positive", part),
+        }
+    }
+}
+
+impl std::error::Error for InstanceError {}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b)
+    }
+}
+
+/// `bits |= bits << shift`, limited to the first `n_bits` bits
+fn shift_or(bits: &mut [u64], shift: usize, n_bits: usize) {
+    if shift >= n_bits {
+        return;
+    }
+    let (word_shift, bit_shift) = (shift / 64, shift % 64);
+    for i in (word_shift..bits.len()).rev() {
+        let mut shifted = bits[i - word_shift] << bit_shift;
+        if bit_shift > 0 && i > word_shift {
+            shifted |= bits[i - word_shift - 1] >> (64 - bit_shift);
+        }
+        bits[i] |= shifted;
+    }
+    if !n_bits.is_multiple_of(64) {
+        let last = bits.len() - 1;
+        bits[last] &= (1 << (n_bits % 64)) - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::util::test_util::parse;
+
+    fn material_lower_bound(objects: serde_json::Value, items: serde_json::Value) -> Option<u64> {
+        let (_, instance, _) = parse(json!({"Name": "lower_bound", "Objects": objects, "Items": items}), json!({"sheetValuationMode": "cost"}));
+        instance.material_lower_bound()
+    }
+
+    #[test]
+    fn material_lower_bound_with_a_single_sheettype() {
+        let sheets = json!([{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}]);
+        //One and a half sheet of part area, only the covered fraction of the second sheet is paid for
+        assert_eq!(material_lower_bound(sheets.clone(), json!([{"Length": 50, "Height": 50, "Demand": 6, "Value": 1}])), Some(150));
+        //A part larger than the sheet, or more part area than the stock can cover
+        assert_eq!(material_lower_bound(sheets.clone(), json!([{"Length": 150, "Height": 50, "Demand": 1, "Value": 1}])), None);
+        assert_eq!(material_lower_bound(sheets, json!([{"Length": 50, "Height": 50, "Demand": 21, "Value": 1}])), None);
+    }
+
+    #[test]
+    fn material_lower_bound_uses_the_cheapest_sheettypes_first() {
+        let sheets = json!([
+            {"Length": 100, "Height": 100, "Stock": 5, "Cost": 200},
+            {"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}
+        ]);
+        //The cheap sheet covers the first 10000 of the part area, the expensive ones the remaining 5000
+        assert_eq!(material_lower_bound(sheets, json!([{"Length": 50, "Height": 50, "Demand": 6, "Value": 1}])), Some(100 + 100));
+    }
+}
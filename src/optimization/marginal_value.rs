@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crate::io::json_format::JsonInstance;
+use crate::io::parser::generate_instance;
+use crate::optimization::config::Config;
+use crate::optimization::instance::InstanceError;
+use crate::optimization::optimize::run_optimization;
+use crate::optimization::sol_collectors::reporter::ConsoleReporter;
+use crate::optimization::solutions::solution::Solution;
+
+/// Result of solving an instance with and without one extra unit of stock of a sheettype
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarginalSheetValue {
+    pub part_area_included: u64,
+    pub part_area_included_with_extra: u64,
+    pub material_cost: u64,
+    pub material_cost_with_extra: u64,
+}
+
+impl MarginalSheetValue {
+    /// Additional part area which could be placed thanks to the extra unit of stock
+    pub fn marginal_part_area(&self) -> i128 {
+        self.part_area_included_with_extra as i128 - self.part_area_included as i128
+    }
+}
+
+/// Estimates how much more part area can be placed with one extra unit of stock of an object (by index in the input).
+/// The instance is optimized twice with the config (within its run time limit each): once as is, and once with the stock
+/// of the object increased by a single book (`bookHeight` sheets). Objects with unlimited stock have no marginal value.
+/// Being based on heuristic solutions, the difference is an estimate and can even be negative if one of the runs was unlucky.
+pub fn marginal_sheet_value(json_instance: &JsonInstance, config: &Config, object: usize) -> Result<MarginalSheetValue, InstanceError> {
+    let (part_area_included, material_cost) = solve_best(&mut json_instance.clone(), config)?;
+
+    let mut extended_instance = json_instance.clone();
+    let json_sheet = &mut extended_instance.sheettypes[object];
+    let (part_area_included_with_extra, material_cost_with_extra) = match json_sheet.stock {
+        Some(stock) => {
+            json_sheet.stock = Some(stock + config.book_height.unwrap_or(1).max(1));
+            solve_best(&mut extended_instance, config)?
+        }
+        None => (part_area_included, material_cost)
+    };
+
+    Ok(MarginalSheetValue {
+        part_area_included,
+        part_area_included_with_extra,
+        material_cost,
+        material_cost_with_extra,
+    })
+}
+
+/// Part area included and material cost of the best solution found for the instance
+fn solve_best(json_instance: &mut JsonInstance, config: &Config) -> Result<(u64, u64), InstanceError> {
+    let instance = Arc::new(generate_instance(json_instance, config)?);
+    let global_sol_collector = run_optimization(instance, Arc::new(config.clone()), Box::new(ConsoleReporter));
+    let best_solution = global_sol_collector.best_complete_solution().as_ref()
+        .or(global_sol_collector.best_incomplete_solution().as_ref());
+    Ok(best_solution.map_or((0, 0), |solution| (solution.cost().part_area_included, solution.cost().material_cost)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::io::json_format::JsonInstance;
+    use crate::io::parser::generate_config;
+    use crate::optimization::marginal_value::marginal_sheet_value;
+    use crate::util::test_util::test_config;
+
+    #[test]
+    fn extra_board_increases_the_placed_part_area() {
+        //Every item requires a board of its own, but there are only two
+        let json_instance: JsonInstance = serde_json::from_value(json!({
+            "Name": "marginal_value",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 2, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 3, "Value": 1}]
+        })).unwrap();
+        let config = generate_config(&json_instance, Some(test_config(json!({})))).unwrap();
+        let marginal_value = marginal_sheet_value(&json_instance, &config, 0).unwrap();
+        assert_eq!(marginal_value.part_area_included, 2 * 3600);
+        assert_eq!(marginal_value.part_area_included_with_extra, 3 * 3600);
+        assert_eq!(marginal_value.marginal_part_area(), 3600);
+        assert!(marginal_value.material_cost_with_extra > marginal_value.material_cost);
+    }
+}
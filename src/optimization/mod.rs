@@ -5,3 +5,8 @@ pub mod sol_collectors;
 pub mod instance;
 pub mod config;
 pub mod solutions;
+
+pub mod construction;
+pub mod optimize;
+pub mod feasibility;
+pub mod marginal_value;
\ No newline at end of file
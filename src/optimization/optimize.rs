@@ -0,0 +1,245 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{channel, Receiver, Sender, sync_channel, SyncSender};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use colored::*;
+
+use crate::optimization::config::{Config, InitialHeuristic};
+use crate::optimization::gdrr::GDRR;
+use crate::optimization::instance::Instance;
+use crate::optimization::sol_collectors::global_sol_collector::{DEFAULT_REPORT_CHANNEL_CAPACITY, GlobalSolCollector};
+use crate::optimization::sol_collectors::local_sol_collector::LocalSolCollector;
+use crate::optimization::sol_collectors::reporter::Reporter;
+use crate::optimization::solutions::sendable_solution::SendableSolution;
+use crate::util::macros::timed_println;
+use crate::util::messages::{SolutionReportMessage, SyncMessage};
+
+/// Optimizes the instance and returns the best solution found: the best complete one or, if none was found, the one closest to being complete.
+/// If any of the GDRR threads failed (panicked), an error is returned, which still contains the best solution found by all threads.
+pub fn optimize(instance: Arc<Instance>, config: Arc<Config>, reporter: Box<dyn Reporter>) -> Result<Option<SendableSolution>, SolveError> {
+    let global_sol_collector = run_optimization(instance, config, reporter);
+    optimization_result(&global_sol_collector)
+}
+
+/// Best solution held by the collector after the optimization, or an error containing it if any of the GDRR threads failed
+pub fn optimization_result(global_sol_collector: &GlobalSolCollector) -> Result<Option<SendableSolution>, SolveError> {
+    let best_solution = match (global_sol_collector.best_complete_solution(), global_sol_collector.best_incomplete_solution()) {
+        (Some(solution), _) | (None, Some(solution)) => Some(solution.clone()),
+        (None, None) => None
+    };
+    match global_sol_collector.failed_workers().is_empty() {
+        true => Ok(best_solution),
+        false => Err(SolveError {
+            failed_workers: global_sol_collector.failed_workers().clone(),
+            best_solution: best_solution.map(Box::new),
+        })
+    }
+}
+
+/// Optimizes the instance with `nThreads` GDRR threads until one of the termination conditions of the config is reached.
+/// With a `constructionBudgetFraction` (and a run time limit), the optimization is staged, see `run_two_phase_optimization`.
+/// Returns the GlobalSolCollector, which holds the best complete and incomplete solutions found and the threads which failed.
+pub fn run_optimization(instance: Arc<Instance>, config: Arc<Config>, reporter: Box<dyn Reporter>) -> GlobalSolCollector {
+    crate::QUIET.store(config.quiet.unwrap_or(false), Ordering::Relaxed);
+    if let (Some(construction_budget_fraction), Some(run_time)) = (config.construction_budget_fraction, config.run_time_limit()) {
+        return run_two_phase_optimization(instance, config.clone(), reporter, run_time.mul_f64(construction_budget_fraction.clamp(0.0, 1.0)));
+    }
+    let cost_comparator = config.cost_comparator();
+    let (tx_solution_report, rx_solution_report) = report_channel(&config);
+    let (gdrr_thread_handlers, tx_syncs) = spawn_gdrr_threads(&instance, &config, tx_solution_report, None);
+    let mut global_sol_collector = GlobalSolCollector::new(instance, config, tx_syncs, rx_solution_report, cost_comparator, reporter);
+    global_sol_collector.monitor(gdrr_thread_handlers);
+    global_sol_collector.report_final_summary();
+    global_sol_collector
+}
+
+/// Staged optimization, which guarantees a complete solution early on hard instances.
+/// During the construction phase, the threads start from a greedy solution and only search for any complete solution, until the first one is found or the construction time has passed.
+/// The remainder of the run time is spent improving it, with all threads starting below the material of the best solution of the first phase.
+pub fn run_two_phase_optimization(instance: Arc<Instance>, config: Arc<Config>, reporter: Box<dyn Reporter>, construction_time: Duration) -> GlobalSolCollector {
+    let start = Instant::now();
+    let construction_config = Arc::new(Config {
+        initial_heuristic: InitialHeuristic::DecreasingAreaFirstFit,
+        stop_on_first_complete: Some(true),
+        max_run_time_ms: Some(construction_time.as_millis() as u64),
+        ..(*config).clone()
+    });
+    timed_println!("{}", "Construction phase".bold());
+    let cost_comparator = config.cost_comparator();
+    let (tx_solution_report, rx_solution_report) = report_channel(&config);
+    let (gdrr_thread_handlers, tx_syncs) = spawn_gdrr_threads(&instance, &construction_config, tx_solution_report, None);
+    let mut global_sol_collector = GlobalSolCollector::new(instance.clone(), construction_config, tx_syncs, rx_solution_report, cost_comparator, reporter);
+    global_sol_collector.monitor(gdrr_thread_handlers);
+
+    let remaining_time = config.run_time_limit().unwrap_or(Duration::MAX).saturating_sub(start.elapsed());
+    if !remaining_time.is_zero() && !global_sol_collector.optimum_reached() {
+        timed_println!("{}", "Improvement phase".bold());
+        let improvement_config = Arc::new(Config {
+            max_run_time_ms: Some(remaining_time.as_millis() as u64),
+            ..(*config).clone()
+        });
+        let (tx_solution_report, rx_solution_report) = report_channel(&config);
+        let (gdrr_thread_handlers, tx_syncs) = spawn_gdrr_threads(&instance, &improvement_config, tx_solution_report, global_sol_collector.material_limit());
+        global_sol_collector.start_next_phase(improvement_config, tx_syncs, rx_solution_report);
+        global_sol_collector.monitor(gdrr_thread_handlers);
+    }
+    global_sol_collector.report_final_summary();
+    global_sol_collector
+}
+
+fn report_channel(config: &Config) -> (SyncSender<SolutionReportMessage>, Receiver<SolutionReportMessage>) {
+    //Bounded, so a lagging monitor cannot cause reports to pile up in memory
+    sync_channel(config.report_channel_capacity.unwrap_or(DEFAULT_REPORT_CHANNEL_CAPACITY).max(1))
+}
+
+/// Spawns `nThreads` GDRR threads, which start with a material limit of at most `material_limit`.
+/// Returns their handles and the senders to synchronize with them.
+fn spawn_gdrr_threads(instance: &Arc<Instance>, config: &Config, tx_solution_report: SyncSender<SolutionReportMessage>, material_limit: Option<u64>) -> (Vec<JoinHandle<()>>, Vec<Sender<SyncMessage>>) {
+    let mut gdrr_thread_handlers = Vec::new();
+    let mut tx_syncs = Vec::new();
+
+    for i in 0..config.n_threads {
+        let instance_thread = instance.clone();
+        let config_thread = Arc::new(config.for_worker(i));
+        let thread_name = format!("T{}", i);
+        let (tx_sync, rx_sync) = channel();
+        let tx_solution_report_thread = tx_solution_report.clone();
+        let material_limit = match (config.initial_material_limit(instance, i), material_limit) {
+            (Some(a), Some(b)) => Some(u64::min(a, b)),
+            (a, b) => a.or(b)
+        };
+        tx_syncs.push(tx_sync);
+
+        let handle = thread::Builder::new().name(thread_name).spawn(move || {
+            let local_sol_collector = LocalSolCollector::new(instance_thread.clone(), rx_sync, tx_solution_report_thread, config_thread.cost_comparator(), material_limit);
+            let mut gdrr = GDRR::new(&instance_thread, &config_thread, local_sol_collector);
+            gdrr.lahc();
+        });
+        gdrr_thread_handlers.push(handle.expect("could not spawn thread"));
+    }
+    (gdrr_thread_handlers, tx_syncs)
+}
+
+/// One or more GDRR threads failed during the optimization
+#[derive(Debug, Clone)]
+pub struct SolveError {
+    /// Names of the threads which failed
+    pub failed_workers: Vec<String>,
+    /// Best solution found, including the ones reported by the failed threads before they failed
+    pub best_solution: Option<Box<SendableSolution>>,
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GDRR threads failed: {}", self.failed_workers.join(", "))
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use crate::optimization::optimize::optimize;
+    use crate::optimization::sol_collectors::reporter::ConsoleReporter;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::util::test_util::parse;
+
+    #[test]
+    fn millisecond_run_time_limit_is_respected() {
+        //Every part requires its own sheet, so the area lower bound is never reached and only the time limit ends the run
+        let (_, instance, config) = parse(json!({
+            "Name": "run_time",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 3, "Value": 1}]
+        }), json!({"maxRRIterations": null, "maxRunTimeMs": 200}));
+        let start = Instant::now();
+        let solution = optimize(instance, config, Box::new(ConsoleReporter)).unwrap();
+        let elapsed = start.elapsed();
+        assert!(solution.is_some());
+        assert!(elapsed >= Duration::from_millis(200));
+        assert!(elapsed < Duration::from_millis(800), "run took {:?}", elapsed);
+    }
+
+    #[test]
+    fn over_demanded_instance_returns_a_best_effort_solution() {
+        //The stock only fits 12 of the 100 parts
+        let (_, instance, config) = parse(json!({
+            "Name": "best_effort",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 3, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 100, "Value": 1}]
+        }), json!({"maxRRIterations": null, "maxRunTimeMs": 30000, "minCompletionFraction": 0.1}));
+        let start = Instant::now();
+        let solution = optimize(instance, config, Box::new(ConsoleReporter)).unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10), "run took {:?}", start.elapsed());
+        assert_eq!(solution.n_layouts(), 3);
+        assert_eq!(solution.cost().part_area_fraction_included(), 0.12);
+    }
+
+    #[test]
+    fn reaching_the_target_usage_terminates_the_run() {
+        //Every part requires its own sheet, so without a target usage only the time limit ends the run
+        let (_, instance, config) = parse(json!({
+            "Name": "target_usage",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 3, "Value": 1}]
+        }), json!({"maxRRIterations": null, "maxRunTimeMs": 30000, "targetUsagePct": 30.0}));
+        let start = Instant::now();
+        let solution = optimize(instance, config, Box::new(ConsoleReporter)).unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10), "run took {:?}", start.elapsed());
+        assert!(solution.usage() >= 0.3);
+    }
+
+    #[test]
+    fn two_phase_optimization_returns_a_complete_solution_within_a_short_budget() {
+        let items = (0..8)
+            .map(|i| json!({"Length": 20 + 7 * i, "Height": 15 + 9 * i, "Demand": 3, "Value": 1}))
+            .collect::<Vec<_>>();
+        let (_, instance, config) = parse(json!({
+            "Name": "two_phase",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 30, "Cost": 100}],
+            "Items": items
+        }), json!({"maxRRIterations": null, "maxRunTimeMs": 500, "constructionBudgetFraction": 0.2}));
+        let start = Instant::now();
+        let solution = optimize(instance, config, Box::new(ConsoleReporter)).unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2), "run took {:?}", start.elapsed());
+        assert_eq!(solution.cost().part_area_excluded, 0);
+    }
+
+    #[test]
+    fn run_stops_once_the_best_solution_stagnates() {
+        //Every part requires its own sheet, so without a stagnation limit only the time limit ends the run
+        let (_, instance, config) = parse(json!({
+            "Name": "stagnation",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 3, "Value": 1}]
+        }), json!({"maxRRIterations": null, "maxRunTimeMs": 30000, "maxStagnationTimeMs": 300}));
+        let start = Instant::now();
+        let solution = optimize(instance, config, Box::new(ConsoleReporter)).unwrap().unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_secs(5), "run took {:?}", elapsed);
+        assert_eq!(solution.n_layouts(), 3);
+    }
+
+    #[test]
+    fn solver_runs_within_a_tiny_memory_budget() {
+        let (_, instance, config) = parse(json!({
+            "Name": "memory_budget",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [
+                {"Length": 35, "Height": 20, "Demand": 12, "Value": 7},
+                {"Length": 15, "Height": 40, "Demand": 9, "Value": 6}
+            ]
+        }), json!({"solutionPoolSize": 5, "memoryBudgetMb": 0.0001}));
+        let solution = optimize(instance, config, Box::new(ConsoleReporter)).unwrap().unwrap();
+        assert!(solution.is_complete());
+    }
+}
@@ -1,9 +1,12 @@
 use generational_arena::{Arena, Index};
+use itertools::Itertools;
 use rand::{SeedableRng, thread_rng};
 use rand::rngs::SmallRng;
 
 use crate::{DETERMINISTIC_MODE, Instance, Orientation};
 use crate::core::cost::Cost;
+use crate::optimization::config::Config;
+use crate::optimization::instance::PieceSet;
 use crate::core::entities::layout::Layout;
 use crate::core::insertion::insertion_blueprint::InsertionBlueprint;
 use crate::core::layout_index::LayoutIndex;
@@ -20,6 +23,7 @@ use crate::util::assertions;
 pub struct Problem<'a> {
     instance: &'a Instance,
     parttype_qtys: Vec<usize>,
+    parttype_demands: Vec<usize>,
     sheettype_qtys: Vec<usize>,
     layouts: Arena<Layout<'a>>,
     empty_layouts: Vec<Layout<'a>>,
@@ -28,28 +32,53 @@ pub struct Problem<'a> {
     solution_id_changed_layouts: Option<usize>,
     solution_id_counter: usize,
     layout_id_counter: usize,
+    included_part_area: u64,
+    used_sheet_area: u64,
+    min_pattern_utilization: f64,
+    max_layouts: usize,
+    max_stages: Option<usize>,
+    max_per_pattern_constrained: bool,
+    released_parttypes: Vec<usize>,
 }
 
 impl<'a> Problem<'a> {
-    pub fn new(instance: &'a Instance) -> Self {
-        let parttype_qtys = instance.parts().iter().map(|(_, qty)| *qty).collect::<Vec<_>>();
-        let sheettype_qtys = instance.sheets().iter().map(|(_, qty)| *qty).collect::<Vec<_>>();
-        let random = match DETERMINISTIC_MODE {
+    pub fn new(instance: &'a Instance, config: &Config) -> Self {
+        let rng = match DETERMINISTIC_MODE {
             true => SmallRng::seed_from_u64(0),
             false => SmallRng::from_rng(thread_rng()).unwrap()
         };
+        Problem::with_rng(instance, config, rng)
+    }
+
+    /// Creates a problem whose random decisions are fully determined by `seed`
+    pub fn new_seeded(instance: &'a Instance, config: &Config, seed: u64) -> Self {
+        Problem::with_rng(instance, config, SmallRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(instance: &'a Instance, config: &Config, rng: SmallRng) -> Self {
+        let parttype_qtys = instance.parts().iter().map(|(parttype, _)| instance.get_parttype_max_qty(parttype.id())).collect::<Vec<_>>();
+        let sheettype_qtys = instance.sheets().iter().map(|(_, qty)| *qty).collect::<Vec<_>>();
 
         let mut problem = Problem {
             instance,
+            parttype_demands: parttype_qtys.clone(),
             parttype_qtys,
             sheettype_qtys,
             layouts : Arena::new(),
             empty_layouts : Vec::new(),
             changed_layouts : Vec::new(),
             solution_id_changed_layouts : None,
-            rng: random,
+            rng,
             solution_id_counter : 0,
             layout_id_counter : 0,
+            included_part_area : 0,
+            used_sheet_area : 0,
+            min_pattern_utilization : config.min_pattern_utilization.unwrap_or(0.0),
+            released_parttypes : Vec::new(),
+            max_per_pattern_constrained : instance.parts().iter().any(|(parttype, _)| parttype.max_per_pattern().is_some()),
+            max_stages : config.max_stages,
+            //Every layout consumes a book of sheets
+            max_layouts : config.fixed_sheet_count.map_or(usize::MAX, |n_sheets| n_sheets / instance.book_height()),
         };
 
         //Initiate the empty layouts
@@ -95,6 +124,10 @@ impl<'a> Problem<'a> {
 
                 //Implement the blueprint
                 let mut cache_updates = IOCUpdates::new(LayoutIndex::Existing(clone_index));
+                //Sheets with defects have multiple empty nodes, the ones not used by the blueprint become available in the new layout
+                self.layouts[clone_index].sorted_empty_nodes().iter()
+                    .filter(|node_i| *node_i != blueprint.original_node_index())
+                    .for_each(|node_i| cache_updates.add_new(*node_i));
                 self.layouts[clone_index].implement_insertion_blueprint(blueprint, self.instance, &mut cache_updates);
 
                 cache_updates
@@ -131,14 +164,63 @@ impl<'a> Problem<'a> {
         }
     }
 
+    /// Removes all layouts from the problem, resetting it to an empty problem
+    pub fn clear(&mut self) {
+        let layout_indices = self.layouts.iter().map(|(i, _)| i).collect::<Vec<Index>>();
+        for layout_index in layout_indices {
+            self.layout_has_changed(self.layouts[layout_index].id());
+            self.unregister_layout(LayoutIndex::Existing(layout_index));
+        }
+    }
+
     pub fn cost(&mut self) -> Cost {
         let mut cost = self.layouts.iter_mut()
             .fold(Cost::empty(), |acc, (_,l)| acc + l.cost(false));
 
-        cost.part_area_excluded = self.parttype_qtys.iter().enumerate()
-            .fold(0, |acc, (id, qty)| acc + self.instance().get_parttype(id).area() * (*qty as u64));
+        //Parts in layouts below the minimum utilization are not considered to be produced
+        let min_pattern_utilization = self.min_pattern_utilization;
+        let instance = self.instance;
+        let underutilized_part_area = match min_pattern_utilization > 0.0 {
+            true => self.layouts.iter_mut()
+                .filter_map(|(_, l)| match l.usage(false) < min_pattern_utilization {
+                    true => Some(l.get_included_parts().iter().map(|id| instance.get_parttype(*id).area()).sum::<u64>()),
+                    false => None
+                })
+                .sum::<u64>(),
+            false => 0
+        };
 
-        cost.part_area_included = self.instance.total_part_area() - cost.part_area_excluded;
+        //Copies of a part exceeding its maximum per pattern are not considered to be produced either
+        let excess_part_area = match self.max_per_pattern_constrained {
+            true => self.layouts.iter_mut()
+                .filter_map(|(_, l)| match min_pattern_utilization == 0.0 || l.usage(false) >= min_pattern_utilization {
+                    true => Some(l),
+                    false => None
+                })
+                .map(|l| {
+                    l.get_included_parts().iter().counts().into_iter()
+                        .map(|(id, count)| {
+                            let parttype = instance.get_parttype(*id);
+                            let excess = count.saturating_sub(parttype.max_per_pattern().unwrap_or(usize::MAX));
+                            excess as u64 * parttype.area()
+                        })
+                        .sum::<u64>()
+                })
+                .sum::<u64>(),
+            false => 0
+        };
+
+        //Copies placed on top of the demand (overproduction) do not count towards the included part area
+        let overproduced_part_area = match instance.overproduction_allowed() {
+            true => self.parttype_qtys.iter().enumerate()
+                .map(|(id, qty)| instance.get_parttype_overproduction(id).saturating_sub(*qty) as u64 * instance.get_parttype(id).area())
+                .sum::<u64>(),
+            false => 0
+        };
+
+        debug_assert!(self.included_part_area == self.calculate_included_part_area());
+        cost.part_area_included = self.included_part_area.saturating_sub(underutilized_part_area + excess_part_area + overproduced_part_area);
+        cost.part_area_excluded = self.instance.total_part_area() - cost.part_area_included;
 
         cost
     }
@@ -220,26 +302,57 @@ impl<'a> Problem<'a> {
         }
 
         self.parttype_qtys = solution.parttype_qtys().clone();
+        self.parttype_demands = solution.parttype_demands().clone();
         self.sheettype_qtys = solution.sheettype_qtys().clone();
+        self.included_part_area = self.calculate_included_part_area();
+        self.used_sheet_area = self.calculate_used_sheet_area();
 
         debug_assert!(assertions::problem_matches_solution(self, solution));
 
         self.reset_changed_layouts(solution.id());
     }
 
-    pub fn restore_from_instance_solution(&mut self, _solution: &SendableSolution) {
-        todo!()
+    /// Replaces all layouts of the problem with those of the solution
+    pub fn restore_from_instance_solution(&mut self, solution: &SendableSolution) {
+        self.clear();
+        for sendable_layout in solution.layouts() {
+            let layout = sendable_layout.convert_to_layout(self.next_layout_id(), self.instance);
+            self.register_layout(layout);
+        }
+        //The problem no longer relates to any of the previously created solutions
+        self.changed_layouts.clear();
+        self.solution_id_changed_layouts = None;
+
+        debug_assert!(self.parttype_qtys == *solution.parttype_qtys());
+        debug_assert!(self.parttype_demands == *solution.parttype_demands());
+        debug_assert!(self.sheettype_qtys == *solution.sheettype_qtys());
+    }
+
+    /// Creates a problem containing the layouts of the solution
+    pub fn from_sendable(instance: &'a Instance, config: &Config, solution: &SendableSolution) -> Self {
+        let mut problem = Problem::new(instance, config);
+        problem.restore_from_instance_solution(solution);
+        problem
     }
 
+    /// Usage of the problem, based on the incrementally maintained included part and used sheet area
     pub fn usage(&self) -> f64 {
-        let total_included_part_area = self.instance().parts().iter().map(
-            |(parttype, qty)| { parttype.area() * (*qty - self.parttype_qtys.get(parttype.id()).unwrap()) as u64 }
-        ).sum::<u64>();
-        let total_used_sheet_area = self.layouts().iter().map(
-            |(_, layout)| { layout.sheettype().area() }
-        ).sum::<u64>();
+        debug_assert!(self.included_part_area == self.calculate_included_part_area());
+        debug_assert!(self.used_sheet_area == self.calculate_used_sheet_area());
+
+        self.included_part_area as f64 / self.used_sheet_area as f64
+    }
+
+    fn calculate_included_part_area(&self) -> u64 {
+        self.instance().parts().iter().map(
+            |(parttype, _)| { parttype.area() * (self.parttype_demands[parttype.id()] - self.parttype_qtys[parttype.id()]) as u64 }
+        ).sum::<u64>()
+    }
 
-        total_included_part_area as f64 / total_used_sheet_area as f64
+    fn calculate_used_sheet_area(&self) -> u64 {
+        self.layouts().iter().map(
+            |(_, layout)| { layout.sheettype().area() }
+        ).sum::<u64>()
     }
 
     pub fn instance(&self) -> &'a Instance {
@@ -250,10 +363,28 @@ impl<'a> Problem<'a> {
         &self.parttype_qtys
     }
 
+    /// The number of copies of every parttype to place: its maximum quantity in the instance, adjusted for the units of splittable parttypes
+    /// which are split into pieces (see `Instance::piece_sets`). A unit is split when a piece is placed of which no copy is remaining,
+    /// and joined again once none of its pieces are placed anymore.
+    pub fn parttype_demands(&self) -> &Vec<usize> {
+        &self.parttype_demands
+    }
+
+    /// Whether a copy of the parttype can still be placed: some of it is remaining or, for a piece, a unit of its original parttype can still be split
+    pub fn parttype_insertable(&self, parttype_id: usize) -> bool {
+        self.parttype_qtys[parttype_id] > 0 || self.instance.get_piece_set(parttype_id)
+            .is_some_and(|piece_set| self.parttype_qtys[piece_set.original] > 0)
+    }
+
     pub fn sheettype_qtys(&self) -> &Vec<usize> {
         &self.sheettype_qtys
     }
 
+    /// Maximum number of guillotine stages in which the parts have to be cut out, if limited
+    pub fn max_stages(&self) -> Option<usize> {
+        self.max_stages
+    }
+
     pub fn rng(&mut self) -> &mut SmallRng {
         &mut self.rng
     }
@@ -266,6 +397,16 @@ impl<'a> Problem<'a> {
         &mut self.layouts
     }
 
+    /// The existing layouts and the available empty layouts, in which parts can be inserted
+    pub fn layouts_to_consider(&self) -> Vec<(LayoutIndex, &Layout<'a>)> {
+        self.layouts.iter().map(|(i, l)| (LayoutIndex::Existing(i), l))
+            .chain(self.empty_layouts.iter().enumerate()
+                .filter(|(_, l)| self.empty_layout_available(l))
+                .map(|(i, l)| (LayoutIndex::Empty(i), l))
+            )
+            .collect_vec()
+    }
+
     pub fn get_layout(&self, layout_index: &LayoutIndex) -> &Layout<'a>{
         match layout_index{
             LayoutIndex::Existing(index) => self.layouts.get(*index).unwrap(),
@@ -273,6 +414,15 @@ impl<'a> Problem<'a> {
         }
     }
 
+    /// Whether a part can be inserted in this empty layout: the sheet has to be in stock and the maximum number of layouts not yet reached
+    pub fn empty_layout_available(&self, empty_layout: &Layout) -> bool {
+        self.sheettype_qtys[empty_layout.sheettype().id()] > 0 && self.new_layouts_allowed()
+    }
+
+    pub fn new_layouts_allowed(&self) -> bool {
+        self.layouts.len() < self.max_layouts
+    }
+
     pub fn register_layout(&mut self, layout: Layout<'a>) -> Index {
         self.register_sheet(layout.sheettype().id(), 1);
         layout.get_included_parts().iter().for_each(
@@ -307,21 +457,73 @@ impl<'a> Problem<'a> {
     }
 
     fn register_part(&mut self, parttype_id: usize, qty: usize) {
+        if let Some(piece_set) = self.instance.get_piece_set(parttype_id) {
+            while self.parttype_qtys[parttype_id] < qty {
+                self.split_unit(piece_set, true);
+            }
+        }
         self.parttype_qtys[parttype_id] -= qty;
+        self.included_part_area += self.instance.get_parttype(parttype_id).area() * qty as u64;
+    }
+
+    /// Ids of the parttypes of which parts were removed from the problem since the last call to `clear_released_parttypes`
+    pub fn released_parttypes(&self) -> &Vec<usize> {
+        &self.released_parttypes
+    }
+
+    pub fn clear_released_parttypes(&mut self) {
+        self.released_parttypes.clear();
     }
 
     fn unregister_part(&mut self, parttype_id: usize, qty: usize) {
-        debug_assert!(self.parttype_qtys[parttype_id] + qty <= self.instance.get_parttype_qty(parttype_id).unwrap());
+        self.released_parttypes.push(parttype_id);
+        debug_assert!(self.parttype_qtys[parttype_id] + qty <= self.parttype_demands[parttype_id]);
         self.parttype_qtys[parttype_id] += qty;
+        self.included_part_area -= self.instance.get_parttype(parttype_id).area() * qty as u64;
+
+        //Units of which no pieces are placed anymore are joined again
+        if let Some(piece_set) = self.instance.get_piece_set(parttype_id) {
+            while piece_set.pieces().all(|(piece_id, per_unit)| self.parttype_qtys[piece_id] >= per_unit) {
+                self.split_unit(piece_set, false);
+                self.released_parttypes.push(piece_set.original);
+            }
+        }
+    }
+
+    /// Moves the demand of a unit of the original parttype of the piece set to its pieces (`split`), or back.
+    /// The included part area is unaffected, as none of the pieces of the unit are placed.
+    fn split_unit(&mut self, piece_set: &PieceSet, split: bool) {
+        let original = piece_set.original;
+        match split {
+            true => {
+                debug_assert!(self.parttype_qtys[original] > 0, "no unit of parttype {} left to split", original);
+                self.parttype_qtys[original] -= 1;
+                self.parttype_demands[original] -= 1;
+                for (piece_id, per_unit) in piece_set.pieces() {
+                    self.parttype_qtys[piece_id] += per_unit;
+                    self.parttype_demands[piece_id] += per_unit;
+                }
+            }
+            false => {
+                self.parttype_qtys[original] += 1;
+                self.parttype_demands[original] += 1;
+                for (piece_id, per_unit) in piece_set.pieces() {
+                    self.parttype_qtys[piece_id] -= per_unit;
+                    self.parttype_demands[piece_id] -= per_unit;
+                }
+            }
+        }
     }
 
     fn register_sheet(&mut self, sheettype_id: usize, qty: usize) {
         self.sheettype_qtys[sheettype_id] -= qty;
+        self.used_sheet_area += self.instance.get_sheettype(sheettype_id).area() * qty as u64;
     }
 
     fn unregister_sheet(&mut self, sheettype_id: usize, qty: usize) {
         debug_assert!(self.sheettype_qtys[sheettype_id] + qty <= self.instance.get_sheettype_qty(sheettype_id).unwrap());
         self.sheettype_qtys[sheettype_id] += qty;
+        self.used_sheet_area -= self.instance.get_sheettype(sheettype_id).area() * qty as u64;
     }
 
     fn next_layout_id(&mut self) -> usize {
@@ -347,4 +549,129 @@ impl<'a> PartialEq for Problem<'a> {
     fn eq(&self, other: &Problem<'a>) -> bool {
         std::ptr::eq(self, other)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::core::insertion::insertion_blueprint::InsertionBlueprint;
+    use crate::core::layout_index::LayoutIndex;
+    use crate::core::leftover_valuator;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::rr::insertion_option_cache::InsertionOptionCache;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::util::test_util::{parse, solve};
+
+    fn instance() -> serde_json::Value {
+        json!({
+            "Name": "problem",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 3, "Cost": 100}],
+            "Items": [
+                {"Length": 40, "Height": 40, "Demand": 5, "Value": 1},
+                {"Length": 30, "Height": 20, "Demand": 4, "Value": 1}
+            ]
+        })
+    }
+
+    /// Inserts a part of the parttype into the first empty node which can hold it, existing layouts first
+    fn insert_first_fit<'a>(problem: &mut Problem<'a>, parttype_id: usize) -> LayoutIndex {
+        let parttype = problem.instance().get_parttype(parttype_id);
+        let blueprint: InsertionBlueprint<'a> = problem.layouts_to_consider().into_iter()
+            .flat_map(|(layout_i, layout)| layout.sorted_empty_nodes().iter()
+                .filter_map(|node_i| InsertionOptionCache::generate_insertion_option(&layout.nodes()[*node_i], parttype, layout_i, *node_i, layout.sheettype()))
+                .collect::<Vec<_>>())
+            .flat_map(|option| option.generate_blueprints(problem))
+            .next()
+            .expect("no insertion possible");
+        let layout_i = *blueprint.layout_index();
+        problem.implement_insertion_blueprint(&blueprint);
+        layout_i
+    }
+
+    #[test]
+    fn maintained_usage_equals_recomputed_usage() {
+        let (_, instance, config) = parse(instance(), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let mut problem = Problem::new(&instance, &config);
+        let assert_consistent = |problem: &Problem| {
+            assert_eq!(problem.included_part_area, problem.calculate_included_part_area());
+            assert_eq!(problem.used_sheet_area, problem.calculate_used_sheet_area());
+            assert_eq!(problem.usage(), problem.calculate_included_part_area() as f64 / problem.calculate_used_sheet_area() as f64);
+        };
+
+        for parttype_id in [0, 0, 1, 0, 0, 0, 1, 1] {
+            insert_first_fit(&mut problem, parttype_id);
+            assert_consistent(&problem);
+        }
+        assert!(problem.layouts().len() > 1);
+
+        //Remove parts until the problem is empty, which also removes the emptied layouts
+        while let Some((layout_i, node_i)) = problem.layouts().iter()
+            .find_map(|(layout_i, layout)| layout.get_removable_nodes().first().map(|node_i| (layout_i, *node_i))) {
+            problem.remove_node(node_i, LayoutIndex::Existing(layout_i));
+            if !problem.layouts().is_empty() {
+                assert_consistent(&problem);
+            }
+        }
+        assert_eq!((problem.included_part_area, problem.used_sheet_area), (0, 0));
+    }
+
+    #[test]
+    fn quantities_are_restored_when_a_new_layout_is_emptied() {
+        let (_, instance, config) = parse(instance(), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let mut problem = Problem::new(&instance, &config);
+        let (parttype_qtys, sheettype_qtys) = (problem.parttype_qtys().clone(), problem.sheettype_qtys().clone());
+
+        //The first insertion spawns a new layout from the empty one
+        assert!(matches!(insert_first_fit(&mut problem, 0), LayoutIndex::Empty(_)));
+        assert!(matches!(insert_first_fit(&mut problem, 1), LayoutIndex::Existing(_)));
+        assert_eq!(problem.layouts().len(), 1);
+        assert_eq!(problem.parttype_qtys(), &vec![parttype_qtys[0] - 1, parttype_qtys[1] - 1]);
+        assert_eq!(problem.sheettype_qtys(), &vec![sheettype_qtys[0] - 1]);
+
+        let (layout_i, layout) = problem.layouts().iter().next().unwrap();
+        let top_node_i = *layout.top_node_index();
+        assert!(problem.remove_node(top_node_i, LayoutIndex::Existing(layout_i)).is_some());
+        assert!(problem.layouts().is_empty());
+        assert_eq!(problem.parttype_qtys(), &parttype_qtys);
+        assert_eq!(problem.sheettype_qtys(), &sheettype_qtys);
+    }
+
+    #[test]
+    fn registered_layouts_get_unique_ids() {
+        let (_, instance, config) = parse(instance(), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let mut problem = Problem::new(&instance, &config);
+        for _ in 0..5 {
+            insert_first_fit(&mut problem, 0);
+        }
+        assert_eq!(problem.layouts().len(), 2);
+        let ids = problem.layouts().iter().map(|(_, layout)| layout.id()).collect::<Vec<usize>>();
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn problem_is_reconstructed_from_a_sendable_solution() {
+        let (_, instance, config, solution) = solve(instance(), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let mut problem = Problem::from_sendable(&instance, &config, &solution);
+        assert_eq!(problem.parttype_qtys(), solution.parttype_qtys());
+        assert_eq!(problem.sheettype_qtys(), solution.sheettype_qtys());
+        assert_eq!(problem.layouts().len(), solution.n_layouts());
+        let n_placed = |problem: &Problem| problem.layouts().iter().map(|(_, layout)| layout.get_included_parts().len()).sum::<usize>();
+        let n_missing = |problem: &Problem| problem.parttype_qtys().iter().sum::<usize>();
+        assert_eq!(n_placed(&problem) + n_missing(&problem), 9);
+
+        //Removing a part of the reconstructed problem releases it again
+        let (layout_i, node_i) = problem.layouts().iter()
+            .find_map(|(layout_i, layout)| layout.get_removable_nodes().into_iter()
+                .find(|node_i| layout.nodes()[*node_i].parttype().is_some())
+                .map(|node_i| (layout_i, node_i)))
+            .unwrap();
+        let missing_before = n_missing(&problem);
+        problem.remove_node(node_i, LayoutIndex::Existing(layout_i));
+        assert_eq!(n_missing(&problem), missing_before + 1);
+        assert_eq!(n_placed(&problem) + n_missing(&problem), 9);
+    }
+}
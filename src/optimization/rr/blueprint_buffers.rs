@@ -0,0 +1,26 @@
+use crate::core::insertion::insertion_blueprint::InsertionBlueprint;
+
+/// Reusable buffers for the insertion blueprints generated while selecting how to insert a part.
+/// Every GDRR thread owns one, the buffers are cleared and refilled for every selection,
+/// thereby keeping their allocated capacity instead of allocating new vectors thousands of times per iteration.
+#[derive(Default)]
+pub struct BlueprintBuffers<'a> {
+    existing_layouts: Vec<InsertionBlueprint<'a>>,
+    new_layouts: Vec<InsertionBlueprint<'a>>,
+}
+
+impl<'a> BlueprintBuffers<'a> {
+    pub fn new() -> Self {
+        Self {
+            existing_layouts: Vec::new(),
+            new_layouts: Vec::new(),
+        }
+    }
+
+    /// Empty buffers for the blueprints targeting existing layouts and new (empty) layouts respectively
+    pub fn cleared(&mut self) -> (&mut Vec<InsertionBlueprint<'a>>, &mut Vec<InsertionBlueprint<'a>>) {
+        self.existing_layouts.clear();
+        self.new_layouts.clear();
+        (&mut self.existing_layouts, &mut self.new_layouts)
+    }
+}
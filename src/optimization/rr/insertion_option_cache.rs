@@ -3,9 +3,11 @@ use std::rc::{Rc};
 use generational_arena::{Index};
 use itertools::Itertools;
 
-use crate::{PartType, Rotation};
+use crate::{PartType, Rotation, SheetType};
 use crate::core::entities::layout::Layout;
 use crate::core::entities::node::Node;
+#[cfg(feature = "batched-fit")]
+use crate::core::geometry;
 use crate::core::insertion::insertion_option::InsertionOption;
 use crate::core::layout_index::LayoutIndex;
 use crate::optimization::problem::Problem;
@@ -35,10 +37,13 @@ impl<'a : 'b, 'b> InsertionOptionCache<'a> {
             self.remove_for_node(layout_i, node_i);
         });
         let layout = problem.get_layout(layout_i);
+        let (accepted, full): (Vec<&&'a PartType>, Vec<&&'a PartType>) = parttypes.iter().partition(|parttype| layout.accepts_part(parttype));
         cache_updates.new_nodes().iter().for_each(|node_i| {
             let node = &layout.nodes()[*node_i];
-            self.add_for_node(node_i, node, layout_i, parttypes.iter());
+            self.add_for_node(node_i, node, layout_i, layout.sheettype(), accepted.iter().copied());
         });
+        //The layout may have reached the maximum number of copies of a parttype, which can then no longer be inserted anywhere in it
+        full.iter().for_each(|parttype| self.remove_for_parttype_in_layout(parttype, layout_i));
     }
 
     pub fn add_for_parttypes(&mut self, parttypes: &[&'a PartType], layouts: &[(LayoutIndex, &Layout)])
@@ -53,49 +58,106 @@ impl<'a : 'b, 'b> InsertionOptionCache<'a> {
         }
 
         for (layout_i, layout) in layouts {
-            let sorted_empty_nodes = layout.sorted_empty_nodes();
-            let mut starting_index = 0;
-
-            for empty_node_i in sorted_empty_nodes.iter() {
-                let mut generated_insertion_options = Vec::new();
-                let empty_node = &layout.nodes()[*empty_node_i];
-                if sorted_parttypes[sorted_parttypes.len() - 1].area() > empty_node.area() {
-                    //The smallest parttype is larger than this node, there are no possible insertion options left.
-                    break;
-                }
-                for i in starting_index..sorted_parttypes.len() {
-                    let parttype = *sorted_parttypes.get(i).unwrap();
-
-                    if empty_node.area() < parttype.area() {
-                        //The empty node is smaller than this parttype. For the next (smaller) empty node, start searching from next index
-                        starting_index = i + 1;
-                    } else {
-                        let insertion_option = InsertionOptionCache::generate_insertion_option(empty_node, parttype, *layout_i, *empty_node_i);
-                        match insertion_option {
-                            Some(insertion_option) => {
-                                let insertion_option = Rc::new(insertion_option);
-                                generated_insertion_options.push(insertion_option.clone());
-                            }
-                            None => {}
-                        }
+            #[cfg(not(feature = "batched-fit"))]
+            self.add_for_layout(&sorted_parttypes, layout_i, layout);
+            #[cfg(feature = "batched-fit")]
+            self.add_for_layout_batched(&sorted_parttypes, layout_i, layout);
+        }
+    }
+
+    /// Adds the insertion options of the parttypes (sorted by decreasing area) in all empty nodes of the layout.
+    /// Parttypes larger than a node are skipped for it and all smaller nodes,
+    /// as are parttypes of which the layout already holds the maximum number of copies per pattern.
+    #[cfg(not(feature = "batched-fit"))]
+    fn add_for_layout(&mut self, sorted_parttypes: &[&&'a PartType], layout_i: &LayoutIndex, layout: &Layout) {
+        let sorted_parttypes = sorted_parttypes.iter().copied().filter(|parttype| layout.accepts_part(parttype)).collect_vec();
+        if sorted_parttypes.is_empty() {
+            return;
+        }
+        let sorted_empty_nodes = layout.sorted_empty_nodes();
+        let mut starting_index = 0;
+        for empty_node_i in sorted_empty_nodes.iter() {
+            let mut generated_insertion_options = Vec::new();
+            let empty_node = &layout.nodes()[*empty_node_i];
+            if sorted_parttypes[sorted_parttypes.len() - 1].area() > empty_node.area() {
+                //The smallest parttype is larger than this node, there are no possible insertion options left.
+                break;
+            }
+            for i in starting_index..sorted_parttypes.len() {
+                let parttype = *sorted_parttypes.get(i).unwrap();
+
+                if empty_node.area() < parttype.area() {
+                    //The empty node is smaller than this parttype. For the next (smaller) empty node, start searching from next index
+                    starting_index = i + 1;
+                } else {
+                    let insertion_option = InsertionOptionCache::generate_insertion_option(empty_node, parttype, *layout_i, *empty_node_i, layout.sheettype());
+                    if let Some(insertion_option) = insertion_option {
+                        generated_insertion_options.push(Rc::new(insertion_option));
                     }
                 }
-                //update the maps
-                for insertion_option in &generated_insertion_options {
-                    let parttype = insertion_option.parttype();
-                    self.option_parttype_map.insert(parttype, insertion_option.clone());
-                }
-                self.option_node_map.insert_all((*layout_i, *empty_node_i), generated_insertion_options);
             }
+            self.insert_for_node(layout_i, empty_node_i, generated_insertion_options);
+        }
+    }
+
+    /// Adds the insertion options of the parttypes in all empty nodes of the layout,
+    /// looking up whether they fit in the `fit_matrix` instead of checking every parttype and node separately.
+    /// Parttypes of which the layout already holds the maximum number of copies per pattern are skipped.
+    #[cfg(feature = "batched-fit")]
+    fn add_for_layout_batched(&mut self, sorted_parttypes: &[&&'a PartType], layout_i: &LayoutIndex, layout: &Layout) {
+        let sorted_parttypes = sorted_parttypes.iter().copied().filter(|parttype| layout.accepts_part(parttype)).collect_vec();
+        let sorted_empty_nodes = layout.sorted_empty_nodes();
+        let fit_matrix = InsertionOptionCache::fit_matrix(&sorted_parttypes, layout, sorted_empty_nodes);
+        let allowed_rotations = sorted_parttypes.iter()
+            .map(|parttype| parttype.allowed_rotations(layout.sheettype()))
+            .collect_vec();
+
+        for (node_rank, empty_node_i) in sorted_empty_nodes.iter().enumerate() {
+            let generated_insertion_options = sorted_parttypes.iter().enumerate()
+                .filter_map(|(i, parttype)| {
+                    let (default_fits, rotated_fits) = fit_matrix[i][node_rank];
+                    let (default_allowed, rotated_allowed) = allowed_rotations[i];
+                    let insertion_option = InsertionOptionCache::insertion_option_from_fits(parttype, *layout_i, *empty_node_i,
+                                                                                            default_allowed && default_fits, rotated_allowed && rotated_fits);
+                    debug_assert!(insertion_option == InsertionOptionCache::generate_insertion_option(&layout.nodes()[*empty_node_i], parttype, *layout_i, *empty_node_i, layout.sheettype()));
+                    insertion_option
+                })
+                .map(Rc::new)
+                .collect_vec();
+            self.insert_for_node(layout_i, empty_node_i, generated_insertion_options);
         }
     }
 
-    pub fn add_for_node<I>(&mut self, node_i: &Index, node: &Node, layout_i: &LayoutIndex, parttypes: I)
+    fn insert_for_node(&mut self, layout_i: &LayoutIndex, node_i: &Index, insertion_options: Vec<Rc<InsertionOption<'a>>>) {
+        for insertion_option in &insertion_options {
+            self.option_parttype_map.insert(insertion_option.parttype(), insertion_option.clone());
+        }
+        self.option_node_map.insert_all((*layout_i, *node_i), insertion_options);
+    }
+
+    /// Whether every parttype fits in every empty node of the layout, in its default and rotated orientation.
+    /// Computed at once per parttype for all nodes, indexed by parttype and node rank.
+    #[cfg(feature = "batched-fit")]
+    fn fit_matrix(sorted_parttypes: &[&&PartType], layout: &Layout, sorted_empty_nodes: &[Index]) -> Vec<Vec<(bool, bool)>> {
+        let node_widths = sorted_empty_nodes.iter().map(|i| layout.nodes()[*i].width()).collect_vec();
+        let node_heights = sorted_empty_nodes.iter().map(|i| layout.nodes()[*i].height()).collect_vec();
+        let mut default_fits = Vec::with_capacity(sorted_empty_nodes.len());
+        let mut rotated_fits = Vec::with_capacity(sorted_empty_nodes.len());
+        sorted_parttypes.iter()
+            .map(|parttype| {
+                geometry::fits_batch(parttype.size().width(), parttype.size().height(), &node_widths, &node_heights, &mut default_fits);
+                geometry::fits_batch(parttype.rotated_size().width(), parttype.rotated_size().height(), &node_widths, &node_heights, &mut rotated_fits);
+                default_fits.iter().zip(rotated_fits.iter()).map(|(d, r)| (*d, *r)).collect_vec()
+            })
+            .collect_vec()
+    }
+
+    pub fn add_for_node<I>(&mut self, node_i: &Index, node: &Node, layout_i: &LayoutIndex, sheettype: &SheetType, parttypes: I)
         where I: Iterator<Item=&'b &'a PartType> {
         if node.parttype().is_none() && node.children().is_empty() {
             for parttype in parttypes.into_iter() {
                 let insertion_option =
-                    InsertionOptionCache::generate_insertion_option(node, parttype, *layout_i, *node_i);
+                    InsertionOptionCache::generate_insertion_option(node, parttype, *layout_i, *node_i, sheettype);
                 match insertion_option {
                     Some(insertion_option) => {
                         let insertion_option = Rc::new(insertion_option);
@@ -121,6 +183,18 @@ impl<'a : 'b, 'b> InsertionOptionCache<'a> {
         }
     }
 
+    /// Removes all insertion options of the parttype in the layout
+    pub fn remove_for_parttype_in_layout(&mut self, parttype: &'a PartType, layout_i: &LayoutIndex) {
+        let options = match self.option_parttype_map.get(&parttype) {
+            Some(options) => options.iter().filter(|option| option.layout_index() == layout_i).cloned().collect_vec(),
+            None => return
+        };
+        for option in options {
+            self.option_parttype_map.remove(&parttype, &option);
+            self.option_node_map.remove(&(*layout_i, *option.original_node_index()), &option);
+        }
+    }
+
     pub fn remove_all_for_layout(&mut self, layout_i: &LayoutIndex, layout: &Layout) {
         let sorted_empty_nodes = layout.sorted_empty_nodes();
         for empty_node_i in sorted_empty_nodes.iter() {
@@ -128,31 +202,29 @@ impl<'a : 'b, 'b> InsertionOptionCache<'a> {
         }
     }
 
-    fn generate_insertion_option(node: &Node, parttype: &'a PartType, layout_i: LayoutIndex, node_i: Index) -> Option<InsertionOption<'a>> {
-        match parttype.fixed_rotation() {
-            Some(fixed_rotation) => {
-                match node.insertion_possible(parttype, *fixed_rotation) {
-                    true => Some(InsertionOption::new(layout_i, node_i, parttype, Some(*fixed_rotation))),
-                    false => None
-                }
+    /// Insertion option of a parttype in a node of a layout of the sheettype.
+    /// Only the rotations allowed on the sheettype (see `PartType::allowed_rotations`) are considered.
+    pub fn generate_insertion_option(node: &Node, parttype: &'a PartType, layout_i: LayoutIndex, node_i: Index, sheettype: &SheetType) -> Option<InsertionOption<'a>> {
+        let (default_allowed, rotated_allowed) = parttype.allowed_rotations(sheettype);
+        let default_possible = default_allowed && node.insertion_possible(parttype, Rotation::Default);
+        let rotated_possible = rotated_allowed && node.insertion_possible(parttype, Rotation::Rotated);
+        InsertionOptionCache::insertion_option_from_fits(parttype, layout_i, node_i, default_possible, rotated_possible)
+    }
+
+    /// Insertion option of a parttype in a node, given whether it is possible to insert it in its default and rotated orientation
+    fn insertion_option_from_fits(parttype: &'a PartType, layout_i: LayoutIndex, node_i: Index, default_possible: bool, rotated_possible: bool) -> Option<InsertionOption<'a>> {
+        match (default_possible, rotated_possible) {
+            (true, true) => {
+                Some(InsertionOption::new(layout_i, node_i, parttype, None))
             }
-            None => {
-                let default_possible = node.insertion_possible(parttype, Rotation::Default);
-                let rotated_possible = node.insertion_possible(parttype, Rotation::Rotated);
-                match (default_possible, rotated_possible) {
-                    (true, true) => {
-                        Some(InsertionOption::new(layout_i, node_i, parttype, None))
-                    }
-                    (true, false) => {
-                        Some(InsertionOption::new(layout_i, node_i, parttype,  Some(Rotation::Default)))
-                    }
-                    (false, true) => {
-                        Some(InsertionOption::new(layout_i, node_i, parttype, Some(Rotation::Rotated)))
-                    }
-                    (false, false) => {
-                        None
-                    }
-                }
+            (true, false) => {
+                Some(InsertionOption::new(layout_i, node_i, parttype,  Some(Rotation::Default)))
+            }
+            (false, true) => {
+                Some(InsertionOption::new(layout_i, node_i, parttype, Some(Rotation::Rotated)))
+            }
+            (false, false) => {
+                None
             }
         }
     }
@@ -169,3 +241,104 @@ impl<'a : 'b, 'b> InsertionOptionCache<'a> {
         self.option_parttype_map.is_empty() && self.option_node_map.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use serde_json::json;
+
+    use crate::core::insertion::node_blueprint::NodeBlueprint;
+    use crate::core::layout_index::LayoutIndex;
+    use crate::core::leftover_valuator;
+    use crate::core::rotation::Rotation;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::rr::insertion_option_cache::InsertionOptionCache;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::util::test_util::{parse, solve};
+
+    /// Rotations of all parts in the blueprint
+    fn part_rotations(blueprint: &NodeBlueprint, rotations: &mut Vec<Rotation>) {
+        rotations.extend(blueprint.rotation());
+        blueprint.children().iter().for_each(|child| part_rotations(child, rotations));
+    }
+
+    /// Rotations of the parts in all blueprints for inserting the parttype in the first empty layout
+    fn blueprint_rotations(problem: &Problem, parttype_id: usize) -> Vec<Rotation> {
+        let empty_layout = &problem.empty_layouts()[0];
+        let top_node_i = empty_layout.sorted_empty_nodes()[0];
+        let option = InsertionOptionCache::generate_insertion_option(&empty_layout.nodes()[top_node_i], problem.instance().get_parttype(parttype_id),
+            LayoutIndex::Empty(0), top_node_i, empty_layout.sheettype()).unwrap();
+        let mut rotations = Vec::new();
+        option.generate_blueprints(problem).iter()
+            .flat_map(|blueprint| blueprint.replacements())
+            .for_each(|replacement| part_rotations(replacement, &mut rotations));
+        rotations
+    }
+
+    #[test]
+    fn no_layout_exceeds_the_maximum_per_pattern() {
+        let instance = json!({
+            "Name": "max_per_pattern",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 10, "Cost": 100}],
+            "Items": [
+                {"Length": 10, "Height": 10, "Demand": 10, "Value": 1, "MaxPerPattern": 2},
+                {"Length": 30, "Height": 20, "Demand": 4, "Value": 6}
+            ]
+        });
+        let (_, instance, _, solution) = solve(instance, json!({}));
+        assert_eq!(solution.parttype_qtys(), &vec![0, 0]);
+        for (i, sendable_layout) in solution.layouts().iter().enumerate() {
+            let layout = sendable_layout.convert_to_layout(i, &instance);
+            let counts = layout.get_included_parts().into_iter().counts();
+            assert!(counts.get(&0).copied().unwrap_or(0) <= 2);
+        }
+        assert_eq!(solution.n_layouts(), 5);
+    }
+
+    #[test]
+    fn no_rotated_blueprint_is_generated_for_parts_allowing_only_half_turns() {
+        let (_, instance, config) = parse(json!({
+            "Name": "allowed_rotations",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [
+                {"Length": 30, "Height": 60, "Demand": 1, "Value": 1, "AllowedRotations": [0, 180]},
+                {"Length": 30, "Height": 60, "Demand": 1, "Value": 1}
+            ]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let problem = Problem::new(&instance, &config);
+        assert!(blueprint_rotations(&problem, 0).iter().all(|rotation| *rotation == Rotation::Default));
+        assert!(blueprint_rotations(&problem, 1).contains(&Rotation::Rotated));
+    }
+
+    #[test]
+    fn only_grain_aligned_blueprints_are_generated_on_grained_sheets() {
+        let (_, instance, config) = parse(json!({
+            "Name": "grain",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100, "Grain": "Length"}],
+            "Items": [
+                {"Length": 30, "Height": 60, "Demand": 1, "Value": 1, "Grain": "Length"},
+                {"Length": 30, "Height": 60, "Demand": 1, "Value": 1, "Grain": "Height"},
+                {"Length": 30, "Height": 60, "Demand": 1, "Value": 1}
+            ]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let problem = Problem::new(&instance, &config);
+        let distinct_rotations = |parttype_id: usize| blueprint_rotations(&problem, parttype_id).into_iter().unique().sorted_by_key(|rotation| *rotation == Rotation::Rotated).collect_vec();
+        assert_eq!(distinct_rotations(0), vec![Rotation::Default]);
+        assert_eq!(distinct_rotations(1), vec![Rotation::Rotated]);
+        assert_eq!(distinct_rotations(2), vec![Rotation::Default, Rotation::Rotated]);
+
+        //Without rotation, the misaligned part cannot be placed at all
+        let (_, instance, config) = parse(json!({
+            "Name": "grain",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100, "Grain": "Length"}],
+            "Items": [{"Length": 30, "Height": 60, "Demand": 1, "Value": 1, "Grain": "Height"}]
+        }), json!({"rotationAllowed": false}));
+        let problem = Problem::new(&instance, &config);
+        let empty_layout = &problem.empty_layouts()[0];
+        let top_node_i = empty_layout.sorted_empty_nodes()[0];
+        assert!(InsertionOptionCache::generate_insertion_option(&empty_layout.nodes()[top_node_i], instance.get_parttype(0),
+            LayoutIndex::Empty(0), top_node_i, empty_layout.sheettype()).is_none());
+    }
+}
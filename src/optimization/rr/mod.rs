@@ -1,2 +1,4 @@
 pub mod insertion_option_cache;
-pub mod cache_updates;
\ No newline at end of file
+pub mod cache_updates;
+pub mod ruin_controller;
+pub mod blueprint_buffers;
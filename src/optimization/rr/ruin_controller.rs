@@ -0,0 +1,86 @@
+use crate::optimization::config::Config;
+
+/// Growth of the ruin strength after every rejected iteration
+const STAGNATION_GROWTH: f64 = 1.005;
+/// Decay of the ruin strength after every accepted iteration
+const ACCEPTANCE_DECAY: f64 = 0.95;
+/// The ruin operator removes between 4 and 2 * (avg - 1) nodes, which requires an average of at least 3
+pub const MIN_AVG_NODES_REMOVED: usize = 3;
+
+/// Controls the strength of the ruin operator (average number of nodes removed per iteration, μ).
+/// With adaptive ruin, the strength increases gradually while iterations are being rejected, to escape from stagnation,
+/// and decreases again once iterations are accepted. Otherwise the strength is fixed at `avgNodesRemoved`.
+pub struct RuinController {
+    avg_nodes_removed: f64,
+    min: f64,
+    max: f64,
+    adaptive: bool,
+}
+
+impl RuinController {
+    pub fn new(config: &Config) -> Self {
+        let min = config.adaptive_ruin_min_nodes.unwrap_or(MIN_AVG_NODES_REMOVED).max(MIN_AVG_NODES_REMOVED);
+        let max = config.adaptive_ruin_max_nodes.unwrap_or(config.avg_nodes_removed * 2).max(min);
+        Self {
+            avg_nodes_removed: config.avg_nodes_removed as f64,
+            min: min as f64,
+            max: max as f64,
+            adaptive: config.adaptive_ruin.unwrap_or(false),
+        }
+    }
+
+    pub fn avg_nodes_removed(&self) -> usize {
+        self.avg_nodes_removed.round() as usize
+    }
+
+    pub fn accepted(&mut self) {
+        if self.adaptive {
+            self.avg_nodes_removed = f64::max(self.avg_nodes_removed * ACCEPTANCE_DECAY, self.min);
+        }
+    }
+
+    pub fn rejected(&mut self) {
+        if self.adaptive {
+            self.avg_nodes_removed = f64::min(self.avg_nodes_removed * STAGNATION_GROWTH, self.max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::optimization::rr::ruin_controller::RuinController;
+    use crate::util::test_util::parse;
+
+    fn ruin_controller(adaptive: bool) -> RuinController {
+        let instance = json!({
+            "Name": "ruin",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 50, "Height": 50, "Demand": 1, "Value": 1}]
+        });
+        let (_, _, config) = parse(instance, json!({"avgNodesRemoved": 6, "adaptiveRuin": adaptive}));
+        RuinController::new(&config)
+    }
+
+    #[test]
+    fn ruin_strength_grows_while_stagnating_and_drops_after_an_acceptance() {
+        let mut controller = ruin_controller(true);
+        assert_eq!(controller.avg_nodes_removed(), 6);
+        (0..50).for_each(|_| controller.rejected());
+        assert_eq!(controller.avg_nodes_removed(), 8);
+        controller.accepted();
+        assert_eq!(controller.avg_nodes_removed(), 7);
+
+        //Bounded by twice avgNodesRemoved
+        (0..1000).for_each(|_| controller.rejected());
+        assert_eq!(controller.avg_nodes_removed(), 12);
+    }
+
+    #[test]
+    fn ruin_strength_is_fixed_without_adaptive_ruin() {
+        let mut controller = ruin_controller(false);
+        (0..50).for_each(|_| controller.rejected());
+        assert_eq!(controller.avg_nodes_removed(), 6);
+    }
+}
@@ -1,6 +1,7 @@
 use std::{thread, time};
+use std::collections::BTreeMap;
 use std::cmp::Ordering;
-use std::sync::{Arc, atomic};
+use std::sync::{Arc, atomic, Once};
 use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::{Receiver, Sender};
 use std::time::Duration;
@@ -9,29 +10,58 @@ use colored::*;
 
 use crate::{Config, Instance};
 use crate::core::cost::Cost;
+use crate::optimization::config::{HashConfig, ReportImprovement};
+use crate::optimization::problem::Problem;
+use crate::optimization::sol_collectors::reporter::Reporter;
+use crate::optimization::solutions::exclusion_reason::{determine_exclusion_reasons, ExclusionReason};
 use crate::optimization::solutions::sendable_solution::SendableSolution;
 use crate::optimization::solutions::solution::Solution;
 use crate::optimization::solutions::solution_stats::SolutionStats;
 use crate::util::macros::timed_println;
-use crate::util::messages::{SolutionReportMessage, SyncMessage};
-use crate::util::util;
+use crate::util::messages::{SolutionReportMessage, SyncMessage, WorkerStatus};
 
-const MONITOR_INTERVAL: Duration = Duration::from_millis(10);
+/// Default interval at which the monitor processes the reports of the GDRR threads
+pub const DEFAULT_MONITOR_INTERVAL_MS: u64 = 10;
+static CTRLC_HANDLER: Once = Once::new();
+static CTRLC_RECEIVED: AtomicBool = AtomicBool::new(false);
+/// Default capacity of the channel through which the GDRR threads report their solutions
+pub const DEFAULT_REPORT_CHANNEL_CAPACITY: usize = 64;
+/// Estimated memory of a pooled solution per unit of demand: its item node, the leftover or waste node next to it and their share of the canonical form
+const POOLED_BYTES_PER_PART: usize = 256;
+/// Estimated memory of a pooled solution regardless of its size (the instance is shared)
+const POOLED_BYTES_PER_SOLUTION: usize = 1024;
 
 /// Global Solution Collector
 /// communicates with a set of LocalSolCollectors
 /// It receives solutions and sends out sync messages (material limit lowering, terminate)
-
+/// New best solutions and the final result are passed on to its Reporter
 pub struct GlobalSolCollector {
     _instance: Arc<Instance>,
     config: Arc<Config>,
     best_complete_solution: Option<SendableSolution>,
     best_incomplete_solution: Option<SendableSolution>,
     best_incomplete_cost: Option<Cost>,
+    last_reported_incomplete_cost: Option<Cost>,
     cost_comparator: fn(&Cost, &Cost) -> Ordering,
     material_limit: Option<u64>,
     tx_syncs: Vec<Sender<SyncMessage>>,
     rx_solution_report: Receiver<SolutionReportMessage>,
+    reporter: Box<dyn Reporter>,
+    best_solution_time: Option<Duration>,
+    last_complete_improvement: Option<time::Instant>,
+    failed_workers: Vec<String>,
+    worker_statuses: BTreeMap<String, WorkerStatus>,
+    solution_pool: Vec<SendableSolution>,
+    /// Canonical forms of the pooled solutions, at the same positions
+    solution_pool_forms: Vec<String>,
+    last_incomplete_print: Option<time::Instant>,
+    pending_incomplete_report: Option<(String, IncompleteReport)>,
+}
+
+/// An incomplete improvement, as reported by a GDRR thread: the full solution or only its statistics
+enum IncompleteReport {
+    Solution(SendableSolution),
+    Stats(SolutionStats),
 }
 
 impl GlobalSolCollector {
@@ -40,6 +70,7 @@ impl GlobalSolCollector {
                tx_syncs: Vec<Sender<SyncMessage>>,
                rx_solution_report: Receiver<SolutionReportMessage>,
                cost_comparator: fn(&Cost, &Cost) -> Ordering,
+               reporter: Box<dyn Reporter>,
     ) -> Self {
         Self {
             _instance,
@@ -47,45 +78,85 @@ impl GlobalSolCollector {
             best_complete_solution : None,
             best_incomplete_solution : None,
             best_incomplete_cost : None,
+            last_reported_incomplete_cost : None,
             cost_comparator,
             material_limit : None,
             tx_syncs,
             rx_solution_report,
+            reporter,
+            best_solution_time : None,
+            last_complete_improvement : None,
+            failed_workers : Vec::new(),
+            worker_statuses : BTreeMap::new(),
+            solution_pool : Vec::new(),
+            solution_pool_forms : Vec::new(),
+            last_incomplete_print : None,
+            pending_incomplete_report : None,
         }
     }
 
+    /// Forgets a previously received Ctrl-C, so subsequent monitors run until their own termination conditions again
+    pub fn clear_manual_termination() {
+        CTRLC_RECEIVED.store(false, atomic::Ordering::SeqCst);
+    }
+
     pub fn monitor(&mut self, gdrr_thread_handlers: Vec<thread::JoinHandle<()>>) {
         let start_time = time::Instant::now();
-        let max_run_time = self.config.max_run_time.unwrap_or(usize::MAX);
-        let running = Arc::new(AtomicBool::new(true));
-        let r = running.clone();
-
-        ctrlc::set_handler(move || {
-            r.store(false, atomic::Ordering::SeqCst);
-        }).expect("Error setting Ctrl-C handler");
-
-        while running.load(atomic::Ordering::SeqCst) &&
-            (time::Instant::now() - start_time).as_secs() < max_run_time as u64 {
-            thread::sleep(MONITOR_INTERVAL);
-
-            while let Ok(message) = self.rx_solution_report.try_recv() {
-                match message {
-                    SolutionReportMessage::NewCompleteSolution(thread_name, solution) => {
-                        self.report_new_complete_solution(thread_name, solution);
-                    }
-                    SolutionReportMessage::NewIncompleteStats(thread_name, stats) => {
-                        self.report_new_incomplete_cost(thread_name, stats);
-                    }
-                    SolutionReportMessage::NewIncompleteSolution(thread_name, solution) => {
-                        self.report_new_incomplete_solution(thread_name, solution);
-                    }
+        let max_run_time = self.config.run_time_limit().unwrap_or(Duration::MAX);
+        let monitor_interval = self.monitor_interval();
+        let max_stagnation_time = self.config.max_stagnation_time_ms.map(Duration::from_millis);
+        let status_interval = self.config.worker_status_interval_ms.map(Duration::from_millis);
+        let mut last_status_print = time::Instant::now();
+        //The handler can only be set once per process, it terminates the current and all subsequent monitors (until `clear_manual_termination`)
+        CTRLC_HANDLER.call_once(|| {
+            if ctrlc::set_handler(|| CTRLC_RECEIVED.store(true, atomic::Ordering::SeqCst)).is_err() {
+                timed_println!("Ctrl-C handler already set, manual termination unavailable");
+            }
+        });
+
+        while !CTRLC_RECEIVED.load(atomic::Ordering::SeqCst) &&
+            start_time.elapsed() < max_run_time {
+            thread::sleep(monitor_interval);
+
+            self.process_reports();
+            self.flush_incomplete_report(false);
+            if let Some(status_interval) = status_interval {
+                if last_status_print.elapsed() >= status_interval && !self.worker_statuses.is_empty() {
+                    timed_println!("{}\t{}", "<status>".bright_blue(), self.worker_status_line());
+                    last_status_print = time::Instant::now();
                 }
             }
-            if self.material_limit.unwrap_or(u64::MAX) == self._instance.smallest_sheet_value(){
+            if self.optimum_reached() {
                 timed_println!("Minimum material limit reached");
                 break;
             }
 
+            if self.config.stop_on_first_complete.unwrap_or(false) && self.best_complete_solution.is_some() {
+                timed_println!("First complete solution found");
+                break;
+            }
+
+            if let (Some(target_usage_pct), Some(best_complete_solution)) = (self.config.target_usage_pct, self.best_complete_solution.as_ref()) {
+                if best_complete_solution.usage() * 100.0 >= target_usage_pct {
+                    timed_println!("Target usage of {}% reached", target_usage_pct);
+                    break;
+                }
+            }
+
+            if let Some(max_stagnation_time) = max_stagnation_time {
+                //Measured from the start of this monitor until a complete solution improves the global best
+                let plateau_start = self.last_complete_improvement.map_or(start_time, |t| t.max(start_time));
+                if plateau_start.elapsed() >= max_stagnation_time {
+                    timed_println!("No new best complete solution found for {}ms", max_stagnation_time.as_millis());
+                    break;
+                }
+            }
+
+            if self.best_effort_reached() {
+                timed_println!("Best effort solution reached, all stock which fits the remaining parts is exhausted");
+                break;
+            }
+
             if gdrr_thread_handlers.iter().all(|h| h.is_finished()) {
                 timed_println!("All GDRR threads have finished execution");
                 break;
@@ -99,61 +170,237 @@ impl GlobalSolCollector {
                 Err(_) => {}
             }
         }
-        //Wait for them to finish
+        //Keep draining the reports until they are finished, threads block on a full channel while flushing their last reports
+        while !gdrr_thread_handlers.iter().all(|h| h.is_finished()) {
+            self.process_reports();
+            thread::sleep(monitor_interval);
+        }
         for handler in gdrr_thread_handlers {
-            handler.join().expect("Error joining GDRR thread");
+            let thread_name = handler.thread().name().unwrap_or("<>").to_string();
+            //A panicking thread does not bring down the others, its last reported solutions are preserved
+            if handler.join().is_err() {
+                timed_println!("{} {}", "GDRR thread failed:".bright_red(), thread_name);
+                self.failed_workers.push(thread_name);
+            }
         }
+        //Threads may have reported solutions after the last check (e.g. their best incomplete solution upon termination)
+        self.process_reports();
+        self.flush_incomplete_report(true);
+    }
 
-        match (self.best_complete_solution.as_ref(), self.best_incomplete_cost.as_ref()) {
-            (Some(_best_complete_solution), _) => {
-                timed_println!("{}:\t {}",
-                    "Final global solution".cyan().bold(),
-                    util::solution_stats_string(self.best_complete_solution.as_ref().unwrap()));
-            }
-            (None, Some(_best_incomplete_cost)) => {}
-            (None, None) => {
-                timed_println!("{}","No Global Solution".bright_red().bold());
+    /// Passes the final result on to the reporter, once the optimization is finished
+    pub fn report_final_summary(&mut self) {
+        //The final solutions should be convertible back into a problem with the same quantities
+        debug_assert!(self.best_complete_solution.iter().chain(self.best_incomplete_solution.iter()).all(|solution| {
+            let problem = Problem::from_sendable(solution.instance(), &self.config, solution);
+            problem.parttype_qtys() == solution.parttype_qtys() && problem.sheettype_qtys() == solution.sheettype_qtys()
+        }));
+        self.reporter.final_summary(self.best_complete_solution.as_ref(), self.best_incomplete_solution.as_ref());
+        if let Some(best_complete_solution) = self.best_complete_solution.as_ref() {
+            self.reporter.material_gap(best_complete_solution.cost().material_cost, self._instance.material_lower_bound());
+        }
+        if !self.solution_pool.is_empty() {
+            self.reporter.solution_pool(&self.solution_pool);
+        }
+    }
+
+    fn monitor_interval(&self) -> Duration {
+        Duration::from_millis(self.config.monitor_interval_ms.unwrap_or(DEFAULT_MONITOR_INTERVAL_MS))
+    }
+
+    /// Prepares the collector to monitor a new set of GDRR threads, e.g. for the next phase of a staged optimization.
+    /// The best solutions, material limit and failed threads found so far are kept.
+    pub fn start_next_phase(&mut self, config: Arc<Config>, tx_syncs: Vec<Sender<SyncMessage>>, rx_solution_report: Receiver<SolutionReportMessage>) {
+        self.config = config;
+        self.tx_syncs = tx_syncs;
+        self.rx_solution_report = rx_solution_report;
+        self.worker_statuses.clear();
+    }
+
+    /// Handles all solution reports currently waiting in the channel
+    fn process_reports(&mut self) {
+        while let Ok(message) = self.rx_solution_report.try_recv() {
+            match message {
+                SolutionReportMessage::NewCompleteSolution(thread_name, solution) => {
+                    self.report_new_complete_solution(thread_name, solution);
+                }
+                SolutionReportMessage::NewIncompleteStats(thread_name, stats) => {
+                    self.report_new_incomplete_cost(thread_name, stats);
+                }
+                SolutionReportMessage::NewIncompleteSolution(thread_name, solution) => {
+                    self.report_new_incomplete_solution(thread_name, solution);
+                }
+                SolutionReportMessage::WorkerStatus(thread_name, status) => {
+                    self.worker_statuses.insert(thread_name, status);
+                }
             }
         }
     }
 
+    /// Only strictly better solutions replace the current best one, so of two equal solutions the one reported first is kept.
+    /// Reports are processed in the order in which they were sent, which makes the earliest found solution win ties.
     fn report_new_complete_solution(&mut self, thread_name: String, solution: SendableSolution) {
+        self.add_to_pool(&solution);
+        if self.config.deterministic_tiebreak_hash.unwrap_or(false) && self.wins_tiebreak(&solution) {
+            //Equal on all metrics, but canonically smaller: replaces the best solution without changing the material limit
+            self.reporter.new_complete(&thread_name, &solution);
+            self.best_solution_time = Some(crate::EPOCH.elapsed());
+            self.last_complete_improvement = Some(time::Instant::now());
+            self.best_complete_solution = Some(solution);
+            return;
+        }
         if solution.cost().material_cost < self.material_limit.unwrap_or(u64::MAX) {
             if self.best_complete_solution.is_none()
                 || solution.cost().material_cost < self.best_complete_solution.as_ref().unwrap().cost().material_cost {
                 self.best_incomplete_cost = None;
+                self.last_reported_incomplete_cost = None;
                 self.best_incomplete_solution = None;
+                self.pending_incomplete_report = None;
                 self.material_limit = Some(solution.cost().material_cost);
-                timed_println!("[{}]\t{}{}", thread_name, "<complete>\t".cyan().bold(), util::solution_stats_string(&solution).cyan().bold());
+                self.reporter.new_complete(&thread_name, &solution);
+                self.best_solution_time = Some(crate::EPOCH.elapsed());
+                self.last_complete_improvement = Some(time::Instant::now());
                 self.best_complete_solution = Some(solution.clone());
 
+                //Threads which already terminated (e.g. after their maximum number of iterations) no longer receive
+                let share_incumbent = self.config.worker_restart_from_incumbent.unwrap_or(false);
                 for tx_sync in &self.tx_syncs {
-                    tx_sync.send(SyncMessage::SyncMatLimit(solution.cost().material_cost)).expect("Error sending sync matlimit message");
+                    let _ = tx_sync.send(SyncMessage::SyncMatLimit(solution.cost().material_cost));
+                    if share_incumbent {
+                        let _ = tx_sync.send(SyncMessage::SyncIncumbent(solution.clone()));
+                    }
                 }
             }
         }
     }
 
+    /// Keeps the `solutionPoolSize` best distinct complete solutions, ordered by material cost and then by the cost comparator.
+    /// Solutions with an identical structure (see `SendableSolution::canonical_form`) are only kept once.
+    fn add_to_pool(&mut self, solution: &SendableSolution) {
+        let pool_size = self.pool_capacity();
+        if pool_size == 0 {
+            return;
+        }
+        let canonical_form = solution.canonical_form(&HashConfig::default());
+        if self.solution_pool_forms.contains(&canonical_form) {
+            return;
+        }
+        let cost_comparator = self.cost_comparator;
+        let compare = |a: &SendableSolution, b: &SendableSolution| a.cost().material_cost.cmp(&b.cost().material_cost)
+            .then_with(|| cost_comparator(a.cost(), b.cost()));
+        //Of two equal solutions, the one received first ranks higher
+        let index = self.solution_pool.partition_point(|pooled| compare(pooled, solution) != Ordering::Greater);
+        if index < pool_size {
+            self.solution_pool.insert(index, solution.clone());
+            self.solution_pool_forms.insert(index, canonical_form);
+            self.solution_pool.truncate(pool_size);
+            self.solution_pool_forms.truncate(pool_size);
+        }
+    }
+
+    /// With a minimum completion fraction configured: whether no complete solution was found yet, but the best incomplete solution
+    /// includes at least that fraction of the part area and all of its excluded parts are out of stock (or fit on no sheettype at all).
+    /// Such a solution cannot be completed, so searching further would mostly be in vain.
+    fn best_effort_reached(&self) -> bool {
+        match (self.config.min_completion_fraction, &self.best_complete_solution, &self.best_incomplete_solution) {
+            (Some(min_completion_fraction), None, Some(best_incomplete_solution)) => {
+                best_incomplete_solution.cost().part_area_fraction_included() >= min_completion_fraction
+                    && determine_exclusion_reasons(best_incomplete_solution, &self._instance, self.config.min_pattern_utilization.unwrap_or(0.0)).iter()
+                    .all(|(_, _, reason)| !matches!(reason, ExclusionReason::NotPlaced | ExclusionReason::UnderutilizedPattern))
+            }
+            _ => false
+        }
+    }
+
+    /// Whether the solution ties with the best complete solution on all metrics, but has a lexicographically smaller structure
+    fn wins_tiebreak(&self, solution: &SendableSolution) -> bool {
+        match self.best_complete_solution.as_ref() {
+            Some(best) => {
+                best.cost().material_cost == solution.cost().material_cost
+                    && (self.cost_comparator)(solution.cost(), best.cost()) == Ordering::Equal
+                    && solution.canonical_form(&HashConfig::default()) < best.canonical_form(&HashConfig::default())
+            }
+            None => false
+        }
+    }
+
     fn report_new_incomplete_solution(&mut self, thread_name: String, solution: SendableSolution) {
         if self.best_complete_solution.is_none() {
             if self.best_incomplete_solution.is_none()
-                || (self.cost_comparator)(&solution.cost(), &self.best_incomplete_solution.as_ref().unwrap().cost()) == Ordering::Less {
-                timed_println!("[{}]\t{}{}", thread_name, "<incomplete>\t".bright_green(), util::solution_stats_string(&solution));
+                || (self.cost_comparator)(solution.cost(), self.best_incomplete_solution.as_ref().unwrap().cost()) == Ordering::Less {
+                //The solution is always kept, but only reported if it is a significant improvement
+                if self.significant_improvement(solution.cost()) {
+                    self.last_reported_incomplete_cost = Some(solution.cost().clone());
+                    self.print_incomplete(thread_name, IncompleteReport::Solution(solution.clone()));
+                }
                 self.best_incomplete_solution = Some(solution.clone());
+                self.best_solution_time = Some(crate::EPOCH.elapsed());
             }
         }
     }
 
     fn report_new_incomplete_cost(&mut self, thread_name: String, stats: SolutionStats) {
         if stats.cost.material_cost < self.material_limit.unwrap_or(u64::MAX) {
-            if self.best_incomplete_cost.is_none()
-                || (self.cost_comparator)(&stats.cost, &self.best_incomplete_cost.as_ref().unwrap()) == Ordering::Less {
-                timed_println!("[{}]\t{}{}", thread_name, "<incomplete>\t".bright_green(), util::compact_stats_string(&stats));
+            if (self.best_incomplete_cost.is_none()
+                || (self.cost_comparator)(&stats.cost, self.best_incomplete_cost.as_ref().unwrap()) == Ordering::Less)
+                && self.significant_improvement(&stats.cost) {
                 self.best_incomplete_cost = Some(stats.cost.clone());
+                self.last_reported_incomplete_cost = Some(stats.cost.clone());
+                self.print_incomplete(thread_name, IncompleteReport::Stats(stats));
             }
         }
     }
 
+    /// Passes an incomplete improvement on to the reporter, unless another one was printed less than `reportThrottleMs` ago.
+    /// In that case it is held back (replacing any improvement held back before) until the window has passed.
+    fn print_incomplete(&mut self, thread_name: String, report: IncompleteReport) {
+        let throttled = match (self.config.report_throttle_ms, self.last_incomplete_print) {
+            (Some(throttle_ms), Some(last_print)) => last_print.elapsed() < Duration::from_millis(throttle_ms),
+            _ => false
+        };
+        if throttled {
+            self.pending_incomplete_report = Some((thread_name, report));
+        } else {
+            self.pass_on_incomplete(&thread_name, &report);
+            self.last_incomplete_print = Some(time::Instant::now());
+        }
+    }
+
+    /// Prints the held back incomplete improvement once the throttle window has passed (or regardless, if forced)
+    fn flush_incomplete_report(&mut self, force: bool) {
+        let window_passed = match (self.config.report_throttle_ms, self.last_incomplete_print) {
+            (Some(throttle_ms), Some(last_print)) => last_print.elapsed() >= Duration::from_millis(throttle_ms),
+            _ => true
+        };
+        if force || window_passed {
+            if let Some((thread_name, report)) = self.pending_incomplete_report.take() {
+                self.pass_on_incomplete(&thread_name, &report);
+                self.last_incomplete_print = Some(time::Instant::now());
+            }
+        }
+    }
+
+    fn pass_on_incomplete(&mut self, thread_name: &str, report: &IncompleteReport) {
+        match report {
+            IncompleteReport::Solution(solution) => self.reporter.new_incomplete_solution(thread_name, solution),
+            IncompleteReport::Stats(stats) => self.reporter.new_incomplete(thread_name, stats),
+        }
+    }
+
+    /// Whether an incomplete solution excludes enough part area less than the last reported incomplete solution to be reported
+    fn significant_improvement(&self, cost: &Cost) -> bool {
+        match (self.config.min_report_improvement, self.last_reported_incomplete_cost.as_ref()) {
+            (Some(min_report_improvement), Some(last_reported)) => {
+                let improvement = last_reported.part_area_excluded.saturating_sub(cost.part_area_excluded);
+                match min_report_improvement {
+                    ReportImprovement::Absolute(min_area) => improvement >= min_area,
+                    ReportImprovement::Fraction(min_fraction) => improvement as f64 >= min_fraction * self._instance.total_part_area() as f64,
+                }
+            }
+            _ => true
+        }
+    }
+
 
     pub fn best_complete_solution(&self) -> &Option<SendableSolution> {
         &self.best_complete_solution
@@ -165,6 +412,65 @@ impl GlobalSolCollector {
         &self.best_incomplete_cost
     }
 
+    /// The best distinct complete solutions found, best first (empty without a `solutionPoolSize`)
+    pub fn best_solutions(&self) -> &[SendableSolution] {
+        &self.solution_pool
+    }
+
+    /// Number of solutions the pool keeps: `solutionPoolSize`, reduced to what fits in the `memoryBudgetMb`.
+    /// Every pooled solution (and its canonical form) is estimated to take up a fixed amount plus an amount per unit of demand of the instance,
+    /// the pool keeps at least the best solution when it is enabled.
+    pub fn pool_capacity(&self) -> usize {
+        let pool_size = self.config.solution_pool_size.unwrap_or(0);
+        match (self.config.memory_budget_mb, pool_size) {
+            (_, 0) | (None, _) => pool_size,
+            (Some(memory_budget_mb), _) => {
+                let n_parts = self._instance.parts().iter().map(|(_, qty)| *qty).sum::<usize>();
+                let bytes_per_solution = POOLED_BYTES_PER_SOLUTION + n_parts * POOLED_BYTES_PER_PART;
+                let budget_bytes = (memory_budget_mb.max(0.0) * 1024.0 * 1024.0) as usize;
+                (budget_bytes / bytes_per_solution).clamp(1, pool_size)
+            }
+        }
+    }
+
+    /// Time since the start of the program at which the current best solution was received
+    pub fn best_solution_time(&self) -> Option<Duration> {
+        self.best_solution_time
+    }
+
+    /// Latest reported status of every worker, by thread name
+    pub fn worker_statuses(&self) -> &BTreeMap<String, WorkerStatus> {
+        &self.worker_statuses
+    }
+
+    /// Compact summary of the latest status of every worker, e.g. `[T0] mat: 700, p_excl: 0 (1200 it) | [T1] ...`.
+    /// Workers which restarted also show the iterations since their last restart, e.g. `(1200 it, 300 since restart #2)`
+    pub fn worker_status_line(&self) -> String {
+        self.worker_statuses.iter()
+            .map(|(thread_name, status)| {
+                let iterations = match status.n_restarts {
+                    0 => format!("{} it", status.n_iterations),
+                    n_restarts => format!("{} it, {} since restart #{}", status.n_iterations, status.n_iterations_since_restart, n_restarts),
+                };
+                match status.best_cost.as_ref() {
+                    Some(cost) => format!("[{}] mat: {}, p_excl: {} ({})", thread_name, cost.material_cost, cost.part_area_excluded, iterations),
+                    None => format!("[{}] matlimit: {} ({})", thread_name, status.material_limit, iterations),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" | ")
+    }
+
+    /// Names of the GDRR threads which panicked
+    pub fn failed_workers(&self) -> &Vec<String> {
+        &self.failed_workers
+    }
+
+    /// Whether the material limit has reached the value of the cheapest sheet, no better solution can exist
+    pub fn optimum_reached(&self) -> bool {
+        self.material_limit.unwrap_or(u64::MAX) == self._instance.smallest_sheet_value()
+    }
+
     pub fn material_limit(&self) -> Option<u64> {
         self.material_limit
     }
@@ -1,6 +1,6 @@
 use std::cmp::Ordering;
 use std::sync::Arc;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
 
 use colored::*;
 
@@ -11,7 +11,7 @@ use crate::optimization::solutions::sendable_solution::SendableSolution;
 use crate::optimization::solutions::solution::Solution;
 use crate::optimization::solutions::solution_stats::SolutionStats;
 use crate::util::macros::{timed_thread_println};
-use crate::util::messages::{SolutionReportMessage, SyncMessage};
+use crate::util::messages::{SolutionReportMessage, SyncMessage, WorkerStatus};
 
 /// Local solution collectors collect and compare solutions from GDRR threads.
 /// It keeps track of the best complete and incomplete solutions.
@@ -24,9 +24,10 @@ pub struct LocalSolCollector<'a> {
     cost_comparator: fn(&Cost, &Cost) -> Ordering,
     material_limit: Option<u64>,
     rx_sync: Receiver<SyncMessage>,
-    tx_solution_report: Sender<SolutionReportMessage>,
+    tx_solution_report: SyncSender<SolutionReportMessage>,
     best_complete_transferred: bool,
     best_incomplete_transferred: bool,
+    global_incumbent: Option<SendableSolution>,
     terminate: bool,
 }
 
@@ -34,8 +35,9 @@ pub struct LocalSolCollector<'a> {
 impl<'a> LocalSolCollector<'a> {
     pub fn new(instance: Arc<Instance>,
                rx_sync: Receiver<SyncMessage>,
-               tx_solution_report: Sender<SolutionReportMessage>,
+               tx_solution_report: SyncSender<SolutionReportMessage>,
                cost_comparator: fn(&Cost, &Cost) -> Ordering,
+               material_limit: Option<u64>,
     ) -> Self {
 
         Self {
@@ -43,11 +45,12 @@ impl<'a> LocalSolCollector<'a> {
             best_complete_solution : None,
             best_incomplete_solution : None,
             cost_comparator,
-            material_limit : None,
+            material_limit,
             rx_sync,
             tx_solution_report,
             best_complete_transferred : false,
             best_incomplete_transferred : false,
+            global_incumbent : None,
             terminate : false,
         }
     }
@@ -94,12 +97,33 @@ impl<'a> LocalSolCollector<'a> {
                         self.lower_matlimit(mat_limit);
                     }
                 }
+                SyncMessage::SyncIncumbent(solution) => {
+                    self.global_incumbent = Some(solution);
+                }
                 SyncMessage::Terminate => {
                     timed_thread_println!("{}", "Terminate received".red());
                     self.terminate = true;
                 }
             }
         }
+        //Retry the reports which could not be sent earlier because the channel was full
+        if self.report_pending() {
+            self.tx_solution_report();
+        }
+    }
+
+    fn report_pending(&self) -> bool {
+        (self.best_complete_solution.is_some() && !self.best_complete_transferred) ||
+            (self.best_incomplete_solution.is_some() && !self.best_incomplete_transferred)
+    }
+
+    /// Sends a report without blocking, returns false if the channel is full
+    fn try_send(&self, message: SolutionReportMessage) -> bool {
+        match self.tx_solution_report.try_send(message) {
+            Ok(_) => true,
+            Err(TrySendError::Full(_)) => false,
+            Err(TrySendError::Disconnected(_)) => panic!("Failed to send solution report message: channel disconnected")
+        }
     }
 
     fn tx_solution_report(&mut self) {
@@ -111,7 +135,7 @@ impl<'a> LocalSolCollector<'a> {
                     let message = match self.material_limit {
                         Some(_) => {
                             //timed_thread_println!("{}", "Sending solution stats");
-                            SolutionReportMessage::NewIncompleteStats(thread_name, SolutionStats::new(cost, best_incomplete_solution.usage(), best_incomplete_solution.n_layouts()))
+                            SolutionReportMessage::NewIncompleteStats(thread_name, SolutionStats::new(cost, best_incomplete_solution.usage(), best_incomplete_solution.n_layouts(), self.instance.sheet_lower_bound()))
                         }
                         None => {
                             //timed_thread_println!("{}", "Sending full incomplete solution");
@@ -119,9 +143,8 @@ impl<'a> LocalSolCollector<'a> {
                             SolutionReportMessage::NewIncompleteSolution(thread_name, sendable_solution)
                         }
                     };
-                    self.tx_solution_report.send(message).expect("Failed to send solution report message");
-
-                    self.best_incomplete_transferred = true;
+                    //If the channel is full, the report is retried later
+                    self.best_incomplete_transferred = self.try_send(message);
                 }
             }
             None => {}
@@ -132,11 +155,9 @@ impl<'a> LocalSolCollector<'a> {
                     let thread_name = std::thread::current().name().unwrap().parse().unwrap();
                     let sendable_solution = SendableSolution::new(self.instance.clone(), &best_complete_solution);
                     //timed_thread_println!("{}", "Sending full solution".green());
-                    self.tx_solution_report.send(
+                    self.best_complete_transferred = self.try_send(
                         SolutionReportMessage::NewCompleteSolution(thread_name, sendable_solution)
-                    ).expect("Failed to send solution report message");
-
-                    self.best_complete_transferred = true;
+                    );
                 }
             }
             None => {}
@@ -163,7 +184,89 @@ impl<'a> LocalSolCollector<'a> {
         self.material_limit.unwrap_or(u64::MAX)
     }
 
+    /// Latest best complete solution of all threads received from the GlobalSolCollector, if it shares them
+    pub fn global_incumbent(&self) -> Option<&SendableSolution> {
+        self.global_incumbent.as_ref()
+    }
+
+    /// Reports the progress of the GDRR thread. Statuses are not essential: they are dropped if the channel is full
+    pub fn report_status(&self, status: WorkerStatus) {
+        let thread_name = std::thread::current().name().unwrap().parse().unwrap();
+        self.try_send(SolutionReportMessage::WorkerStatus(thread_name, status));
+    }
+
+    /// Sends all reports which are still pending, blocking until there is room in the channel.
+    /// Should only be called once the GDRR thread is finished, the GlobalSolCollector keeps draining the channel until all threads have finished.
+    pub fn flush(&mut self) {
+        let thread_name: String = std::thread::current().name().unwrap().parse().unwrap();
+        if let (Some(solution), false) = (self.best_incomplete_solution.as_ref(), self.best_incomplete_transferred) {
+            let message = match self.material_limit {
+                Some(_) => SolutionReportMessage::NewIncompleteStats(thread_name.clone(), SolutionStats::new(solution.cost().clone(), solution.usage(), solution.n_layouts(), self.instance.sheet_lower_bound())),
+                None => SolutionReportMessage::NewIncompleteSolution(thread_name.clone(), SendableSolution::new(self.instance.clone(), solution))
+            };
+            self.tx_solution_report.send(message).expect("Failed to send solution report message");
+            self.best_incomplete_transferred = true;
+        }
+        if let (Some(solution), false) = (self.best_complete_solution.as_ref(), self.best_complete_transferred) {
+            let message = SolutionReportMessage::NewCompleteSolution(thread_name, SendableSolution::new(self.instance.clone(), solution));
+            self.tx_solution_report.send(message).expect("Failed to send solution report message");
+            self.best_complete_transferred = true;
+        }
+    }
+
+    /// Whether a Terminate message was received.
+    /// The flag only changes in `rx_sync`, which the worker calls between complete iterations (never during a ruin or recreate),
+    /// so a worker which stops on it always leaves its problem in a consistent state.
     pub fn terminate(&self) -> bool {
         self.terminate
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{channel, sync_channel};
+
+    use serde_json::json;
+
+    use crate::core::entities::layout::Layout;
+    use crate::core::insertion::node_blueprint::NodeBlueprint;
+    use crate::core::leftover_valuator;
+    use crate::core::rotation::Rotation;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::sol_collectors::local_sol_collector::LocalSolCollector;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::Orientation;
+    use crate::util::messages::SolutionReportMessage;
+    use crate::util::test_util::parse;
+
+    #[test]
+    fn report_which_does_not_fit_in_the_channel_is_retried() {
+        let (_, instance, config) = parse(json!({
+            "Name": "retry",
+            "Objects": [{"Length": 10, "Height": 10, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 10, "Height": 10, "Demand": 1, "Value": 1}]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let (_tx_sync, rx_sync) = channel();
+        let (tx_solution_report, rx_solution_report) = sync_channel(1);
+        let mut collector = LocalSolCollector::new(instance.clone(), rx_sync, tx_solution_report, config.cost_comparator(), None);
+        let mut problem = Problem::new(&instance, &config);
+
+        //The empty solution fills the channel
+        let incomplete = problem.create_solution(&None, None);
+        collector.report_problem_solution(&incomplete);
+        let mut top_node = NodeBlueprint::new(10, 10, None, Orientation::Horizontal);
+        top_node.add_child(NodeBlueprint::new(10, 10, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Vertical));
+        problem.register_layout(Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance));
+        let complete = problem.create_solution(&None, None);
+        assert!(complete.is_complete());
+        collector.report_problem_solution(&complete);
+
+        assert!(matches!(rx_solution_report.try_recv(), Ok(SolutionReportMessage::NewIncompleteSolution(..))));
+        assert!(rx_solution_report.try_recv().is_err());
+        //The complete solution is sent as soon as there is room again
+        collector.rx_sync();
+        assert!(matches!(rx_solution_report.try_recv(), Ok(SolutionReportMessage::NewCompleteSolution(..))));
+        collector.rx_sync();
+        assert!(rx_solution_report.try_recv().is_err());
+    }
+}
@@ -1,2 +1,5 @@
 pub mod local_sol_collector;
-pub mod global_sol_collector;
\ No newline at end of file
+pub mod global_sol_collector;
+pub mod reporter;
+#[cfg(any(test, feature = "collector-harness"))]
+pub mod scripted_worker;
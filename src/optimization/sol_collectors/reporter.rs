@@ -0,0 +1,150 @@
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use colored::*;
+
+use crate::io::json_format::JsonInstance;
+use crate::io::parser;
+use crate::optimization::config::Config;
+
+use crate::optimization::solutions::sendable_solution::SendableSolution;
+use crate::optimization::solutions::solution_stats::SolutionStats;
+use crate::util::macros::timed_println;
+use crate::util::util;
+
+/// Receives the events of a GlobalSolCollector (new best solutions, final result).
+/// Allows the output of the optimization to be redirected, e.g. to a GUI or a log.
+pub trait Reporter {
+    fn new_complete(&mut self, thread_name: &str, solution: &SendableSolution);
+
+    fn new_incomplete(&mut self, thread_name: &str, stats: &SolutionStats);
+
+    /// A new best incomplete solution, for which the thread reported the full solution instead of only its statistics
+    fn new_incomplete_solution(&mut self, thread_name: &str, solution: &SendableSolution);
+
+    fn final_summary(&mut self, best_complete_solution: Option<&SendableSolution>, best_incomplete_solution: Option<&SendableSolution>);
+
+    /// The best distinct complete solutions kept in the solution pool, best first, after the final summary
+    fn solution_pool(&mut self, _solutions: &[SendableSolution]) {}
+
+    /// The material cost of the best complete solution compared to the lower bound of the instance (None if it has no complete solution)
+    fn material_gap(&mut self, _material_cost: u64, _lower_bound: Option<u64>) {}
+}
+
+/// Default Reporter, prints all events to stdout
+pub struct ConsoleReporter;
+
+impl Reporter for ConsoleReporter {
+    fn new_complete(&mut self, thread_name: &str, solution: &SendableSolution) {
+        timed_println!("[{}]\t{}{}", thread_name, "<complete>\t".cyan().bold(), util::solution_stats_string(solution).cyan().bold());
+    }
+
+    fn new_incomplete(&mut self, thread_name: &str, stats: &SolutionStats) {
+        timed_println!("[{}]\t{}{}", thread_name, "<incomplete>\t".bright_green(), util::compact_stats_string(stats));
+    }
+
+    fn new_incomplete_solution(&mut self, thread_name: &str, solution: &SendableSolution) {
+        timed_println!("[{}]\t{}{}", thread_name, "<incomplete>\t".bright_green(), util::solution_stats_string(solution));
+    }
+
+    fn final_summary(&mut self, best_complete_solution: Option<&SendableSolution>, best_incomplete_solution: Option<&SendableSolution>) {
+        match (best_complete_solution, best_incomplete_solution) {
+            (Some(best_complete_solution), _) => {
+                timed_println!("{}:\t {}",
+                    "Final global solution".cyan().bold(),
+                    util::solution_stats_string(best_complete_solution));
+            }
+            (None, Some(best_incomplete_solution)) => {
+                timed_println!("{}:\t {}",
+                    "Best effort solution (incomplete)".bright_green().bold(),
+                    util::solution_stats_string(best_incomplete_solution));
+            }
+            (None, None) => {
+                timed_println!("{}","No Global Solution".bright_red().bold());
+            }
+        }
+    }
+
+    fn solution_pool(&mut self, solutions: &[SendableSolution]) {
+        for (i, solution) in solutions.iter().enumerate() {
+            timed_println!("{} {}:\t {}", "Pooled solution".cyan(), i + 1, util::solution_stats_string(solution));
+        }
+    }
+
+    fn material_gap(&mut self, material_cost: u64, lower_bound: Option<u64>) {
+        match lower_bound {
+            Some(0) | None => timed_println!("{}\t (mat: {}, no material lower bound)", "Material gap:".cyan(), material_cost),
+            Some(lower_bound) => timed_println!("{}\t (mat: {}, lower bound: {}, gap: {:.3}%)", "Material gap:".cyan(), material_cost, lower_bound,
+                (material_cost as f64 - lower_bound as f64) / lower_bound as f64 * 100.0),
+        }
+    }
+}
+
+/// Writes every new best complete solution to a numbered JSON file (`best_1.json`, `best_2.json`, ...) in a directory,
+/// so external processes can react to improvements during the optimization. All events are also passed on to the inner Reporter.
+/// Every file is first written under a temporary name and then renamed, so a file never appears partially written.
+pub struct IncrementalOutputReporter {
+    inner: Box<dyn Reporter>,
+    output_dir: PathBuf,
+    json_instance: JsonInstance,
+    config: Arc<Config>,
+    config_path: PathBuf,
+    n_written: usize,
+}
+
+impl IncrementalOutputReporter {
+    /// Fails if the output directory does not exist and cannot be created
+    pub fn new(inner: Box<dyn Reporter>, output_dir: PathBuf, json_instance: JsonInstance, config: Arc<Config>, config_path: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&output_dir)?;
+        Ok(Self {
+            inner,
+            output_dir,
+            json_instance,
+            config,
+            config_path,
+            n_written: 0,
+        })
+    }
+
+    fn write_solution(&mut self, solution: &SendableSolution) -> std::io::Result<()> {
+        let json_solution = parser::generate_json_solution(&self.json_instance, solution, &self.config, &self.config_path);
+        let file_name = format!("best_{}.json", self.n_written + 1);
+        let tmp_path = self.output_dir.join(format!(".{}.tmp", file_name));
+        let mut tmp_file = File::create(&tmp_path)?;
+        serde_json::to_writer_pretty(&mut tmp_file, &json_solution)?;
+        tmp_file.sync_all()?;
+        std::fs::rename(&tmp_path, self.output_dir.join(file_name))?;
+        self.n_written += 1;
+        Ok(())
+    }
+}
+
+impl Reporter for IncrementalOutputReporter {
+    fn new_complete(&mut self, thread_name: &str, solution: &SendableSolution) {
+        self.inner.new_complete(thread_name, solution);
+        if let Err(err) = self.write_solution(solution) {
+            timed_println!("{} {}", "Could not write incremental solution:".bright_red(), err);
+        }
+    }
+
+    fn new_incomplete(&mut self, thread_name: &str, stats: &SolutionStats) {
+        self.inner.new_incomplete(thread_name, stats);
+    }
+
+    fn new_incomplete_solution(&mut self, thread_name: &str, solution: &SendableSolution) {
+        self.inner.new_incomplete_solution(thread_name, solution);
+    }
+
+    fn final_summary(&mut self, best_complete_solution: Option<&SendableSolution>, best_incomplete_solution: Option<&SendableSolution>) {
+        self.inner.final_summary(best_complete_solution, best_incomplete_solution);
+    }
+
+    fn solution_pool(&mut self, solutions: &[SendableSolution]) {
+        self.inner.solution_pool(solutions);
+    }
+
+    fn material_gap(&mut self, material_cost: u64, lower_bound: Option<u64>) {
+        self.inner.material_gap(material_cost, lower_bound);
+    }
+}
@@ -0,0 +1,378 @@
+//! Harness to drive a GlobalSolCollector in isolation, without running the GDRR algorithm.
+//! Mock workers send a scripted sequence of reports to the collector, which makes its behavior deterministic and testable.
+
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver, sync_channel, SyncSender, TrySendError};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::{Config, Instance};
+use crate::optimization::sol_collectors::global_sol_collector::{DEFAULT_REPORT_CHANNEL_CAPACITY, GlobalSolCollector};
+use crate::optimization::sol_collectors::reporter::Reporter;
+use crate::util::messages::{SolutionReportMessage, SyncMessage};
+
+/// Spawns a mock worker which sends all scripted reports in order and then finishes, or panics if `panics` is set.
+/// If `after_terminate` is not empty, the worker first waits for the Terminate message and then sends those reports as well,
+/// as a GDRR thread reporting its last solutions upon termination would.
+/// Like a GDRR thread, it does not block on a full channel but retries the report until there is room.
+pub fn spawn_scripted_worker(name: String, script: Vec<SolutionReportMessage>, after_terminate: Vec<SolutionReportMessage>, panics: bool,
+                             rx_sync: Receiver<SyncMessage>, tx_solution_report: SyncSender<SolutionReportMessage>) -> JoinHandle<()> {
+    thread::Builder::new().name(name).spawn(move || {
+        for message in script {
+            send_retrying(&tx_solution_report, message);
+        }
+        if !after_terminate.is_empty() {
+            while !matches!(rx_sync.recv().expect("Failed to receive sync message"), SyncMessage::Terminate) {}
+            for message in after_terminate {
+                send_retrying(&tx_solution_report, message);
+            }
+        }
+        if panics {
+            panic!("Scripted worker failure");
+        }
+    }).expect("Failed to spawn scripted worker")
+}
+
+fn send_retrying(tx_solution_report: &SyncSender<SolutionReportMessage>, mut message: SolutionReportMessage) {
+    loop {
+        match tx_solution_report.try_send(message) {
+            Ok(_) => return,
+            Err(TrySendError::Full(returned)) => {
+                message = returned;
+                thread::sleep(Duration::from_millis(1));
+            }
+            Err(TrySendError::Disconnected(_)) => panic!("Failed to send scripted report: channel disconnected")
+        }
+    }
+}
+
+/// Runs a GlobalSolCollector with one mock worker per script until all of them have finished.
+/// Returns the collector, so its final state (best solutions, material limit) can be inspected.
+pub fn run_scripted(instance: Arc<Instance>, config: Arc<Config>, scripts: Vec<Vec<SolutionReportMessage>>, reporter: Box<dyn Reporter>) -> GlobalSolCollector {
+    let scripts = scripts.into_iter().map(|script| (script, false)).collect();
+    run_scripted_workers(instance, config, scripts, reporter)
+}
+
+/// Like `run_scripted`, the mock workers of which the script is paired with `true` panic after sending their reports.
+/// The workers are named `T0`, `T1`, ... in the order of their scripts.
+pub fn run_scripted_workers(instance: Arc<Instance>, config: Arc<Config>, scripts: Vec<(Vec<SolutionReportMessage>, bool)>, reporter: Box<dyn Reporter>) -> GlobalSolCollector {
+    let workers = scripts.into_iter().map(|(script, panics)| (script, vec![], panics)).collect();
+    run_scripted_harness(instance, config, workers, reporter)
+}
+
+/// Like `run_scripted`, but every mock worker sends the second script of its pair only after the monitor has issued the Terminate message.
+/// The run therefore only ends by one of the monitor's own stopping criteria, such as the time limit.
+pub fn run_scripted_until_terminate(instance: Arc<Instance>, config: Arc<Config>, scripts: Vec<(Vec<SolutionReportMessage>, Vec<SolutionReportMessage>)>, reporter: Box<dyn Reporter>) -> GlobalSolCollector {
+    let workers = scripts.into_iter().map(|(script, after_terminate)| (script, after_terminate, false)).collect();
+    run_scripted_harness(instance, config, workers, reporter)
+}
+
+fn run_scripted_harness(instance: Arc<Instance>, config: Arc<Config>, workers: Vec<(Vec<SolutionReportMessage>, Vec<SolutionReportMessage>, bool)>, reporter: Box<dyn Reporter>) -> GlobalSolCollector {
+    let (tx_solution_report, rx_solution_report) = sync_channel(config.report_channel_capacity.unwrap_or(DEFAULT_REPORT_CHANNEL_CAPACITY).max(1));
+    let mut tx_syncs = Vec::new();
+    let mut handles = Vec::new();
+
+    for (i, (script, after_terminate, panics)) in workers.into_iter().enumerate() {
+        let (tx_sync, rx_sync) = channel();
+        tx_syncs.push(tx_sync);
+        handles.push(spawn_scripted_worker(format!("T{}", i), script, after_terminate, panics, rx_sync, tx_solution_report.clone()));
+    }
+
+    let cost_comparator = config.cost_comparator();
+    let mut global_sol_collector = GlobalSolCollector::new(instance, config, tx_syncs, rx_solution_report, cost_comparator, reporter);
+    global_sol_collector.monitor(handles);
+    global_sol_collector.report_final_summary();
+    global_sol_collector
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use crate::core::cost::Cost;
+    use crate::core::entities::layout::Layout;
+    use crate::core::insertion::node_blueprint::NodeBlueprint;
+    use crate::core::leftover_valuator;
+    use crate::core::rotation::Rotation;
+    use crate::io::json_format::JsonSolution;
+    use crate::optimization::optimize::optimization_result;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::sol_collectors::global_sol_collector::GlobalSolCollector;
+    use crate::optimization::sol_collectors::reporter::{ConsoleReporter, IncrementalOutputReporter, Reporter};
+    use crate::optimization::sol_collectors::scripted_worker::{run_scripted, run_scripted_until_terminate, run_scripted_workers};
+    use crate::optimization::solutions::sendable_solution::SendableSolution;
+    use crate::optimization::solutions::solution::Solution;
+    use crate::optimization::solutions::solution_stats::SolutionStats;
+    use crate::Orientation;
+    use crate::util::messages::{SolutionReportMessage, WorkerStatus};
+    use crate::util::messages::SolutionReportMessage::{NewCompleteSolution, NewIncompleteSolution};
+    use crate::util::test_util::{config_path, parse, solve};
+
+    /// Instance of the collector in the scripted runs, the reported solutions do not have to belong to it
+    fn scripted_instance() -> serde_json::Value {
+        json!({
+            "Name": "scripted",
+            "Objects": [{"Length": 10, "Height": 10, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 10, "Height": 5, "Demand": 1, "Value": 1}]
+        })
+    }
+
+    /// Solution producing `demand` parts of the given height on a single sheet with the given cost, as far as they fit.
+    /// The structure of the solution differs per part height. The layout is built directly, without running the optimization.
+    fn solution(part_height: u64, demand: usize, cost: u64) -> SendableSolution {
+        let (_, instance, config) = parse(json!({
+            "Name": "scripted",
+            "Objects": [{"Length": 10, "Height": 10, "Stock": 1, "Cost": cost}],
+            "Items": [{"Length": 10, "Height": part_height, "Demand": demand, "Value": 1}]
+        }), json!({"sheetValuationMode": "cost"}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let n_parts = usize::min(demand, (10 / part_height) as usize);
+        let mut top_node = NodeBlueprint::new(10, 10, None, Orientation::Horizontal);
+        for _ in 0..n_parts {
+            top_node.add_child(NodeBlueprint::new(10, part_height, Some((instance.get_parttype(0), Rotation::Default)), Orientation::Vertical));
+        }
+        top_node.add_child(NodeBlueprint::new(10, 10 - n_parts as u64 * part_height, None, Orientation::Vertical));
+        let mut problem = Problem::new(&instance, &config);
+        problem.register_layout(Layout::from_top_node_blueprint(0, instance.get_sheettype(0), &top_node, &instance));
+        SendableSolution::new(instance.clone(), &problem.create_solution(&None, None))
+    }
+
+    fn complete(part_height: u64, cost: u64) -> SolutionReportMessage {
+        NewCompleteSolution("T0".to_string(), solution(part_height, 1, cost))
+    }
+
+    fn run(scripts: Vec<Vec<SolutionReportMessage>>, config: serde_json::Value) -> GlobalSolCollector {
+        let (_, instance, config) = parse(scripted_instance(), config);
+        run_scripted(instance, config, scripts, Box::new(ConsoleReporter))
+    }
+
+    /// Records the events it receives instead of printing them
+    struct RecordingReporter(Arc<Mutex<Vec<String>>>);
+
+    impl Reporter for RecordingReporter {
+        fn new_complete(&mut self, thread_name: &str, _solution: &SendableSolution) {
+            self.0.lock().unwrap().push(format!("complete {}", thread_name));
+        }
+
+        fn new_incomplete(&mut self, thread_name: &str, _stats: &SolutionStats) {
+            self.0.lock().unwrap().push(format!("incomplete stats {}", thread_name));
+        }
+
+        fn new_incomplete_solution(&mut self, thread_name: &str, _solution: &SendableSolution) {
+            self.0.lock().unwrap().push(format!("incomplete {}", thread_name));
+        }
+
+        fn final_summary(&mut self, best_complete_solution: Option<&SendableSolution>, _best_incomplete_solution: Option<&SendableSolution>) {
+            let summary = match best_complete_solution {
+                Some(_) => "final complete",
+                None => "final none"
+            };
+            self.0.lock().unwrap().push(summary.to_string());
+        }
+    }
+
+    #[test]
+    fn cheaper_complete_solutions_lower_the_material_limit() {
+        let collector = run(vec![vec![complete(5, 300), complete(5, 200), complete(5, 250)]], json!({}));
+        assert_eq!(collector.material_limit(), Some(200));
+        assert_eq!(collector.best_complete_solution().as_ref().unwrap().cost().material_cost, 200);
+        assert!(collector.best_incomplete_solution().is_none());
+    }
+
+    #[test]
+    fn incomplete_solutions_are_kept_until_a_complete_one_is_found() {
+        let incomplete = || NewIncompleteSolution("T1".to_string(), solution(10, 2, 100));
+        let collector = run(vec![vec![incomplete()]], json!({}));
+        assert!(collector.best_complete_solution().is_none());
+        assert!(collector.best_incomplete_solution().as_ref().unwrap().cost().part_area_excluded > 0);
+
+        let collector = run(vec![vec![incomplete(), complete(5, 100)]], json!({}));
+        assert!(collector.best_complete_solution().is_some());
+        assert!(collector.best_incomplete_solution().is_none());
+    }
+
+    #[test]
+    fn of_equal_solutions_the_first_reported_is_kept() {
+        let (first, second) = (solution(5, 1, 100), solution(6, 1, 100));
+        assert_ne!(first.canonical_form(&Default::default()), second.canonical_form(&Default::default()));
+        let collector = run(vec![vec![NewCompleteSolution("T0".to_string(), first.clone()), NewCompleteSolution("T0".to_string(), second)]], json!({}));
+        let best = collector.best_complete_solution().as_ref().unwrap();
+        assert_eq!(best.canonical_form(&Default::default()), first.canonical_form(&Default::default()));
+    }
+
+    #[test]
+    fn pool_retains_the_best_distinct_solutions_in_order() {
+        let script = vec![complete(5, 300), complete(6, 100), complete(7, 400), complete(8, 200), complete(9, 100)];
+        let collector = run(vec![script], json!({"solutionPoolSize": 3}));
+        let pooled = collector.best_solutions().iter().map(|s| s.cost().material_cost).collect::<Vec<u64>>();
+        assert_eq!(pooled, vec![100, 100, 200]);
+        //Of the two solutions with equal cost, the one received first ranks higher
+        assert_eq!(collector.best_solutions()[0].canonical_form(&Default::default()), solution(6, 1, 100).canonical_form(&Default::default()));
+    }
+
+    #[test]
+    fn pool_keeps_identical_solutions_once() {
+        let script = vec![complete(5, 300), complete(5, 300), complete(6, 300)];
+        let collector = run(vec![script], json!({"solutionPoolSize": 3}));
+        assert_eq!(collector.best_solutions().len(), 2);
+    }
+
+    #[test]
+    fn tiny_memory_budget_shrinks_the_pool_to_the_best_solution() {
+        let script = || vec![complete(5, 300), complete(6, 200), complete(7, 100)];
+        let collector = run(vec![script()], json!({"solutionPoolSize": 3, "memoryBudgetMb": 0.0001}));
+        assert_eq!(collector.pool_capacity(), 1);
+        assert_eq!(collector.best_solutions().iter().map(|s| s.cost().material_cost).collect::<Vec<u64>>(), vec![100]);
+        assert_eq!(collector.best_complete_solution().as_ref().unwrap().cost().material_cost, 100);
+        //A budget which fits the pool leaves it unchanged
+        let collector = run(vec![script()], json!({"solutionPoolSize": 3, "memoryBudgetMb": 1}));
+        assert_eq!(collector.pool_capacity(), 3);
+        assert_eq!(collector.best_solutions().len(), 3);
+    }
+
+    #[test]
+    fn panicking_worker_is_surfaced_and_its_solution_kept() {
+        let (_, instance, config) = parse(scripted_instance(), json!({}));
+        let scripts = vec![(vec![complete(5, 100)], true), (vec![complete(6, 300)], false)];
+        let collector = run_scripted_workers(instance, config, scripts, Box::new(ConsoleReporter));
+        match optimization_result(&collector) {
+            Err(solve_error) => {
+                assert_eq!(solve_error.failed_workers, vec!["T0".to_string()]);
+                assert_eq!(solve_error.best_solution.unwrap().cost().material_cost, 100);
+            }
+            Ok(_) => panic!("the failed worker is not reported")
+        }
+    }
+
+    #[test]
+    fn every_improvement_is_written_to_a_numbered_file() {
+        let json_instance = scripted_instance();
+        let (json_instance, instance, config) = parse(json_instance, json!({"sheetValuationMode": "cost"}));
+        let output_dir = std::env::temp_dir().join(format!("gdrr_2bp_incremental_{}", std::process::id()));
+        let reporter = IncrementalOutputReporter::new(Box::new(ConsoleReporter), output_dir.clone(), json_instance, config.clone(), config_path()).unwrap();
+        run_scripted(instance, config, vec![vec![complete(5, 300), complete(5, 250), complete(5, 200)]], Box::new(reporter));
+
+        let costs = (1..=3)
+            .map(|n| {
+                let file = std::fs::File::open(output_dir.join(format!("best_{}.json", n))).unwrap();
+                let json_solution: JsonSolution = serde_json::from_reader(file).unwrap();
+                json_solution.statistics.material_cost
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(costs, vec![300, 250, 200]);
+        assert!(!output_dir.join("best_4.json").exists());
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn unusable_incremental_output_dir_is_an_error() {
+        let (json_instance, _, config) = parse(scripted_instance(), json!({}));
+        //A directory cannot be created inside a file
+        let file_path = std::env::temp_dir().join(format!("gdrr_2bp_not_a_dir_{}", std::process::id()));
+        std::fs::write(&file_path, "").unwrap();
+        let reporter = IncrementalOutputReporter::new(Box::new(ConsoleReporter), file_path.join("out"), json_instance, config, config_path());
+        assert!(reporter.is_err());
+        std::fs::remove_file(&file_path).unwrap();
+    }
+
+    #[test]
+    fn recording_reporter_receives_the_events_in_order() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (_, instance, config) = parse(scripted_instance(), json!({}));
+        let scripts = vec![vec![
+            NewIncompleteSolution("T1".to_string(), solution(10, 2, 100)),
+            complete(5, 100),
+        ]];
+        run_scripted(instance, config, scripts, Box::new(RecordingReporter(events.clone())));
+        assert_eq!(*events.lock().unwrap(), vec!["incomplete T1", "complete T0", "final complete"]);
+    }
+
+    #[test]
+    fn canonically_smallest_of_equal_solutions_wins_the_tiebreak() {
+        //Same parts, sheet and leftover, but the parttypes are listed in a different order
+        let swapped_solution = |swapped: bool| {
+            let mut items = vec![
+                json!({"Length": 10, "Height": 3, "Demand": 1, "Value": 1}),
+                json!({"Length": 10, "Height": 2, "Demand": 1, "Value": 1})
+            ];
+            if swapped {
+                items.reverse();
+            }
+            solve(json!({"Name": "scripted", "Objects": [{"Length": 10, "Height": 10, "Stock": 1, "Cost": 100}], "Items": items}), json!({})).3
+        };
+        let (a, b) = (swapped_solution(false), swapped_solution(true));
+        assert_eq!(a.cost(), b.cost());
+        assert_ne!(a.canonical_form(&Default::default()), b.canonical_form(&Default::default()));
+        let canonical = std::cmp::min(a.canonical_form(&Default::default()), b.canonical_form(&Default::default()));
+
+        for _ in 0..3 {
+            for (first, second) in [(&a, &b), (&b, &a)] {
+                let script = vec![NewCompleteSolution("T0".to_string(), first.clone()), NewCompleteSolution("T0".to_string(), second.clone())];
+                let collector = run(vec![script], json!({"deterministicTiebreakHash": true}));
+                assert_eq!(collector.best_complete_solution().as_ref().unwrap().canonical_form(&Default::default()), canonical);
+            }
+        }
+    }
+
+    #[test]
+    fn worker_statuses_are_recorded_without_affecting_the_best_solution() {
+        let status = |best_cost: Option<Cost>, n_iterations: usize| WorkerStatus { material_limit: 200, best_cost, n_iterations, n_restarts: 0, n_iterations_since_restart: n_iterations };
+        let scripts = vec![
+            vec![complete(5, 200), SolutionReportMessage::WorkerStatus("T0".to_string(), status(Some(Cost::new(50, 0.0, 0, 50, 0)), 1200))],
+            vec![SolutionReportMessage::WorkerStatus("T1".to_string(), status(None, 300))],
+        ];
+        let collector = run(scripts, json!({}));
+        assert_eq!(collector.worker_statuses().len(), 2);
+        assert_eq!(collector.worker_status_line(), "[T0] mat: 50, p_excl: 0 (1200 it) | [T1] matlimit: 200 (300 it)");
+        //The cheaper cost in the status is not a solution
+        assert_eq!(collector.material_limit(), Some(200));
+        assert_eq!(collector.best_complete_solution().as_ref().unwrap().cost().material_cost, 200);
+    }
+
+    #[test]
+    fn no_complete_report_is_lost_when_the_channel_is_full() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (_, instance, config) = parse(scripted_instance(), json!({"reportChannelCapacity": 1, "monitorIntervalMs": 50}));
+        //The worker is several reports ahead of the monitor, which only drains the channel every interval
+        let script = (0..4).map(|i| complete(5, 400 - i * 100)).collect();
+        let collector = run_scripted_workers(instance, config, vec![(script, false)], Box::new(RecordingReporter(events.clone())));
+        assert_eq!(*events.lock().unwrap(), vec!["complete T0"; 4].into_iter().chain(["final complete"]).collect::<Vec<_>>());
+        assert_eq!(collector.material_limit(), Some(100));
+    }
+
+    #[test]
+    fn solution_reported_after_the_time_limit_is_in_the_final_result() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (_, instance, config) = parse(scripted_instance(), json!({"maxRunTimeMs": 100, "monitorIntervalMs": 10}));
+        let scripts = vec![(vec![complete(5, 300)], vec![complete(6, 200)])];
+        let collector = run_scripted_until_terminate(instance, config, scripts, Box::new(RecordingReporter(events.clone())));
+        assert_eq!(collector.best_complete_solution().as_ref().unwrap().cost().material_cost, 200);
+        assert_eq!(collector.material_limit(), Some(200));
+        assert_eq!(*events.lock().unwrap(), vec!["complete T0", "complete T0", "final complete"]);
+    }
+
+    #[test]
+    fn incomplete_improvements_within_the_throttle_window_are_coalesced() {
+        let events = |config: serde_json::Value| {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let (_, instance, config) = parse(scripted_instance(), config);
+            //Every solution excludes one part less than the previous one
+            let script = (2..=5).rev().map(|demand| NewIncompleteSolution("T1".to_string(), solution(10, demand, 100))).collect();
+            let start = std::time::Instant::now();
+            run_scripted(instance, config, vec![script], Box::new(RecordingReporter(events.clone())));
+            let elapsed = start.elapsed();
+            let events = events.lock().unwrap().clone();
+            (events, elapsed)
+        };
+        let (unthrottled, _) = events(json!({}));
+        assert_eq!(unthrottled, vec!["incomplete T1"; 4].into_iter().chain(["final none"]).collect::<Vec<_>>());
+        //The first improvement is passed on right away, the best of the others is held back until the end of the run
+        let (throttled, elapsed) = events(json!({"reportThrottleMs": 60000, "monitorIntervalMs": 200}));
+        assert_eq!(throttled, vec!["incomplete T1", "incomplete T1", "final none"]);
+        //The reports are only consumed after the first monitor interval
+        assert!(elapsed >= std::time::Duration::from_millis(200));
+    }
+}
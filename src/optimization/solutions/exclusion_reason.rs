@@ -0,0 +1,114 @@
+use crate::optimization::instance::Instance;
+use crate::optimization::solutions::sendable_solution::SendableSolution;
+use crate::optimization::solutions::solution::Solution;
+
+/// Reason why (some of the demand of) a parttype is not included in a solution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExclusionReason {
+    /// The part does not fit on any of the sheettypes
+    NoGeometricFit,
+    /// The part fits on some sheettypes, but all of them are out of stock
+    StockExhausted,
+    /// The part could be placed on an available sheettype, but was not included by the search
+    NotPlaced,
+    /// The part is placed, but in a cutting pattern below the minimum pattern utilization
+    UnderutilizedPattern,
+}
+
+/// Determines for every excluded parttype in the solution why it was excluded.
+/// Returns (parttype id, excluded quantity, reason) for every parttype of which the demand is not fully included,
+/// a parttype excluded for multiple reasons is listed once per reason.
+/// Units of splittable parttypes are included if they are produced as a whole or assembled from a complete set of pieces,
+/// the pieces themselves are not listed.
+/// Units placed in layouts below `min_pattern_utilization` are not included (see `Problem::cost`).
+pub fn determine_exclusion_reasons(solution: &SendableSolution, instance: &Instance, min_pattern_utilization: f64) -> Vec<(usize, usize, ExclusionReason)> {
+    //The quantities as if the parts of the underutilized layouts were not placed
+    let mut included_qtys = solution.parttype_qtys().clone();
+    solution.layouts().iter()
+        .filter(|layout| layout.utilization() < min_pattern_utilization)
+        .for_each(|layout| {
+            let mut ids = Vec::new();
+            layout.top_node().parttype_ids(&mut ids);
+            ids.into_iter().for_each(|id| included_qtys[id] += 1);
+        });
+
+    instance.parts().iter()
+        .filter(|(parttype, _)| parttype.original() == parttype.id())
+        .flat_map(|(parttype, _)| {
+            let parttype_id = parttype.id();
+            let produced = instance.produced_units(parttype_id, solution.parttype_qtys(), solution.parttype_demands());
+            let included = instance.produced_units(parttype_id, &included_qtys, solution.parttype_demands());
+            let excluded = instance.get_parttype_max_qty(parttype_id).saturating_sub(included)
+                .saturating_sub(instance.get_parttype_overproduction(parttype_id));
+            let underutilized = usize::min(produced - included, excluded);
+            [(parttype_id, underutilized, Some(ExclusionReason::UnderutilizedPattern)), (parttype_id, excluded - underutilized, None)]
+        })
+        .filter(|(_, qty, _)| *qty > 0)
+        .map(|(parttype_id, qty, reason)| {
+            let fitting_sheettypes = instance.sheets().iter()
+                .filter(|(sheettype, _)| instance.parttype_fits_sheet(parttype_id, sheettype))
+                .map(|(sheettype, _)| sheettype.id())
+                .collect::<Vec<usize>>();
+
+            let reason = reason.unwrap_or(match fitting_sheettypes.is_empty() {
+                true => ExclusionReason::NoGeometricFit,
+                false => match fitting_sheettypes.iter().all(|id| solution.sheettype_qtys()[*id] == 0) {
+                    true => ExclusionReason::StockExhausted,
+                    false => ExclusionReason::NotPlaced,
+                }
+            });
+            (parttype_id, qty, reason)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::optimization::solutions::exclusion_reason::{determine_exclusion_reasons, ExclusionReason};
+    use crate::optimization::solutions::solution::Solution;
+    use crate::util::test_util::solve;
+
+    fn small_part_instance() -> serde_json::Value {
+        json!({
+            "Name": "underutilized",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [{"Length": 10, "Height": 10, "Demand": 1, "Value": 1}]
+        })
+    }
+
+    #[test]
+    fn single_small_part_layout_is_rejected_below_the_minimum_utilization() {
+        let (_, instance, _, solution) = solve(small_part_instance(), json!({}));
+        assert!(solution.is_complete());
+        assert_eq!(solution.n_layouts(), 1);
+        assert_eq!(determine_exclusion_reasons(&solution, &instance, 0.0), vec![]);
+        //The same layout is below a minimum of 30%
+        assert_eq!(determine_exclusion_reasons(&solution, &instance, 0.3), vec![(0, 1, ExclusionReason::UnderutilizedPattern)]);
+
+        let (_, instance, config, solution) = solve(small_part_instance(), json!({"minPatternUtilization": 0.3}));
+        assert!(!solution.is_complete());
+        let reasons = determine_exclusion_reasons(&solution, &instance, config.min_pattern_utilization.unwrap());
+        assert_eq!(reasons.iter().map(|(id, qty, _)| (*id, *qty)).collect::<Vec<_>>(), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn every_excluded_part_gets_its_own_reason() {
+        let instance = json!({
+            "Name": "mixed",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 1, "Cost": 100}],
+            "Items": [
+                {"Length": 60, "Height": 60, "Demand": 2, "Value": 1},
+                {"Length": 120, "Height": 10, "Demand": 1, "Value": 1}
+            ]
+        });
+        let (_, instance, _, solution) = solve(instance, json!({}));
+        assert_eq!(solution.n_layouts(), 1);
+        //The only sheet holds one of the first parts, the second part is longer than any sheet
+        assert_eq!(determine_exclusion_reasons(&solution, &instance, 0.0), vec![
+            (0, 1, ExclusionReason::StockExhausted),
+            (1, 1, ExclusionReason::NoGeometricFit),
+        ]);
+    }
+}
@@ -0,0 +1,12 @@
+/// A leftover (unused node) of one of the layouts of a solution, together with its position on the sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leftover {
+    /// Index of the layout in the solution
+    pub pattern: usize,
+    pub sheettype_id: usize,
+    /// Position of the top left corner, relative to the top left corner of the sheet
+    pub x: u64,
+    pub y: u64,
+    pub width: u64,
+    pub height: u64,
+}
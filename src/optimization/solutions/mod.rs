@@ -1,4 +1,7 @@
 pub mod sendable_solution;
 pub mod problem_solution;
 pub mod solution;
-pub mod solution_stats;
\ No newline at end of file
+pub mod solution_stats;
+pub mod exclusion_reason;
+pub mod leftover;
+pub mod regression;
@@ -18,6 +18,7 @@ pub struct ProblemSolution<'a> {
     cost: Cost,
     id: usize,
     parttype_qtys: Vec<usize>,
+    parttype_demands: Vec<usize>,
     sheettype_qtys: Vec<usize>,
     usage: f64,
 }
@@ -42,6 +43,7 @@ impl<'a> ProblemSolution<'a> {
         }));
 
         let parttype_qtys = problem.parttype_qtys().clone();
+        let parttype_demands = problem.parttype_demands().clone();
         let sheettype_qtys = problem.sheettype_qtys().clone();
 
         let usage = problem.usage();
@@ -52,6 +54,7 @@ impl<'a> ProblemSolution<'a> {
             cost,
             id,
             parttype_qtys,
+            parttype_demands,
             sheettype_qtys,
             usage,
         }
@@ -65,6 +68,7 @@ impl<'a> ProblemSolution<'a> {
         }
 
         let parttype_qtys = problem.parttype_qtys().clone();
+        let parttype_demands = problem.parttype_demands().clone();
         let sheettype_qtys = problem.sheettype_qtys().clone();
 
         let usage = problem.usage();
@@ -75,6 +79,7 @@ impl<'a> ProblemSolution<'a> {
             cost,
             id,
             parttype_qtys,
+            parttype_demands,
             sheettype_qtys,
             usage,
         }
@@ -102,6 +107,9 @@ impl<'a> Solution for ProblemSolution<'a> {
     fn parttype_qtys(&self) -> &Vec<usize> {
         &self.parttype_qtys
     }
+    fn parttype_demands(&self) -> &Vec<usize> {
+        &self.parttype_demands
+    }
     fn sheettype_qtys(&self) -> &Vec<usize> {
         &self.sheettype_qtys
     }
@@ -0,0 +1,92 @@
+use std::fmt::{Display, Formatter};
+
+use crate::io::json_format::JsonSolution;
+use crate::optimization::solutions::sendable_solution::SendableSolution;
+use crate::optimization::solutions::solution::Solution;
+
+/// Outcome of comparing a solution against a baseline solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegressionResult {
+    /// The solution is at most the tolerated percentage worse than the baseline (negative deviation means it is better)
+    Passed { deviation_pct: f64 },
+    /// The solution includes a smaller fraction of the part area than the baseline
+    PartAreaRegression { current_pct: f64, baseline_pct: f64 },
+    /// The material cost of the solution exceeds the one of the baseline by more than the tolerated percentage
+    MaterialCostRegression { deviation_pct: f64 },
+}
+
+impl RegressionResult {
+    pub fn is_regression(&self) -> bool {
+        !matches!(self, RegressionResult::Passed { .. })
+    }
+}
+
+impl Display for RegressionResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegressionResult::Passed { deviation_pct } =>
+                write!(f, "passed (material cost deviation: {:+.3}%)", deviation_pct),
+            RegressionResult::PartAreaRegression { current_pct, baseline_pct } =>
+                write!(f, "part area included dropped from {:.3}% to {:.3}%", baseline_pct, current_pct),
+            RegressionResult::MaterialCostRegression { deviation_pct } =>
+                write!(f, "material cost increased by {:.3}%", deviation_pct),
+        }
+    }
+}
+
+/// Compares a solution with a baseline (e.g. the output of a previous run).
+/// A solution including less part area than the baseline is always a regression,
+/// otherwise the material cost is allowed to exceed the one of the baseline by at most `tolerance_pct` percent.
+pub fn regression_check(current: &SendableSolution, baseline: &JsonSolution, tolerance_pct: f64) -> RegressionResult {
+    let current_pct = current.cost().part_area_fraction_included() * 100.0;
+    let baseline_pct = baseline.statistics.part_area_included_pct as f64;
+
+    //The JSON stores the percentage as f32, compare with a precision matching it
+    if current_pct < baseline_pct - 1e-3 {
+        return RegressionResult::PartAreaRegression { current_pct, baseline_pct };
+    }
+
+    let current_material_cost = current.cost().material_cost as f64;
+    let baseline_material_cost = baseline.statistics.material_cost as f64;
+    let deviation_pct = match baseline_material_cost > 0.0 {
+        true => (current_material_cost - baseline_material_cost) / baseline_material_cost * 100.0,
+        false => match current_material_cost > 0.0 {
+            true => f64::INFINITY,
+            false => 0.0
+        }
+    };
+
+    match deviation_pct > tolerance_pct {
+        true => RegressionResult::MaterialCostRegression { deviation_pct },
+        false => RegressionResult::Passed { deviation_pct },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::io::parser::generate_json_solution;
+    use crate::optimization::solutions::regression::{regression_check, RegressionResult};
+    use crate::optimization::solutions::solution::Solution;
+    use crate::util::test_util::{config_path, solve};
+
+    #[test]
+    fn material_cost_deviation_is_compared_with_the_tolerance() {
+        let instance = json!({
+            "Name": "regression",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 2, "Value": 1}]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({}));
+        let mut baseline = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        assert_eq!(regression_check(&solution, &baseline, 0.0), RegressionResult::Passed { deviation_pct: 0.0 });
+
+        //A baseline for which the current solution is 5% worse
+        baseline.statistics.material_cost = solution.cost().material_cost * 20 / 21;
+        let within_one_pct = regression_check(&solution, &baseline, 1.0);
+        assert!(matches!(within_one_pct, RegressionResult::MaterialCostRegression { deviation_pct } if (deviation_pct - 5.0).abs() < 0.01));
+        assert!(within_one_pct.is_regression());
+        assert!(!regression_check(&solution, &baseline, 10.0).is_regression());
+    }
+}
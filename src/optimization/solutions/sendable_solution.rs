@@ -1,7 +1,11 @@
+use std::collections::HashSet;
 use std::sync::Arc;
+
 use crate::core::cost::Cost;
 use crate::core::entities::sendable_layout::SendableLayout;
+use crate::optimization::config::HashConfig;
 use crate::optimization::instance::Instance;
+use crate::optimization::solutions::leftover::Leftover;
 use crate::optimization::solutions::problem_solution::ProblemSolution;
 use crate::optimization::solutions::solution::Solution;
 
@@ -12,6 +16,7 @@ pub struct SendableSolution {
     instance: Arc<Instance>,
     layouts: Vec<SendableLayout>,
     parttype_qtys: Vec<usize>,
+    parttype_demands: Vec<usize>,
     sheettype_qtys: Vec<usize>,
     cost: Cost,
     usage: f64,
@@ -21,10 +26,12 @@ impl SendableSolution {
     pub fn new(instance: Arc<Instance>, problem_solution: &ProblemSolution) -> SendableSolution {
         debug_assert!(instance.as_ref() as *const _ == problem_solution.instance() as *const _);
 
-        let layouts = problem_solution.layouts().iter().map(|(_id, l)| SendableLayout::new(l)).collect();
+        let layouts: Vec<SendableLayout> = problem_solution.layouts().iter().map(|(_id, l)| SendableLayout::new(l)).collect();
         let cost = problem_solution.cost().clone();
         let usage = problem_solution.usage();
+        debug_assert!(layouts.is_empty() || (usage - weighted_utilization(&layouts, &instance, |l| l.utilization())).abs() < 1e-9);
         let parttype_qtys = problem_solution.parttype_qtys().clone();
+        let parttype_demands = problem_solution.parttype_demands().clone();
         let sheettype_qtys = problem_solution.sheettype_qtys().clone();
 
         Self {
@@ -33,6 +40,7 @@ impl SendableSolution {
             cost,
             usage,
             parttype_qtys,
+            parttype_demands,
             sheettype_qtys,
         }
     }
@@ -44,6 +52,40 @@ impl SendableSolution {
     pub fn instance(&self) -> &Arc<Instance> {
         &self.instance
     }
+
+    /// All leftovers of all layouts of the solution, with the layout and sheettype they belong to
+    pub fn leftovers(&self) -> impl Iterator<Item=Leftover> + '_ {
+        self.layouts.iter().enumerate().flat_map(|(pattern, layout)| {
+            let mut leftovers = Vec::new();
+            layout.top_node().leftovers(0, 0, self.instance.get_sheettype(layout.sheettype_id()), &mut leftovers);
+            leftovers.into_iter().map(move |(x, y, width, height)| Leftover {
+                pattern,
+                sheettype_id: layout.sheettype_id(),
+                x,
+                y,
+                width,
+                height,
+            })
+        })
+    }
+
+    /// Representation of the structure of the entire solution, independent of the order of its layouts
+    pub fn canonical_form(&self, hash_config: &HashConfig) -> String {
+        let mut layout_forms = self.layouts.iter()
+            .map(|layout| layout.canonical_form(hash_config))
+            .collect::<Vec<String>>();
+        layout_forms.sort();
+        layout_forms.join(";")
+    }
+
+    /// Number of physically different cutting patterns, layouts which are mirror images of each other are counted once.
+    /// What makes patterns different is defined by the `hash_config`.
+    pub fn n_distinct_patterns(&self, hash_config: &HashConfig) -> usize {
+        self.layouts.iter()
+            .map(|layout| layout.canonical_form(hash_config))
+            .collect::<HashSet<String>>()
+            .len()
+    }
 }
 
 
@@ -57,10 +99,78 @@ impl Solution for SendableSolution {
     fn parttype_qtys(&self) -> &Vec<usize> {
         &self.parttype_qtys
     }
+    fn parttype_demands(&self) -> &Vec<usize> {
+        &self.parttype_demands
+    }
     fn sheettype_qtys(&self) -> &Vec<usize> {
         &self.sheettype_qtys
     }
     fn usage(&self) -> f64 {
         self.usage
     }
-}
\ No newline at end of file
+}
+
+/// Area-weighted mean of a per layout utilization measure over the layouts of a solution
+pub fn weighted_utilization(layouts: &[SendableLayout], instance: &Instance, utilization: impl Fn(&SendableLayout) -> f64) -> f64 {
+    let sheet_area = |l: &SendableLayout| instance.get_sheettype(l.sheettype_id()).area();
+    let used_area = layouts.iter().map(|l| utilization(l) * sheet_area(l) as f64).sum::<f64>();
+    used_area / layouts.iter().map(sheet_area).sum::<u64>() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::core::entities::layout::Layout;
+    use crate::core::insertion::node_blueprint::NodeBlueprint;
+    use crate::core::leftover_valuator;
+    use crate::core::rotation::Rotation;
+    use crate::optimization::problem::Problem;
+    use crate::optimization::solutions::sendable_solution::SendableSolution;
+    use crate::Orientation;
+    use crate::util::test_util::parse;
+
+    #[test]
+    fn mirror_images_are_counted_as_one_pattern() {
+        let (_, instance, config) = parse(json!({
+            "Name": "mirrored",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 3, "Cost": 100}],
+            "Items": [{"Length": 30, "Height": 100, "Demand": 3, "Value": 1}, {"Length": 35, "Height": 60, "Demand": 6, "Value": 1}]
+        }), json!({}));
+        leftover_valuator::set_power(config.leftover_valuation_power);
+        let part = |parttype_id: usize, next_cut_orient: Orientation| {
+            let parttype = instance.get_parttype(parttype_id);
+            NodeBlueprint::new(parttype.width(), parttype.height(), Some((parttype, Rotation::Default)), next_cut_orient)
+        };
+        //A strip with a part on top of a leftover, or below it
+        let strip = |part_on_top: bool| {
+            let mut strip = NodeBlueprint::new(35, 100, None, Orientation::Horizontal);
+            let leftover = NodeBlueprint::new(35, 40, None, Orientation::Vertical);
+            match part_on_top {
+                true => [part(1, Orientation::Vertical), leftover],
+                false => [leftover, part(1, Orientation::Vertical)],
+            }.into_iter().for_each(|child| strip.add_child(child));
+            strip
+        };
+        let layout = |children: Vec<NodeBlueprint>| {
+            let mut top_node = NodeBlueprint::new(100, 100, None, Orientation::Vertical);
+            children.into_iter().for_each(|child| top_node.add_child(child));
+            top_node
+        };
+        let original = layout(vec![part(0, Orientation::Horizontal), strip(true), strip(true)]);
+        let mirror_image = layout(vec![strip(false), strip(false), part(0, Orientation::Horizontal)]);
+        //Only the order of the nodes within a strip differs from the original, which does not change the exported pattern
+        let reordered = layout(vec![part(0, Orientation::Horizontal), strip(false), strip(true)]);
+
+        let mut problem = Problem::new(&instance, &config);
+        for (id, top_node) in [original, mirror_image, reordered].iter().enumerate() {
+            problem.register_layout(Layout::from_top_node_blueprint(id, instance.get_sheettype(0), top_node, &instance));
+        }
+        let solution = SendableSolution::new(instance.clone(), &problem.create_solution(&None, None));
+
+        let forms = solution.layouts().iter().map(|l| l.canonical_form(&config.pattern_hash)).collect::<Vec<_>>();
+        assert_eq!(forms[0], forms[1]);
+        assert_eq!(forms[0], forms[2]);
+        assert_eq!(solution.n_distinct_patterns(&config.pattern_hash), 1);
+    }
+}
@@ -7,6 +7,9 @@ pub trait Solution {
 
     fn parttype_qtys(&self) -> &Vec<usize>;
 
+    /// See `Problem::parttype_demands`
+    fn parttype_demands(&self) -> &Vec<usize>;
+
     fn sheettype_qtys(&self) -> &Vec<usize>;
 
     fn is_complete(&self) -> bool {
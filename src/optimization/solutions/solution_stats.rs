@@ -4,10 +4,23 @@ pub struct SolutionStats {
     pub cost: Cost,
     pub usage: f64,
     pub n_sheets: usize,
+    /// Lower bound on the number of sheets of the instance
+    pub lower_bound: usize,
+    /// Percentage by which the number of sheets exceeds the lower bound
+    pub gap_pct: f64,
 }
 
 impl SolutionStats {
-    pub fn new(cost: Cost, usage: f64, n_sheets: usize) -> Self {
-        Self { cost, usage, n_sheets }
+    pub fn new(cost: Cost, usage: f64, n_sheets: usize, lower_bound: usize) -> Self {
+        let gap_pct = gap_pct(n_sheets, lower_bound);
+        Self { cost, usage, n_sheets, lower_bound, gap_pct }
     }
-}
\ No newline at end of file
+}
+
+/// Optimality gap of a number of sheets with respect to a lower bound, in percent
+pub fn gap_pct(n_sheets: usize, lower_bound: usize) -> f64 {
+    match lower_bound {
+        0 => 0.0,
+        lower_bound => (n_sheets as f64 - lower_bound as f64) / lower_bound as f64 * 100.0
+    }
+}
@@ -0,0 +1,24 @@
+//! Everything needed to run the optimization from another crate:
+//! parse a [`JsonInstance`] into an [`Instance`], [`optimize`] it and convert the best solution into a [`JsonSolution`],
+//! which can then be written in the output formats of the binary (JSON, HTML, SVG, CSV and the summaries).
+//! The binary itself is built on this prelude only.
+
+pub use crate::io::csv_export::generate_solution_csv;
+pub use crate::io::html_export::generate_solution as generate_html_solution;
+pub use crate::io::json_format::{JsonInstance, JsonSolution};
+pub use crate::io::output::{limit_json_size, open_sink, write_json_solution};
+pub use crate::io::parser::{generate_config, generate_instance, generate_json_solution};
+#[cfg(feature = "repl")]
+pub use crate::io::repl::run_repl;
+pub use crate::io::svg_export::generate_solution_svg;
+pub use crate::optimization::config::{Config, SolutionOutput};
+pub use crate::optimization::instance::Instance;
+pub use crate::optimization::optimize::{optimize, run_optimization, SolveError};
+pub use crate::optimization::sol_collectors::global_sol_collector::GlobalSolCollector;
+pub use crate::optimization::sol_collectors::reporter::{ConsoleReporter, IncrementalOutputReporter, Reporter};
+pub use crate::optimization::solutions::leftover::Leftover;
+pub use crate::optimization::solutions::regression::{regression_check, RegressionResult};
+pub use crate::optimization::solutions::sendable_solution::SendableSolution;
+pub use crate::optimization::solutions::solution::Solution;
+pub use crate::timed_println;
+pub use crate::util::util::{markdown_summary, reconciliation_report};
@@ -9,7 +9,6 @@ use crate::{Orientation, PartType, SheetType};
 use crate::core::entities::layout::Layout;
 use crate::core::entities::node::Node;
 use crate::core::insertion::node_blueprint::NodeBlueprint;
-use crate::core::layout_index::LayoutIndex;
 use crate::optimization::problem::Problem;
 use crate::optimization::rr::insertion_option_cache::InsertionOptionCache;
 use crate::optimization::solutions::problem_solution::ProblemSolution;
@@ -107,6 +106,36 @@ pub fn problem_matches_solution(problem: &Problem, solution: &ProblemSolution) -
     return true;
 }
 
+/// The remaining quantities of the problem together with the parts and sheets in its layouts add up to the quantities of the instance,
+/// i.e. no layout or part is half registered
+pub fn problem_quantities_consistent(problem: &Problem) -> bool {
+    let instance = problem.instance();
+    let mut placed_parts = vec![0; instance.parts().len()];
+    let mut used_sheets = vec![0; instance.sheets().len()];
+    for (_, layout) in problem.layouts().iter() {
+        for parttype_id in layout.get_included_parts() {
+            placed_parts[parttype_id] += 1;
+        }
+        used_sheets[layout.sheettype().id()] += 1;
+    }
+    let parts_consistent = instance.parts().iter()
+        .all(|(parttype, _)| problem.parttype_qtys()[parttype.id()] + placed_parts[parttype.id()] == problem.parttype_demands()[parttype.id()]);
+    //Units are only split into pieces as far as required, and together with the remaining whole units they add up to the quantity of the instance
+    let pieces_consistent = instance.piece_sets().iter()
+        .all(|piece_set| piece_set.pieces().any(|(piece_id, per_unit)| problem.parttype_qtys()[piece_id] < per_unit));
+    let units_consistent = instance.parts().iter()
+        .filter(|(parttype, _)| parttype.n_pieces() == 1)
+        .all(|(parttype, _)| {
+            let split_units = instance.piece_sets_of(parttype.id())
+                .map(|piece_set| problem.parttype_demands()[piece_set.regular] / piece_set.pieces().next().unwrap().1)
+                .sum::<usize>();
+            problem.parttype_demands()[parttype.id()] + split_units == instance.get_parttype_max_qty(parttype.id())
+        });
+    let sheets_consistent = instance.sheets().iter()
+        .all(|(sheettype, qty)| problem.sheettype_qtys()[sheettype.id()] + used_sheets[sheettype.id()] == *qty);
+    parts_consistent && pieces_consistent && units_consistent && sheets_consistent
+}
+
 pub fn layouts_match(l1: &Layout, l2: &Layout) -> bool {
     if l1.sheettype() != l2.sheettype() {
         return false;
@@ -135,12 +164,7 @@ pub fn nodes_match(n_i_1: &Index, n_i_2: &Index, nodes_1 : &Arena<Node>, nodes_2
 
 pub fn insertion_option_cache_is_valid<'a>(problem: &Problem<'a>, ioc: &InsertionOptionCache<'a>, parttypes: &Vec<&'a PartType>) -> bool {
     //Collect all the layouts which should be considered during this recreate iteration
-    let layouts_to_consider = problem.layouts().iter().map(|(i, l)| (LayoutIndex::Existing(i), l))
-        .chain(problem.empty_layouts().iter().enumerate()
-            .filter(|(_, l)| problem.sheettype_qtys()[l.sheettype().id()] > 0)
-            .map(|(i, l)| (LayoutIndex::Empty(i), l))
-        )
-        .collect_vec();
+    let layouts_to_consider = problem.layouts_to_consider();
 
     let mut fresh_ioc = InsertionOptionCache::new();
 
@@ -153,10 +177,10 @@ pub fn insertion_option_cache_is_valid<'a>(problem: &Problem<'a>, ioc: &Insertio
         return true;
     }
 
-    for (i, q) in problem.parttype_qtys().iter().enumerate() {
+    for i in 0..problem.parttype_qtys().len() {
         let parttype = problem.instance().get_parttype(i);
-        match (q, parttypes.contains(&parttype)) {
-            (0, true) => {
+        match (problem.parttype_insertable(i), parttypes.contains(&parttype)) {
+            (false, true) => {
                 return false;
             }
             (_, true) => {
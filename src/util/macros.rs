@@ -1,25 +1,31 @@
+#[macro_export]
 macro_rules! timed_println {
     ($($arg:tt)*)=>{
-        let duration = crate::EPOCH.elapsed();
-        let seconds = duration.as_secs() % 60;
-        let minutes = (duration.as_secs() / 60) % 60;
-        let hours = (duration.as_secs() / 60) / 60;
-        print!("[{:0>2}:{:0>2}:{:0>2}]\t", hours, minutes, seconds);
-        println!($($arg)*);
+        if !$crate::QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+            let duration = $crate::EPOCH.elapsed();
+            let seconds = duration.as_secs() % 60;
+            let minutes = (duration.as_secs() / 60) % 60;
+            let hours = (duration.as_secs() / 60) / 60;
+            print!("[{:0>2}:{:0>2}:{:0>2}]\t", hours, minutes, seconds);
+            println!($($arg)*);
+        }
     };
 }
 
+#[macro_export]
 macro_rules! timed_thread_println {
     ($($arg:tt)*)=>{
-        let duration = crate::EPOCH.elapsed();
-        let seconds = duration.as_secs() % 60;
-        let minutes = (duration.as_secs() / 60) % 60;
-        let hours = (duration.as_secs() / 60) / 60;
-        let handle = std::thread::current();
-        print!("[{:0>2}:{:0>2}:{:0>2}]\t<{}>\t", hours, minutes, seconds, handle.name().unwrap_or("<>"));
-        println!($($arg)*);
+        if !$crate::QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+            let duration = $crate::EPOCH.elapsed();
+            let seconds = duration.as_secs() % 60;
+            let minutes = (duration.as_secs() / 60) % 60;
+            let hours = (duration.as_secs() / 60) / 60;
+            let handle = std::thread::current();
+            print!("[{:0>2}:{:0>2}:{:0>2}]\t<{}>\t", hours, minutes, seconds, handle.name().unwrap_or("<>"));
+            println!($($arg)*);
+        }
     };
 }
 
-pub(crate) use timed_println;
-pub(crate) use timed_thread_println;
\ No newline at end of file
+pub use crate::timed_println;
+pub use crate::timed_thread_println;
\ No newline at end of file
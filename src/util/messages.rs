@@ -1,3 +1,4 @@
+use crate::core::cost::Cost;
 use crate::optimization::solutions::sendable_solution::SendableSolution;
 use crate::optimization::solutions::solution_stats::SolutionStats;
 
@@ -5,6 +6,8 @@ use crate::optimization::solutions::solution_stats::SolutionStats;
 
 pub enum SyncMessage {
     SyncMatLimit(u64),
+    /// New best complete solution of all threads, only sent if workers restart from it (`worker_restart_from_incumbent`)
+    SyncIncumbent(SendableSolution),
     Terminate,
 }
 
@@ -12,5 +15,19 @@ pub enum SolutionReportMessage {
     NewCompleteSolution(String, SendableSolution),
     NewIncompleteStats(String, SolutionStats),
     NewIncompleteSolution(String, SendableSolution),
+    WorkerStatus(String, WorkerStatus),
+}
+
+/// Lightweight progress report of a single GDRR thread, does not affect the global best solutions
+#[derive(Debug, Clone)]
+pub struct WorkerStatus {
+    pub material_limit: u64,
+    /// Cost of the current local optimum of the worker, if any
+    pub best_cost: Option<Cost>,
+    pub n_iterations: usize,
+    /// Number of times the worker restarted after stagnating (see `worker_restart_stagnation_ms`)
+    pub n_restarts: usize,
+    /// Iterations since the last restart, equal to `n_iterations` if the worker never restarted
+    pub n_iterations_since_restart: usize,
 }
 
@@ -4,4 +4,6 @@ pub mod biased_sampler;
 pub mod multi_map;
 pub mod blink;
 pub mod macros;
-pub mod messages;
\ No newline at end of file
+pub mod messages;
+#[cfg(test)]
+pub mod test_util;
@@ -0,0 +1,57 @@
+//! Helpers to parse and solve small instances in unit tests
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::io::json_format::JsonInstance;
+use crate::io::parser::{generate_config, generate_instance};
+use crate::optimization::config::Config;
+use crate::optimization::instance::Instance;
+use crate::optimization::optimize::optimize;
+use crate::optimization::sol_collectors::reporter::ConsoleReporter;
+use crate::optimization::solutions::sendable_solution::SendableSolution;
+
+/// Config of a short, deterministic run on a single thread, fields of `overrides` take precedence
+pub fn test_config(overrides: Value) -> Value {
+    let mut config = serde_json::json!({
+        "maxRRIterations": 200,
+        "nThreads": 1,
+        "rotationAllowed": true,
+        "avgNodesRemoved": 6,
+        "blinkRate": 0.01,
+        "leftoverValuationPower": 2,
+        "historyLength": 100,
+        "sheetValuationMode": "area",
+        "seed": 0,
+        "quiet": true
+    });
+    if let (Value::Object(config), Value::Object(overrides)) = (&mut config, overrides) {
+        config.extend(overrides);
+    }
+    config
+}
+
+/// Parses an instance in the JSON input format together with a config (see `test_config`)
+pub fn parse(instance: Value, config: Value) -> (JsonInstance, Arc<Instance>, Arc<Config>) {
+    let mut json_instance: JsonInstance = serde_json::from_value(instance).unwrap();
+    let config = generate_config(&json_instance, Some(test_config(config))).unwrap();
+    let instance = generate_instance(&mut json_instance, &config).unwrap();
+    (json_instance, Arc::new(instance), Arc::new(config))
+}
+
+/// Parses and solves an instance, returning the best solution found
+pub fn solve(instance: Value, config: Value) -> (JsonInstance, Arc<Instance>, Arc<Config>, SendableSolution) {
+    let (json_instance, instance, config) = parse(instance, config);
+    let solution = optimize(instance.clone(), config.clone(), Box::new(ConsoleReporter))
+        .ok()
+        .flatten()
+        .expect("no solution found");
+    (json_instance, instance, config, solution)
+}
+
+/// Path passed as config path when generating JSON solutions in tests
+pub fn config_path() -> PathBuf {
+    PathBuf::from("test_config.json")
+}
@@ -1,20 +1,148 @@
+use std::time::Duration;
+
+use crate::DETERMINISTIC_MODE;
+use crate::io::json_format::{JsonItemReconciliation, JsonObjectReconciliation, JsonReconciliationReport, JsonSolution};
+use crate::io::parser;
+use crate::optimization::config::Config;
 use crate::optimization::solutions::solution::Solution;
 use crate::optimization::solutions::solution_stats::SolutionStats;
 
 pub fn solution_stats_string(solution: &dyn Solution) -> String {
     format!(
-        "(usage: {:.3}%, p_incl: {:.3}%, sheets: {}, mat: {})",
+        "(usage: {:.3}%, sheets: {}, {})",
         solution.usage() * 100.0,
-        solution.cost().part_area_fraction_included() * 100.0,
         solution.n_layouts(),
-        solution.cost().material_cost)
+        solution.cost())
 }
 
 pub fn compact_stats_string(stats: &SolutionStats) -> String {
     format!(
-        "(usage: {:.3}%, p_incl: {:.3}%, sheets: {}, mat: {})",
+        "(usage: {:.3}%, sheets: {}, {})",
         stats.usage * 100.0,
-        stats.cost.part_area_fraction_included() * 100.0,
         stats.n_sheets,
-        stats.cost.material_cost)
-}
\ No newline at end of file
+        stats.cost)
+}
+
+/// Markdown table summarizing an optimization run, e.g. to share results in an issue
+pub fn markdown_summary(json_solution: &JsonSolution, time_to_best: Option<Duration>, config: &Config) -> String {
+    let stats = &json_solution.statistics;
+    let time_to_best = match time_to_best {
+        Some(time_to_best) => format!("{:.3}s", time_to_best.as_secs_f64()),
+        None => "-".to_string()
+    };
+    let run_time = match stats.run_time_ms {
+        Some(run_time_ms) => format!("{:.3}s", run_time_ms as f64 / 1000.0),
+        None => "-".to_string()
+    };
+    let seed = match (config.seed, DETERMINISTIC_MODE) {
+        (Some(seed), _) => seed.to_string(),
+        (None, true) => "0 (deterministic)".to_string(),
+        (None, false) => "random".to_string()
+    };
+    let config = serde_json::to_string(config).expect("could not serialize config");
+
+    let mut summary = String::new();
+    summary.push_str("| Instance | Material cost | Usage | Part area included | Layouts | Run time | Time to best | Seed |\n");
+    summary.push_str("|---|---|---|---|---|---|---|---|\n");
+    summary.push_str(&format!("| {} | {} | {:.3}% | {:.3}% | {} | {} | {} | {} |\n",
+                              json_solution.name,
+                              stats.material_cost,
+                              stats.usage_pct,
+                              stats.part_area_included_pct,
+                              json_solution.cutting_patterns.len(),
+                              run_time,
+                              time_to_best,
+                              seed));
+    summary.push_str(&format!("\nConfig: `{}`\n", config));
+    summary
+}
+
+/// Per item: demanded, produced, shortfall and overproduction. Per object: available and consumed.
+/// Aggregated from a JSON solution, so the quantities refer to the items and objects of the input.
+pub fn reconciliation_report(json_solution: &JsonSolution, config: &Config) -> JsonReconciliationReport {
+    let items = json_solution.statistics.placed_per_part.iter()
+        .map(|placed_part| JsonItemReconciliation {
+            item: placed_part.item,
+            demanded: placed_part.demand,
+            produced: placed_part.placed,
+            shortfall: placed_part.demand.saturating_sub(placed_part.placed),
+            overproduced: placed_part.overproduced,
+        })
+        .collect();
+
+    let book_height = config.book_height.unwrap_or(1).max(1);
+    let objects = json_solution.sheettypes.iter().enumerate()
+        .map(|(object, json_sheet)| {
+            let available = match parser::available_stock(json_sheet, config, book_height) {
+                usize::MAX => None,
+                n_books => Some(n_books * book_height)
+            };
+            let consumed = json_solution.cutting_patterns.iter()
+                .filter(|json_cp| json_cp.object == object)
+                .map(|json_cp| json_cp.book_height.unwrap_or(1))
+                .sum();
+            JsonObjectReconciliation { object, available, consumed }
+        })
+        .collect();
+
+    JsonReconciliationReport {
+        name: json_solution.name.clone(),
+        items,
+        objects,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::io::parser::generate_json_solution;
+    use crate::util::test_util::{config_path, solve};
+    use crate::util::util::{markdown_summary, reconciliation_report};
+
+    #[test]
+    fn markdown_summary_contains_the_header_row_and_the_best_cost() {
+        let instance = json!({
+            "Name": "markdown",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 5, "Cost": 100}],
+            "Items": [{"Length": 60, "Height": 60, "Demand": 2, "Value": 1}]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let summary = markdown_summary(&json_solution, Some(Duration::from_millis(1500)), &config);
+
+        let mut lines = summary.lines();
+        assert_eq!(lines.next(), Some("| Instance | Material cost | Usage | Part area included | Layouts | Run time | Time to best | Seed |"));
+        assert_eq!(lines.next(), Some("|---|---|---|---|---|---|---|---|"));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with(&format!("| markdown | {} |", json_solution.statistics.material_cost)));
+        assert!(row.contains("| 1.500s |"));
+    }
+
+    #[test]
+    fn reconciliation_reports_shortfalls_and_consumed_sheets() {
+        //Only two of the three large items fit, each one on a sheet of its own. The small items fit next to them.
+        let instance = json!({
+            "Name": "reconciliation",
+            "Objects": [{"Length": 100, "Height": 100, "Stock": 2, "Cost": 100}],
+            "Items": [
+                {"Length": 60, "Height": 60, "Demand": 3, "Value": 1},
+                {"Length": 30, "Height": 30, "Demand": 2, "Value": 1}
+            ]
+        });
+        let (json_instance, _, config, solution) = solve(instance, json!({}));
+        let json_solution = generate_json_solution(&json_instance, &solution, &config, &config_path());
+        let report = reconciliation_report(&json_solution, &config);
+
+        let items = report.items.iter()
+            .map(|item| (item.item, item.demanded, item.produced, item.shortfall))
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![(0, 3, 2, 1), (1, 2, 2, 0)]);
+        let objects = report.objects.iter()
+            .map(|object| (object.object, object.available, object.consumed))
+            .collect::<Vec<_>>();
+        assert_eq!(objects, vec![(0, Some(2), 2)]);
+    }
+}
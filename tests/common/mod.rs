@@ -0,0 +1,23 @@
+//! Helpers shared by the integration tests, which cannot use the crate's own (cfg(test)) test utilities
+
+use serde_json::Value;
+
+/// Config of a short, deterministic run on a single thread, fields of `overrides` take precedence
+pub fn test_config(overrides: Value) -> Value {
+    let mut config = serde_json::json!({
+        "maxRRIterations": 200,
+        "nThreads": 1,
+        "rotationAllowed": true,
+        "avgNodesRemoved": 6,
+        "blinkRate": 0.01,
+        "leftoverValuationPower": 2,
+        "historyLength": 100,
+        "sheetValuationMode": "area",
+        "seed": 0,
+        "quiet": true
+    });
+    if let (Value::Object(config), Value::Object(overrides)) = (&mut config, overrides) {
+        config.extend(overrides);
+    }
+    config
+}
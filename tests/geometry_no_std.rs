@@ -0,0 +1,22 @@
+//! Compiles the geometry module in a `no_std` crate with only `alloc` available, as it would be reused in a constrained environment
+
+#![no_std]
+
+extern crate alloc;
+
+#[path = "../src/core/geometry.rs"]
+#[allow(dead_code)]
+mod geometry;
+
+#[test]
+fn fit_checks_without_std() {
+    assert_eq!(geometry::area(30, 20), 600);
+    assert!(geometry::fits(30, 20, 30, 20));
+    assert!(!geometry::fits(30, 20, 20, 30));
+    assert!(geometry::fits_rotatable(30, 20, 20, 30));
+    assert!(!geometry::fits_rotatable(30, 20, 25, 25));
+
+    let mut fits = alloc::vec::Vec::new();
+    geometry::fits_batch(30, 20, &[30, 20, 40], &[20, 30, 10], &mut fits);
+    assert_eq!(fits, [true, false, false]);
+}
@@ -0,0 +1,36 @@
+//! Solves a small instance through the prelude only, as a dependent crate would
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use serde_json::json;
+
+use gdrr_2bp::prelude::*;
+
+mod common;
+
+#[test]
+fn solve_through_prelude() {
+    let mut json_instance: JsonInstance = serde_json::from_value(json!({
+        "Name": "prelude",
+        "Objects": [{"Length": 100, "Height": 100, "Stock": 2, "Cost": 1}],
+        "Items": [
+            {"Length": 50, "Height": 100, "Demand": 1, "Value": 1},
+            {"Length": 50, "Height": 40, "Demand": 2, "Value": 1}
+        ]
+    })).unwrap();
+    let config = generate_config(&json_instance, Some(common::test_config(json!({})))).unwrap();
+    let instance = generate_instance(&mut json_instance, &config).unwrap();
+
+    let solution = optimize(Arc::new(instance), Arc::new(config.clone()), Box::new(ConsoleReporter))
+        .unwrap()
+        .expect("no solution found");
+    //The quiet option is applied by the library, not only by the binary
+    assert!(gdrr_2bp::QUIET.load(Ordering::Relaxed));
+    let json_solution = generate_json_solution(&json_instance, &solution, &config, &PathBuf::from("config.json"));
+
+    assert!(json_solution.statistics.excluded_parts.is_empty());
+    assert_eq!(json_solution.statistics.n_objects_used, 1);
+    assert!(json_solution.statistics.placed_per_part.iter().all(|p| p.placed == p.demand));
+}
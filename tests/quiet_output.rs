@@ -0,0 +1,27 @@
+//! Runs the binary with the `quiet` option, as a script parsing its output would
+
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn quiet_run_prints_nothing_but_writes_the_solution() {
+    let dir = std::env::temp_dir().join(format!("gdrr_2bp_quiet_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config_path = dir.join("config.json");
+    let solution_path = dir.join("solution.json");
+    std::fs::write(&config_path, common::test_config(serde_json::json!({})).to_string()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gdrr_2bp"))
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/small_example_input.json"))
+        .arg(&config_path)
+        .arg(&solution_path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    let solution: serde_json::Value = serde_json::from_reader(std::fs::File::open(&solution_path).unwrap()).unwrap();
+    assert!(!solution["CuttingPatterns"].as_array().unwrap().is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
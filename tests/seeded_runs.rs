@@ -0,0 +1,36 @@
+//! Solves the same instance twice with the same seed, as a user reproducing a run would
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::json;
+
+use gdrr_2bp::prelude::*;
+
+mod common;
+
+fn seeded_run() -> String {
+    let mut json_instance: JsonInstance = serde_json::from_value(json!({
+        "Name": "seeded",
+        "Objects": [{"Length": 100, "Height": 100, "Stock": 10, "Cost": 100}],
+        "Items": [
+            {"Length": 35, "Height": 20, "Demand": 12, "Value": 7},
+            {"Length": 15, "Height": 40, "Demand": 9, "Value": 6},
+            {"Length": 60, "Height": 25, "Demand": 5, "Value": 15}
+        ]
+    })).unwrap();
+    let config = generate_config(&json_instance, Some(common::test_config(json!({"seed": 42})))).unwrap();
+    let instance = generate_instance(&mut json_instance, &config).unwrap();
+
+    let solution = optimize(Arc::new(instance), Arc::new(config.clone()), Box::new(ConsoleReporter))
+        .unwrap()
+        .expect("no solution found");
+    let json_solution = generate_json_solution(&json_instance, &solution, &config, &PathBuf::from("config.json"));
+    assert_eq!(json_solution.statistics.run_time_ms, None);
+    serde_json::to_string(&json_solution).unwrap()
+}
+
+#[test]
+fn runs_with_the_same_seed_produce_identical_solutions() {
+    assert_eq!(seeded_run(), seeded_run());
+}